@@ -1,30 +1,6 @@
-const MAX_JOINTS = 5;
-
-@group(0) @binding(0) var<uniform> mvp: MVP;
-@group(0) @binding(1) var txSampler: sampler;
-@group(0) @binding(2) var texture1: texture_2d<f32>;
-@group(0) @binding(3) var texture2: texture_2d<f32>;
-@group(0) @binding(4) var<uniform> joint_transforms: array<mat4x4<f32>, MAX_JOINTS>;
-
-struct MVP {
-  model: mat4x4<f32>,
-  view: mat4x4<f32>,
-  proj: mat4x4<f32>,
-}
-
-struct VertIn {
-  @location(0) pos: vec3f,
-  @location(1) uv: vec2f,
-  @location(2) normal: vec3f,
-  @location(3) joints: vec4u,
-  @location(4) weights: vec4f,
-}
-
-struct VertOut {
-  @builtin(position) pos: vec4f,
-  @location(0) uv: vec2f,
-  @location(1) normal: vec3f,
-}
+// HAS_SKINNING + MAX_JOINTS are defined by animated vertex pipelines
+#include "mvp.wgsl"
+#include "vertex.wgsl"
 
 @vertex
 fn vertexMain(input: VertIn) -> VertOut {
@@ -1,16 +1,6 @@
-@group(0) @binding(0) var<uniform> mvp: MVP;
-
-struct MVP {
-  model: mat4x4<f32>,
-  view: mat4x4<f32>,
-  proj: mat4x4<f32>,
-}
-
-struct VertIn {
-  @location(0) pos: vec3f,
-  @location(1) uv: vec2f,
-  @location(2) normal: vec3f,
-}
+#define CUSTOM_VERT_OUT
+#include "mvp.wgsl"
+#include "vertex.wgsl"
 
 struct VertOut {
   @builtin(position) pos: vec4f,
@@ -1,8 +1,9 @@
-use std::{fs, time, path::Path};
+use std::{time, path::Path};
 use rand::{thread_rng, Rng};
 
-use crate::wgpu_renderer::{ModelLoader, Primitives, RCamera, RObjectUpdate, RPipelineId, RPipelineSetup, RTextureId, RUniformSetup, Renderer, Shape};
+use crate::wgpu_renderer::{ModelLoader, Primitives, RCamera, RGraphPass, RObjectUpdate, RPassTarget, RPipelineId, RPipelineSetup, RRenderGraph, RTextureId, RUniformSetup, Renderer, Shape};
 use crate::input_mapper::InputHandler;
+use crate::camera_controller::CameraController;
 
 pub struct AppEventLoop<'a> {
   renderer: Renderer<'a>,
@@ -12,6 +13,8 @@ pub struct AppEventLoop<'a> {
   textures: Vec<RTextureId>,
   shapes: Vec<Shape>,
   camera: RCamera,
+  camera_ctrl: CameraController,
+  last_update: time::Instant,
   screen_center: (f32, f32),
 }
 
@@ -19,6 +22,8 @@ impl<'a> AppEventLoop<'a> {
   pub fn new(wgpu: Renderer<'a>, window_size: &(f32, f32)) -> Self {
     let mut cam = RCamera::new_persp(60.0, 1.0, 1000.0);
     cam.position = [0.0, 0.0, 200.0];
+    let mut camera_ctrl = CameraController::from_camera(CameraController::ORBIT, &cam);
+    camera_ctrl.smoothing = 0.08;
     let input_handler = InputHandler::new();
 
     Self{
@@ -27,6 +32,8 @@ impl<'a> AppEventLoop<'a> {
       shapes: vec![],
       render_frame: 0,
       camera: cam,
+      camera_ctrl,
+      last_update: time::Instant::now(),
       screen_center: (window_size.0 / 2.0, window_size.1 / 2.0),
       pipes: Vec::new(),
       textures: Vec::new(),
@@ -38,12 +45,7 @@ impl<'a> AppEventLoop<'a> {
     // initialize pipeline for objects
     let texture1 = self.renderer.add_texture(1200, 1200, Some(Path::new("assets/test_uv_map.png")), false);
     let texture4 = self.renderer.add_texture(800, 800, None, false);
-    let texture2 = self.renderer.add_texture(
-      (self.screen_center.0 * 2.0) as u32,
-      (self.screen_center.1 * 2.0) as u32,
-      None,
-      true
-    );
+    let texture2 = self.renderer.add_transient_texture(1.0, true);
     let pipe1 = self.renderer.add_pipeline(RPipelineSetup {
       texture1_id: Some(texture1),
       texture2_id: Some(texture4),
@@ -52,31 +54,18 @@ impl<'a> AppEventLoop<'a> {
       ..Default::default()
     });
     // pipeline for miniview
-    let pipe2 = match fs::read_to_string("assets/miniview.wgsl") {
-      Ok(str) => { 
-        self.renderer.add_pipeline(RPipelineSetup {
-          shader: &str,
-          max_obj_count: 1,
-          texture1_id: Some(texture2),
-          uniforms: vec![
-            RUniformSetup {
-              bind_slot: 0,
-              visibility: RUniformSetup::VISIBILITY_FRAGMENT,
-              size_in_bytes: 8
-            }
-          ],
-          ..Default::default()
-        })
-      }
-      Err(..) => {
-        println!("Err: Could not find shader");
-        self.renderer.add_pipeline(RPipelineSetup {
-          max_obj_count: 1, 
-          texture1_id: Some(texture2),
-          ..Default::default()
-        })
-      }
-    };
+    let pipe2 = self.renderer.add_pipeline_from_file(RPipelineSetup {
+      max_obj_count: 1,
+      texture1_id: Some(texture2),
+      uniforms: vec![
+        RUniformSetup {
+          bind_slot: 0,
+          visibility: RUniformSetup::VISIBILITY_FRAGMENT,
+          size_in_bytes: 8
+        }
+      ],
+      ..Default::default()
+    }, Path::new("assets/miniview.wgsl"));
     // initialize text pipeline
     self.renderer.load_font("assets/retro_computer.ttf");
     let (texture3, pipe3) = self.renderer.add_overlay_pipeline();
@@ -91,6 +80,7 @@ impl<'a> AppEventLoop<'a> {
 
     // initialize objects
     let (cube_data, cube_idx) = Primitives::hemisphere(20.0, 32, 16);
+    let cube_mesh = self.renderer.add_mesh(&cube_data, &cube_idx);
     for x in 0..10 {
       for y in 0..10 {
         for z in 0..5 {
@@ -98,7 +88,7 @@ impl<'a> AppEventLoop<'a> {
           let ry: f32 = thread_rng().gen_range(-1.0..1.0);
           let rz: f32 = thread_rng().gen_range(-1.0..1.0);
           let s: f32 = thread_rng().gen_range(0.5..1.2);
-          let mut cube = Shape::new(&mut self.renderer, pipe1, cube_data.clone(), Some(cube_idx.clone()));
+          let mut cube = Shape::new_from_mesh(&mut self.renderer, pipe1, cube_mesh);
           cube.position = [
             -270.0 + x as f32 * 60.0 + rx * 20.0,
             -270.0 + y as f32 * 60.0 + ry * 20.0,
@@ -141,17 +131,21 @@ impl<'a> AppEventLoop<'a> {
   // update logic (asynchronous with render loop)
   pub fn update(&mut self) {
     // logic updates
+    let now = time::Instant::now();
+    let dt = (now - self.last_update).as_secs_f32();
+    self.last_update = now;
     let input_cache = self.input_handler.output();
-    self.camera.position[0] += input_cache.move_x;
-    self.camera.look_at[0] += 0.9 * input_cache.move_x;
-    self.camera.position[1] += input_cache.move_y;
-    self.camera.look_at[1] += 0.9 * input_cache.move_y;
-    self.camera.position[2] += input_cache.move_z;
+    // fly around while the cursor is locked, orbit otherwise
+    let mode = if input_cache.cursor_locked { CameraController::FLY } else { CameraController::ORBIT };
+    self.camera_ctrl.set_mode(mode);
+    self.camera_ctrl.update(input_cache, dt, &mut self.camera);
   }
 
   // render logic updates (synchronous with render loop)
   pub fn pre_render(&mut self, frame_time: &time::Duration) {
     self.render_frame += 1;
+    // pick up shader edits without restarting
+    self.renderer.poll_shader_changes();
     // render logic updates
     for obj in &mut self.shapes {
       if obj.id.0 == 1 {
@@ -171,18 +165,30 @@ impl<'a> AppEventLoop<'a> {
     // find bottom left corner
     let y_max = (self.screen_center.1 * 2.0) as u32;
 
-    // render cubes onto texture
-    self.renderer.render_texture(&self.pipes[0..1], self.textures[1], Some([0.1, 0.0, 0.3, 1.0]));
     // render text onto texture
     self.renderer.render_texture(&[], self.textures[2], Some([0.0, 0.0, 0.0, 0.0])); // clears existing text texture
     self.renderer.render_str_on_texture(self.textures[2], &fps_txt, 20.0, [0, 255, 0], [5, y_max - 10], 1);
-    self.renderer.render_str_on_texture(self.textures[2], "Camera controls: WASD, EQ", 18.0, [50, 50, 255], [5, y_max - 30], 1);
+    self.renderer.render_str_on_texture(self.textures[2], "Camera: drag to orbit, scroll to zoom, hold Alt to fly with WASD, EQ", 18.0, [50, 50, 255], [5, y_max - 30], 1);
   }
 
   // render to screen (can cause frame limiting from requesting screen surface)
   pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-    // render everything to screen
-    match self.renderer.render(&self.pipes) {
+    // render cubes onto texture, then everything to screen
+    let graph = RRenderGraph::new()
+      .with_pass(RGraphPass {
+        label: "screen-pass",
+        inputs: vec![self.textures[1], self.textures[2]],
+        pipeline_ids: self.pipes.clone(),
+        ..Default::default()
+      })
+      .with_pass(RGraphPass {
+        label: "miniview-pass",
+        target: RPassTarget::Texture(self.textures[1]),
+        pipeline_ids: vec![self.pipes[0]],
+        clear_color: Some([0.1, 0.0, 0.3, 1.0]),
+        ..Default::default()
+      });
+    match self.renderer.render_graph(&graph) {
       Ok(_) => Ok(()),
       // Reconfigure the surface if lost
       Err(wgpu::SurfaceError::Lost) => {
@@ -204,8 +210,6 @@ impl<'a> AppEventLoop<'a> {
   pub fn resize(&mut self, width: u32, height: u32) {
     self.renderer.resize_canvas(width, height);
     self.screen_center = (width as f32 / 2.0, height as f32 / 2.0);
-    self.renderer.update_texture_size(self.textures[1], Some(self.pipes[1]), width, height);
-    self.renderer.update_texture_size(self.textures[2], Some(self.pipes[2]), width, height);
     self.update();
   }
 }
\ No newline at end of file
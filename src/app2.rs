@@ -1,4 +1,4 @@
-use std::{fs, time};
+use std::{path::Path, time};
 
 use crate::wgpu_renderer::{Primitives, RCamera, RObjectUpdate, RPipelineId, RPipelineSetup, RTextureId, RVertex, RVertexAnim, Renderer, Shape};
 use crate::input_mapper::InputHandler;
@@ -37,27 +37,13 @@ impl<'a> AppEventLoop<'a> {
     // initialize text pipeline
     let (texture0, pipe0) = self.renderer.add_overlay_pipeline();
     // initialize anim pipeline
-    let pipe1 = match fs::read_to_string("assets/animated.wgsl") {
-      Ok(str) => {
-        self.renderer.add_pipeline(RPipelineSetup {
-          shader: &str,
-          max_obj_count: 10,
-          vertex_type: RPipelineSetup::VERTEX_TYPE_ANIM,
-          poly_mode: RPipelineSetup::POLY_MODE_LINE,
-          max_joints_count: 5,
-          ..Default::default()
-        })
-      }
-      Err(e) => {
-        println!("ERR: shader load error - {}", e.to_string());
-        self.renderer.add_pipeline(RPipelineSetup {
-          max_obj_count: 10,
-          vertex_type: RPipelineSetup::VERTEX_TYPE_ANIM,
-          max_joints_count: 5,
-          ..Default::default()
-        })
-      }
-    };
+    let pipe1 = self.renderer.add_pipeline_from_file(RPipelineSetup {
+      max_obj_count: 10,
+      vertex_type: RPipelineSetup::VERTEX_TYPE_ANIM,
+      poly_mode: RPipelineSetup::POLY_MODE_LINE,
+      max_joints_count: 5,
+      ..Default::default()
+    }, Path::new("assets/animated.wgsl"));
     // initalize static pipeline
     let pipe2 = self.renderer.add_pipeline(RPipelineSetup {
       max_obj_count: 10,
@@ -145,6 +131,8 @@ impl<'a> AppEventLoop<'a> {
   // render logic updates (synchronous with render loop)
   pub fn pre_render(&mut self, frame_time: &time::Duration) {
     self.render_frame += 1;
+    // pick up shader edits without restarting
+    self.renderer.poll_shader_changes();
     // render logic updates
     let x = f32::sin(0.02 * self.render_frame as f32);
     let y = f32::cos(0.02 * self.render_frame as f32);
@@ -205,7 +193,6 @@ impl<'a> AppEventLoop<'a> {
   pub fn resize(&mut self, width: u32, height: u32) {
     self.renderer.resize_canvas(width, height);
     self.screen_center = (width as f32 / 2.0, height as f32 / 2.0);
-    self.renderer.update_texture_size(self.textures[0], Some(self.pipes[0]), width, height);
     self.update();
   }
 }
\ No newline at end of file
@@ -0,0 +1,246 @@
+#![allow(dead_code)]
+
+use crate::wgpu_renderer::{RCamera, Vec3, PI};
+use crate::input_mapper::InputCache;
+
+// drives an RCamera from the input mapper
+// - orbit: left drag rotates around target, right/middle drag pans, scroll zooms
+// - fly: WASD + QE move along the view direction, mouse look while the cursor is locked
+// - first person: like fly, but moves on the ground plane with pitch clamped to pitch_limit
+// note: yaw + pitch in degrees, yaw 0 looks down -z
+#[derive(Debug, Clone)]
+pub struct CameraController {
+  pub mode: u8,
+  pub target: [f32; 3],
+  pub yaw: f32,
+  pub pitch: f32,
+  pub distance: f32,
+  pub min_distance: f32,
+  pub pitch_limit: f32,
+  // units per second
+  pub move_speed: f32,
+  // degrees per pixel of cursor movement
+  pub look_sensitivity: f32,
+  // fraction of distance per scroll line
+  pub zoom_speed: f32,
+  // fraction of distance per pixel of cursor movement
+  pub pan_speed: f32,
+  // seconds to catch up most of the way to the input, 0 follows input immediately
+  pub smoothing: f32,
+  current: ControllerState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ControllerState {
+  target: [f32; 3],
+  yaw: f32,
+  pitch: f32,
+  distance: f32,
+}
+
+impl CameraController {
+  pub const ORBIT: u8 = 0;
+  pub const FLY: u8 = 1;
+  pub const FIRST_PERSON: u8 = 2;
+
+  // continue from the camera's current position + look direction
+  pub fn from_camera(mode: u8, camera: &RCamera) -> Self {
+    let dir = Vec3::subtract(&camera.look_at, &camera.position);
+    let distance = f32::max(Vec3::magnitude(&dir), 0.001);
+    let dir = Vec3::normalize(&dir);
+    let yaw = f32::atan2(dir[0], -dir[2]) * 180.0 / PI;
+    let pitch = f32::asin(dir[1].clamp(-1.0, 1.0)) * 180.0 / PI;
+    let target = match mode {
+      CameraController::ORBIT => camera.look_at,
+      _ => camera.position,
+    };
+    let current = ControllerState { target, yaw, pitch, distance };
+    Self {
+      mode,
+      target,
+      yaw,
+      pitch,
+      distance,
+      min_distance: 1.0,
+      pitch_limit: 85.0,
+      move_speed: 100.0,
+      look_sensitivity: 0.2,
+      zoom_speed: 0.1,
+      pan_speed: 0.002,
+      smoothing: 0.0,
+      current,
+    }
+  }
+
+  // switch mode, keeping the camera where it is
+  pub fn set_mode(&mut self, mode: u8) {
+    if mode == self.mode { return; }
+    let eye = self.eye(&self.current);
+    let forward = forward(self.current.yaw, self.current.pitch);
+    let target = match mode {
+      CameraController::ORBIT => Vec3::add(&eye, &scale(&forward, self.current.distance)),
+      _ => eye,
+    };
+    self.mode = mode;
+    self.target = target;
+    self.current.target = target;
+  }
+
+  // apply input since the last call, then move the camera towards the result
+  pub fn update(&mut self, input: &InputCache, dt: f32, camera: &mut RCamera) {
+    match self.mode {
+      CameraController::ORBIT => self.orbit_input(input),
+      _ => self.fly_input(input, dt),
+    }
+    let limit = match self.mode {
+      CameraController::FIRST_PERSON => self.pitch_limit,
+      // keep away from straight up/down where the up vector flips
+      _ => f32::min(self.pitch_limit, 89.0),
+    };
+    self.pitch = self.pitch.clamp(-limit, limit);
+    self.distance = f32::max(self.distance, self.min_distance);
+
+    // exponential damping, independent of frame rate
+    let t = if self.smoothing > 0.0 { 1.0 - f32::exp(-dt / self.smoothing) } else { 1.0 };
+    let goal = ControllerState { target: self.target, yaw: self.yaw, pitch: self.pitch, distance: self.distance };
+    self.current = ControllerState {
+      target: lerp3(&self.current.target, &goal.target, t),
+      yaw: lerp(self.current.yaw, goal.yaw, t),
+      pitch: lerp(self.current.pitch, goal.pitch, t),
+      distance: lerp(self.current.distance, goal.distance, t),
+    };
+
+    let eye = self.eye(&self.current);
+    let forward = forward(self.current.yaw, self.current.pitch);
+    camera.position = eye;
+    camera.look_at = match self.mode {
+      CameraController::ORBIT => self.current.target,
+      _ => Vec3::add(&eye, &forward),
+    };
+    camera.up = [0.0, 1.0, 0.0];
+  }
+
+  fn orbit_input(&mut self, input: &InputCache) {
+    if input.left_held {
+      self.yaw -= input.cursor_dx * self.look_sensitivity;
+      self.pitch += input.cursor_dy * self.look_sensitivity;
+    }
+    if input.right_held || input.middle_held {
+      // drag the target along with the cursor
+      let right = right(self.yaw);
+      let up = Vec3::cross(&right, &forward(self.yaw, self.pitch));
+      let amount = self.distance * self.pan_speed;
+      let offset = Vec3::add(&scale(&right, -input.cursor_dx * amount), &scale(&up, input.cursor_dy * amount));
+      self.target = Vec3::add(&self.target, &offset);
+    }
+    if input.scroll != 0.0 {
+      self.distance *= f32::powf(1.0 - self.zoom_speed, input.scroll);
+    }
+  }
+
+  fn fly_input(&mut self, input: &InputCache, dt: f32) {
+    if input.cursor_locked {
+      self.yaw += input.cursor_dx * self.look_sensitivity;
+      self.pitch -= input.cursor_dy * self.look_sensitivity;
+    }
+    let forward = match self.mode {
+      CameraController::FIRST_PERSON => forward(self.yaw, 0.0),
+      _ => forward(self.yaw, self.pitch),
+    };
+    let vertical = match self.mode {
+      CameraController::FIRST_PERSON => 0.0,
+      _ => input.key_y,
+    };
+    let step = self.move_speed * dt;
+    let mut offset = scale(&forward, -input.key_z * step);
+    offset = Vec3::add(&offset, &scale(&right(self.yaw), input.key_x * step));
+    offset = Vec3::add(&offset, &[0.0, vertical * step, 0.0]);
+    self.target = Vec3::add(&self.target, &offset);
+  }
+
+  // camera position, target is the eye itself outside of orbit mode
+  fn eye(&self, state: &ControllerState) -> [f32; 3] {
+    match self.mode {
+      CameraController::ORBIT => Vec3::subtract(&state.target, &scale(&forward(state.yaw, state.pitch), state.distance)),
+      _ => state.target,
+    }
+  }
+}
+
+fn forward(yaw: f32, pitch: f32) -> [f32; 3] {
+  let (yaw, pitch) = (yaw * PI / 180.0, pitch * PI / 180.0);
+  [f32::sin(yaw) * f32::cos(pitch), f32::sin(pitch), -f32::cos(yaw) * f32::cos(pitch)]
+}
+
+fn right(yaw: f32) -> [f32; 3] {
+  let yaw = yaw * PI / 180.0;
+  [f32::cos(yaw), 0.0, f32::sin(yaw)]
+}
+
+fn scale(v: &[f32; 3], s: f32) -> [f32; 3] {
+  [v[0] * s, v[1] * s, v[2] * s]
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+  a + (b - a) * t
+}
+
+fn lerp3(a: &[f32; 3], b: &[f32; 3], t: f32) -> [f32; 3] {
+  [lerp(a[0], b[0], t), lerp(a[1], b[1], t), lerp(a[2], b[2], t)]
+}
+
+#[cfg(test)]
+mod camera_controller_tests {
+  use super::*;
+  use crate::input_mapper::InputHandler;
+
+  fn close(a: &[f32; 3], b: &[f32; 3]) -> bool {
+    (0..3).all(|i| (a[i] - b[i]).abs() < 1e-3)
+  }
+
+  #[test]
+  fn orbit_around_target() {
+    let mut cam = RCamera::new_persp(60.0, 1.0, 1000.0);
+    cam.position = [0.0, 0.0, 200.0];
+    let mut ctrl = CameraController::from_camera(CameraController::ORBIT, &cam);
+    assert_eq!((ctrl.yaw, ctrl.pitch, ctrl.distance), (0.0, 0.0, 200.0));
+    let handler = InputHandler::new();
+    ctrl.update(handler.output(), 0.016, &mut cam);
+    assert!(close(&cam.position, &[0.0, 0.0, 200.0]));
+    // quarter turn to the side, then zoom in
+    ctrl.yaw = -90.0;
+    ctrl.distance = 100.0;
+    ctrl.update(handler.output(), 0.016, &mut cam);
+    assert!(close(&cam.position, &[100.0, 0.0, 0.0]));
+    assert_eq!(cam.look_at, [0.0, 0.0, 0.0]);
+    // pitch stops short of straight down
+    ctrl.pitch = -120.0;
+    ctrl.update(handler.output(), 0.016, &mut cam);
+    assert_eq!(ctrl.pitch, -85.0);
+  }
+
+  #[test]
+  fn smoothing_and_first_person() {
+    let mut cam = RCamera::new_persp(60.0, 1.0, 1000.0);
+    cam.position = [0.0, 10.0, 0.0];
+    cam.look_at = [0.0, 10.0, -1.0];
+    let mut ctrl = CameraController::from_camera(CameraController::FIRST_PERSON, &cam);
+    ctrl.pitch = 45.0;
+    ctrl.smoothing = 0.1;
+    let handler = InputHandler::new();
+    // halfway after ln(2) time constants
+    ctrl.target = [0.0, 10.0, -10.0];
+    ctrl.update(handler.output(), 0.1 * f32::ln(2.0), &mut cam);
+    assert!(close(&cam.position, &[0.0, 10.0, -5.0]));
+    for _ in 0..100 {
+      ctrl.update(handler.output(), 0.1, &mut cam);
+    }
+    assert!(close(&cam.position, &[0.0, 10.0, -10.0]));
+    // moving forward stays on the ground plane while looking up
+    let mut input = InputHandler::new();
+    input.winit_kb_event(&winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyW), &winit::event::ElementState::Pressed, false);
+    ctrl.smoothing = 0.0;
+    ctrl.update(input.output(), 0.1, &mut cam);
+    assert!(close(&cam.position, &[0.0, 10.0, -20.0]));
+  }
+}
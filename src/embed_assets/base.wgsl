@@ -1,25 +1,7 @@
-@group(0) @binding(0) var<uniform> mvp: MVP;
-@group(0) @binding(1) var tx_sampler: sampler;
-@group(0) @binding(2) var texture1: texture_2d<f32>;
-@group(0) @binding(3) var texture2: texture_2d<f32>;
-
-struct MVP {
-  model: mat4x4<f32>,
-  view: mat4x4<f32>,
-  proj: mat4x4<f32>,
-}
-
-struct VertIn {
-  @location(0) pos: vec3f,
-  @location(1) uv: vec2f,
-  @location(2) normal: vec3f,
-}
-
-struct VertOut {
-  @builtin(position) pos: vec4f,
-  @location(0) uv: vec2f,
-  @location(1) normal: vec3f,
-}
+#include "mvp.wgsl"
+#include "textures.wgsl"
+#include "vertex.wgsl"
+#include "oit.wgsl"
 
 @vertex
 fn vertexMain(input: VertIn) -> VertOut {
@@ -33,11 +15,25 @@ fn vertexMain(input: VertIn) -> VertOut {
 
 @fragment
 fn fragmentMain(input: VertOut) -> @location(0) vec4f {
+  return shade(input);
+}
+
+// weighted blended order independent transparency output
+@fragment
+fn fragmentOit(input: VertOut) -> OitOut {
+  return oit_output(shade(input), input.pos.z);
+}
+
+fn shade(input: VertOut) -> vec4f {
   let n = (1.0 + input.normal) / 2.0;
   var tx1 = textureSample(texture1, tx_sampler, input.uv);
-  var tx2 = textureSample(texture2, tx_sampler, input.uv);
   // draw normal instead of texture if alpha < 0.0001
   tx1 = mix(tx1, vec4f(n, 1.0), step(tx1.a, 0.0001));
+#ifdef HAS_TEXTURE2
   // mix tx1 and tx2, increasing tx2 influence based on alpha
+  let tx2 = textureSample(texture2, tx_sampler, input.uv);
   return mix(tx1 + tx2, tx2, tx2.a);
+#else
+  return tx1;
+#endif
 }
\ No newline at end of file
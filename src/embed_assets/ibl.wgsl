@@ -0,0 +1,142 @@
+// image based lighting precomputation, each pass renders a single cubemap face or the brdf lut
+// params: face, roughness, -, -
+@group(0) @binding(0) var<uniform> params: vec4f;
+@group(0) @binding(1) var env_sampler: sampler;
+@group(0) @binding(2) var env_map: texture_cube<f32>;
+@group(0) @binding(3) var source_map: texture_2d<f32>;
+
+const PI: f32 = 3.14159265359;
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+}
+
+// full screen triangle
+@vertex
+fn vertexMain(@builtin(vertex_index) idx: u32) -> VertOut {
+  var out: VertOut;
+  let uv = vec2f(f32((idx << 1u) & 2u), f32(idx & 2u));
+  out.pos = vec4f(uv * 2.0 - 1.0, 0.0, 1.0);
+  out.uv = vec2f(uv.x, 1.0 - uv.y);
+  return out;
+}
+
+// world direction of texel on cubemap face (+x, -x, +y, -y, +z, -z)
+fn face_dir(face: u32, uv: vec2f) -> vec3f {
+  let st = uv * 2.0 - 1.0;
+  switch face {
+    case 0u: { return normalize(vec3f(1.0, -st.y, -st.x)); }
+    case 1u: { return normalize(vec3f(-1.0, -st.y, st.x)); }
+    case 2u: { return normalize(vec3f(st.x, 1.0, st.y)); }
+    case 3u: { return normalize(vec3f(st.x, -1.0, -st.y)); }
+    case 4u: { return normalize(vec3f(st.x, -st.y, 1.0)); }
+    default: { return normalize(vec3f(-st.x, -st.y, -1.0)); }
+  }
+}
+
+fn hammersley(i: u32, n: u32) -> vec2f {
+  return vec2f(f32(i) / f32(n), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+fn importance_sample_ggx(xi: vec2f, n: vec3f, roughness: f32) -> vec3f {
+  let a = roughness * roughness;
+  let phi = 2.0 * PI * xi.x;
+  let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+  let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+  let h = vec3f(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+  // tangent space to world
+  let up = select(vec3f(1.0, 0.0, 0.0), vec3f(0.0, 0.0, 1.0), abs(n.z) < 0.999);
+  let tangent = normalize(cross(up, n));
+  let bitangent = cross(n, tangent);
+  return normalize(tangent * h.x + bitangent * h.y + n * h.z);
+}
+
+// procedural sky used as default environment
+@fragment
+fn fragmentSky(input: VertOut) -> @location(0) vec4f {
+  let dir = face_dir(u32(params.x), input.uv);
+  let sky = mix(vec3f(0.6, 0.7, 0.8), vec3f(0.2, 0.35, 0.6), clamp(dir.y, 0.0, 1.0));
+  let ground = mix(vec3f(0.6, 0.7, 0.8), vec3f(0.15, 0.13, 0.12), clamp(-dir.y * 4.0, 0.0, 1.0));
+  return vec4f(select(ground, sky, dir.y >= 0.0), 1.0);
+}
+
+// equirectangular panorama projected onto cubemap face
+@fragment
+fn fragmentEquirect(input: VertOut) -> @location(0) vec4f {
+  let dir = face_dir(u32(params.x), input.uv);
+  let uv = vec2f(atan2(dir.z, dir.x) / (2.0 * PI) + 0.5, acos(clamp(dir.y, -1.0, 1.0)) / PI);
+  return vec4f(textureSampleLevel(source_map, env_sampler, uv, 0.0).rgb, 1.0);
+}
+
+// diffuse convolution over the hemisphere
+@fragment
+fn fragmentIrradiance(input: VertOut) -> @location(0) vec4f {
+  let n = face_dir(u32(params.x), input.uv);
+  let up = select(vec3f(1.0, 0.0, 0.0), vec3f(0.0, 1.0, 0.0), abs(n.y) < 0.999);
+  let right = normalize(cross(up, n));
+  let n_up = cross(n, right);
+  var irradiance = vec3f(0.0);
+  var count = 0.0;
+  for (var phi = 0.0; phi < 2.0 * PI; phi += 0.1) {
+    for (var theta = 0.0; theta < 0.5 * PI; theta += 0.1) {
+      let t = vec3f(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+      let dir = t.x * right + t.y * n_up + t.z * n;
+      irradiance += textureSampleLevel(env_map, env_sampler, dir, 0.0).rgb * cos(theta) * sin(theta);
+      count += 1.0;
+    }
+  }
+  return vec4f(PI * irradiance / count, 1.0);
+}
+
+// specular convolution for a single roughness level
+@fragment
+fn fragmentPrefilter(input: VertOut) -> @location(0) vec4f {
+  let n = face_dir(u32(params.x), input.uv);
+  let roughness = params.y;
+  let sample_count = 256u;
+  var color = vec3f(0.0);
+  var weight = 0.0;
+  for (var i = 0u; i < sample_count; i++) {
+    let h = importance_sample_ggx(hammersley(i, sample_count), n, roughness);
+    let l = normalize(2.0 * dot(n, h) * h - n);
+    let n_dot_l = dot(n, l);
+    if n_dot_l > 0.0 {
+      color += textureSampleLevel(env_map, env_sampler, l, 0.0).rgb * n_dot_l;
+      weight += n_dot_l;
+    }
+  }
+  return vec4f(color / max(weight, 0.0001), 1.0);
+}
+
+fn geometry_schlick_ggx(n_dot_v: f32, roughness: f32) -> f32 {
+  let k = roughness * roughness / 2.0;
+  return n_dot_v / (n_dot_v * (1.0 - k) + k);
+}
+
+// split sum brdf integration, x = n dot v, y = roughness
+@fragment
+fn fragmentBrdfLut(input: VertOut) -> @location(0) vec4f {
+  let n_dot_v = max(input.uv.x, 0.001);
+  let roughness = input.uv.y;
+  let v = vec3f(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+  let n = vec3f(0.0, 0.0, 1.0);
+  let sample_count = 512u;
+  var a = 0.0;
+  var b = 0.0;
+  for (var i = 0u; i < sample_count; i++) {
+    let h = importance_sample_ggx(hammersley(i, sample_count), n, roughness);
+    let l = normalize(2.0 * dot(v, h) * h - v);
+    let n_dot_l = max(l.z, 0.0);
+    let n_dot_h = max(h.z, 0.0);
+    let v_dot_h = max(dot(v, h), 0.0);
+    if n_dot_l > 0.0 {
+      let g = geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+      let g_vis = g * v_dot_h / (n_dot_h * n_dot_v);
+      let fc = pow(1.0 - v_dot_h, 5.0);
+      a += (1.0 - fc) * g_vis;
+      b += fc * g_vis;
+    }
+  }
+  return vec4f(a / f32(sample_count), b / f32(sample_count), 0.0, 1.0);
+}
\ No newline at end of file
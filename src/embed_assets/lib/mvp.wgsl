@@ -0,0 +1,11 @@
+// model-view-projection matrices, joint transforms for animated vertex pipelines
+@group(0) @binding(0) var<uniform> mvp: MVP;
+#ifdef HAS_SKINNING
+@group(0) @binding(4) var<uniform> joint_transforms: array<mat4x4<f32>, MAX_JOINTS>;
+#endif
+
+struct MVP {
+  model: mat4x4<f32>,
+  view: mat4x4<f32>,
+  proj: mat4x4<f32>,
+}
\ No newline at end of file
@@ -0,0 +1,19 @@
+// weighted blended order independent transparency output
+struct OitOut {
+  @location(0) accum: vec4f,
+  @location(1) reveal: f32,
+}
+
+// weight falls off with depth, which grows towards the far plane unless depth is reversed
+fn oit_output(c: vec4f, frag_z: f32) -> OitOut {
+#ifdef REVERSED_Z
+  let z = 1.0 - frag_z;
+#else
+  let z = frag_z;
+#endif
+  let w = clamp(c.a * max(0.01, 3000.0 * pow(1.0 - z, 3.0)), 0.01, 3000.0);
+  var out: OitOut;
+  out.accum = vec4f(c.rgb * c.a, c.a) * w;
+  out.reveal = c.a;
+  return out;
+}
\ No newline at end of file
@@ -0,0 +1,4 @@
+// pipeline textures, sharing the sampler at binding 1
+@group(0) @binding(1) var tx_sampler: sampler;
+@group(0) @binding(2) var texture1: texture_2d<f32>;
+@group(0) @binding(3) var texture2: texture_2d<f32>;
\ No newline at end of file
@@ -0,0 +1,18 @@
+// vertex layout, define CUSTOM_VERT_OUT to declare your own VertOut
+struct VertIn {
+  @location(0) pos: vec3f,
+  @location(1) uv: vec2f,
+  @location(2) normal: vec3f,
+#ifdef HAS_SKINNING
+  @location(3) joints: vec4u,
+  @location(4) weights: vec4f,
+#endif
+}
+
+#ifndef CUSTOM_VERT_OUT
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+  @location(1) normal: vec3f,
+}
+#endif
\ No newline at end of file
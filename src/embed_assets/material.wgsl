@@ -0,0 +1,43 @@
+@group(0) @binding(0) var<uniform> mvp: MVP;
+
+@group(2) @binding(0) var<uniform> material: Material;
+@group(2) @binding(1) var mat_sampler: sampler;
+@group(2) @binding(2) var base_texture: texture_2d<f32>;
+
+struct MVP {
+  model: mat4x4<f32>,
+  view: mat4x4<f32>,
+  proj: mat4x4<f32>,
+}
+
+struct Material {
+  base_color: vec4f,
+}
+
+struct VertIn {
+  @location(0) pos: vec3f,
+  @location(1) uv: vec2f,
+  @location(2) normal: vec3f,
+}
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+  @location(1) normal: vec3f,
+}
+
+@vertex
+fn vertexMain(input: VertIn) -> VertOut {
+  var out: VertOut;
+  let mvp_mat = mvp.proj * mvp.view * mvp.model;
+  out.pos = mvp_mat * vec4f(input.pos, 1.0);
+  out.uv = input.uv;
+  out.normal = (mvp.model * vec4f(input.normal, 0.0)).xyz;
+  return out;
+}
+
+// base color texture tinted by material color
+@fragment
+fn fragmentMain(input: VertOut) -> @location(0) vec4f {
+  return textureSample(base_texture, mat_sampler, input.uv) * material.base_color;
+}
\ No newline at end of file
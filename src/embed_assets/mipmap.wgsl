@@ -0,0 +1,23 @@
+// downsample previous mip level into the next, one full screen triangle per level
+@group(0) @binding(0) var src_sampler: sampler;
+@group(0) @binding(1) var src_texture: texture_2d<f32>;
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+}
+
+@vertex
+fn vertexMain(@builtin(vertex_index) idx: u32) -> VertOut {
+  var out: VertOut;
+  let uv = vec2f(f32((idx << 1u) & 2u), f32(idx & 2u));
+  out.pos = vec4f(uv * 2.0 - 1.0, 0.0, 1.0);
+  out.uv = vec2f(uv.x, 1.0 - uv.y);
+  return out;
+}
+
+// linear filter averages the 2x2 texel footprint
+@fragment
+fn fragmentMain(in: VertOut) -> @location(0) vec4f {
+  return textureSample(src_texture, src_sampler, in.uv);
+}
\ No newline at end of file
@@ -0,0 +1,95 @@
+// instanced camera facing particle quads, see RParticleSystem in wgpu_renderer/particles.rs
+#define CUSTOM_VERT_OUT
+#include "mvp.wgsl"
+#include "textures.wgsl"
+#include "vertex.wgsl"
+
+// evenly spaced color + size keys, counts are (color keys, size keys, sheet columns, sheet rows)
+struct Look {
+  colors: array<vec4f, 8>,
+  sizes: array<vec4f, 2>,
+  counts: vec4u,
+  sheet: vec4f,
+}
+
+@group(1) @binding(0) var<uniform> look: Look;
+
+struct ParticleIn {
+  @location(3) pos_age: vec4f,
+  @location(4) vel_life: vec4f,
+}
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+  @location(1) quad_uv: vec2f,
+  @location(2) color: vec4f,
+}
+
+fn sample_color(t: f32) -> vec4f {
+  let n = look.counts.x;
+  if (n == 0u) {
+    return vec4f(1.0);
+  }
+  if (n == 1u) {
+    return look.colors[0];
+  }
+  let x = t * f32(n - 1u);
+  let i = min(u32(x), n - 2u);
+  return mix(look.colors[i], look.colors[i + 1u], x - f32(i));
+}
+
+fn size_key(i: u32) -> f32 {
+  return look.sizes[i / 4u][i % 4u];
+}
+
+fn sample_size(t: f32) -> f32 {
+  let n = look.counts.y;
+  if (n == 0u) {
+    return 1.0;
+  }
+  if (n == 1u) {
+    return size_key(0u);
+  }
+  let x = t * f32(n - 1u);
+  let i = min(u32(x), n - 2u);
+  return mix(size_key(i), size_key(i + 1u), x - f32(i));
+}
+
+@vertex
+fn vertexMain(input: VertIn, particle: ParticleIn) -> VertOut {
+  var out: VertOut;
+  let age = particle.pos_age.w;
+  let life = particle.vel_life.w;
+  // dead particles collapse to a degenerate quad
+  if (age >= life) {
+    out.pos = vec4f(0.0);
+    return out;
+  }
+  let t = clamp(age / life, 0.0, 1.0);
+  let right = vec3f(mvp.view[0][0], mvp.view[1][0], mvp.view[2][0]);
+  let up = vec3f(mvp.view[0][1], mvp.view[1][1], mvp.view[2][1]);
+  let world = particle.pos_age.xyz + (right * input.pos.x + up * input.pos.y) * sample_size(t);
+  out.pos = mvp.proj * mvp.view * vec4f(world, 1.0);
+  // texture sheet frame
+  let cols = look.counts.z;
+  let rows = look.counts.w;
+  let frames = cols * rows;
+  let frame = u32(min(t, 0.9999) * look.sheet.x * f32(frames)) % frames;
+  out.uv = (input.uv + vec2f(f32(frame % cols), f32(frame / cols))) / vec2f(f32(cols), f32(rows));
+  out.quad_uv = input.uv;
+  out.color = sample_color(t);
+  return out;
+}
+
+@fragment
+fn fragmentMain(input: VertOut) -> @location(0) vec4f {
+#ifdef HAS_TEXTURE
+  let tx = textureSample(texture1, tx_sampler, input.uv);
+#else
+  // soft round particle without a texture
+  let d = length(input.quad_uv - vec2f(0.5));
+  let tx = vec4f(1.0, 1.0, 1.0, 1.0 - smoothstep(0.3, 0.5, d));
+#endif
+  return tx * input.color;
+}
\ No newline at end of file
@@ -0,0 +1,132 @@
+// particle simulation, mirrors RParticleSim in wgpu_renderer/particles.rs
+struct Particle {
+  pos_age: vec4f,
+  vel_life: vec4f,
+}
+
+struct SimParams {
+  origin: vec3f,
+  dt: f32,
+  gravity: vec3f,
+  drag: f32,
+  direction: vec3f,
+  radius: f32,
+  lifetime: vec2f,
+  speed: vec2f,
+  emitter: u32,
+  cone_angle: f32,
+  spawn_count: u32,
+  seed: u32,
+  max_particles: u32,
+  triangle_count: u32,
+}
+
+// triangle corners, with the running total of triangle areas in a.w
+struct Triangle {
+  a: vec4f,
+  b: vec4f,
+  c: vec4f,
+  normal: vec4f,
+}
+
+@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1) var<uniform> params: SimParams;
+@group(0) @binding(2) var<storage, read_write> spawned: atomic<u32>;
+@group(0) @binding(3) var<storage, read> triangles: array<Triangle>;
+
+const PI = 3.14159265;
+const EMITTER_SPHERE = 1u;
+const EMITTER_CONE = 2u;
+const EMITTER_MESH = 3u;
+
+var<private> rng: u32;
+
+fn pcg_hash(v: u32) -> u32 {
+  let state = v * 747796405u + 2891336453u;
+  let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+  return (word >> 22u) ^ word;
+}
+
+fn rand() -> f32 {
+  rng = pcg_hash(rng);
+  return f32(rng >> 8u) / 16777216.0;
+}
+
+fn unit_vector() -> vec3f {
+  let z = 2.0 * rand() - 1.0;
+  let phi = 2.0 * PI * rand();
+  let r = sqrt(max(0.0, 1.0 - z * z));
+  return vec3f(r * cos(phi), r * sin(phi), z);
+}
+
+fn spawn() -> Particle {
+  var offset = vec3f(0.0);
+  var dir: vec3f;
+  if (params.emitter == EMITTER_SPHERE) {
+    dir = unit_vector();
+    offset = dir * params.radius * pow(rand(), 1.0 / 3.0);
+  } else if (params.emitter == EMITTER_CONE) {
+    var up = vec3f(0.0, 1.0, 0.0);
+    if (abs(params.direction.y) >= 0.999) {
+      up = vec3f(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(params.direction, up));
+    let bitangent = cross(params.direction, tangent);
+    // direction within the cone angle around the axis
+    let cos_theta = 1.0 + (cos(params.cone_angle) - 1.0) * rand();
+    let sin_theta = sqrt(max(0.0, 1.0 - cos_theta * cos_theta));
+    let phi = 2.0 * PI * rand();
+    dir = normalize((tangent * cos(phi) + bitangent * sin(phi)) * sin_theta + params.direction * cos_theta);
+    // start from a disc of radius around the axis
+    let r = params.radius * sqrt(rand());
+    let a = 2.0 * PI * rand();
+    offset = (tangent * cos(a) + bitangent * sin(a)) * r;
+  } else if (params.emitter == EMITTER_MESH && params.triangle_count > 0u) {
+    // area weighted triangle, binary search on the running area total
+    let target_area = rand() * triangles[params.triangle_count - 1u].a.w;
+    var lo = 0u;
+    var hi = params.triangle_count - 1u;
+    while (lo < hi) {
+      let mid = (lo + hi) / 2u;
+      if (triangles[mid].a.w < target_area) {
+        lo = mid + 1u;
+      } else {
+        hi = mid;
+      }
+    }
+    let tri = triangles[lo];
+    // uniform barycentric coordinates
+    let su = sqrt(rand());
+    let v = rand();
+    let b0 = 1.0 - su;
+    let b1 = v * su;
+    offset = tri.a.xyz * b0 + tri.b.xyz * b1 + tri.c.xyz * (1.0 - b0 - b1);
+    dir = tri.normal.xyz;
+  } else {
+    dir = unit_vector();
+  }
+  let speed = mix(params.speed.x, params.speed.y, rand());
+  var p: Particle;
+  p.pos_age = vec4f(params.origin + offset, 0.0);
+  p.vel_life = vec4f(dir * speed, mix(params.lifetime.x, params.lifetime.y, rand()));
+  return p;
+}
+
+@compute @workgroup_size(64)
+fn simulate(@builtin(global_invocation_id) id: vec3u) {
+  let i = id.x;
+  if (i >= params.max_particles) {
+    return;
+  }
+  var p = particles[i];
+  if (p.pos_age.w < p.vel_life.w) {
+    let damping = max(0.0, 1.0 - params.drag * params.dt);
+    let vel = (p.vel_life.xyz + params.gravity * params.dt) * damping;
+    p.vel_life = vec4f(vel, p.vel_life.w);
+    p.pos_age = vec4f(p.pos_age.xyz + vel * params.dt, p.pos_age.w + params.dt);
+  } else if (atomicAdd(&spawned, 1u) < params.spawn_count) {
+    rng = pcg_hash(i ^ params.seed);
+    p = spawn();
+  }
+  particles[i] = p;
+}
\ No newline at end of file
@@ -0,0 +1,145 @@
+// metallic-roughness shading with image based lighting
+@group(0) @binding(0) var<uniform> mvp: MVP;
+
+@group(2) @binding(0) var<uniform> material: PbrParams;
+@group(2) @binding(1) var mat_sampler: sampler;
+@group(2) @binding(2) var base_color_map: texture_2d<f32>;
+@group(2) @binding(3) var metal_rough_map: texture_2d<f32>;
+@group(2) @binding(4) var normal_map: texture_2d<f32>;
+@group(2) @binding(5) var occlusion_map: texture_2d<f32>;
+@group(2) @binding(6) var emissive_map: texture_2d<f32>;
+
+@group(3) @binding(0) var ibl_sampler: sampler;
+@group(3) @binding(1) var irradiance_map: texture_cube<f32>;
+@group(3) @binding(2) var prefiltered_map: texture_cube<f32>;
+@group(3) @binding(3) var brdf_lut: texture_2d<f32>;
+@group(3) @binding(4) var<uniform> lighting: Lighting;
+
+const PI: f32 = 3.14159265359;
+
+struct MVP {
+  model: mat4x4<f32>,
+  view: mat4x4<f32>,
+  proj: mat4x4<f32>,
+}
+
+struct PbrParams {
+  base_color: vec4f,
+  emissive: vec4f,
+  metallic: f32,
+  roughness: f32,
+  normal_scale: f32,
+  occlusion_strength: f32,
+}
+
+// light_dir: xyz direction, light_color: rgb * intensity, ibl: max prefiltered mip, intensity
+struct Lighting {
+  light_dir: vec4f,
+  light_color: vec4f,
+  ibl: vec4f,
+}
+
+struct VertIn {
+  @location(0) pos: vec3f,
+  @location(1) uv: vec2f,
+  @location(2) normal: vec3f,
+}
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+  @location(1) normal: vec3f,
+  @location(2) world_pos: vec3f,
+  @location(3) cam_pos: vec3f,
+}
+
+@vertex
+fn vertexMain(input: VertIn) -> VertOut {
+  var out: VertOut;
+  let world_pos = mvp.model * vec4f(input.pos, 1.0);
+  out.pos = mvp.proj * mvp.view * world_pos;
+  out.uv = input.uv;
+  out.normal = (mvp.model * vec4f(input.normal, 0.0)).xyz;
+  out.world_pos = world_pos.xyz;
+  // camera position from inverse of view rotation
+  let t = mvp.view[3].xyz;
+  out.cam_pos = -vec3f(dot(mvp.view[0].xyz, t), dot(mvp.view[1].xyz, t), dot(mvp.view[2].xyz, t));
+  return out;
+}
+
+// normal mapping without precomputed tangents
+fn perturb_normal(n: vec3f, p: vec3f, uv: vec2f, map_n: vec3f) -> vec3f {
+  let dp1 = dpdx(p);
+  let dp2 = dpdy(p);
+  let duv1 = dpdx(uv);
+  let duv2 = dpdy(uv);
+  let dp2_perp = cross(dp2, n);
+  let dp1_perp = cross(n, dp1);
+  let t = dp2_perp * duv1.x + dp1_perp * duv2.x;
+  let b = dp2_perp * duv1.y + dp1_perp * duv2.y;
+  let inv_max = inverseSqrt(max(max(dot(t, t), dot(b, b)), 1e-12));
+  return normalize(mat3x3f(t * inv_max, b * inv_max, n) * map_n);
+}
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+  let a2 = roughness * roughness * roughness * roughness;
+  let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+  return a2 / (PI * d * d);
+}
+
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+  let r = roughness + 1.0;
+  let k = r * r / 8.0;
+  let gv = n_dot_v / (n_dot_v * (1.0 - k) + k);
+  let gl = n_dot_l / (n_dot_l * (1.0 - k) + k);
+  return gv * gl;
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3f) -> vec3f {
+  return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+fn fresnel_schlick_roughness(cos_theta: f32, f0: vec3f, roughness: f32) -> vec3f {
+  return f0 + (max(vec3f(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+@fragment
+fn fragmentMain(input: VertOut) -> @location(0) vec4f {
+  let base = textureSample(base_color_map, mat_sampler, input.uv) * material.base_color;
+  let mr = textureSample(metal_rough_map, mat_sampler, input.uv);
+  let metallic = clamp(material.metallic * mr.b, 0.0, 1.0);
+  let roughness = clamp(material.roughness * mr.g, 0.04, 1.0);
+  let map_n = textureSample(normal_map, mat_sampler, input.uv).xyz * 2.0 - 1.0;
+  let ao_sample = textureSample(occlusion_map, mat_sampler, input.uv).r;
+  let emissive = textureSample(emissive_map, mat_sampler, input.uv).rgb * material.emissive.rgb;
+
+  // normal_scale of 0 disables normal mapping
+  let geo_n = normalize(input.normal);
+  let mapped_n = perturb_normal(geo_n, input.world_pos, input.uv, vec3f(map_n.xy * material.normal_scale, map_n.z));
+  let n = select(geo_n, mapped_n, material.normal_scale > 0.0);
+  let v = normalize(input.cam_pos - input.world_pos);
+  let n_dot_v = max(dot(n, v), 0.0001);
+  let f0 = mix(vec3f(0.04), base.rgb, metallic);
+
+  // direct light, cook-torrance
+  let l = normalize(-lighting.light_dir.xyz);
+  let h = normalize(v + l);
+  let n_dot_l = max(dot(n, l), 0.0);
+  let f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+  let specular = distribution_ggx(max(dot(n, h), 0.0), roughness) * geometry_smith(n_dot_v, n_dot_l, roughness) * f
+    / (4.0 * n_dot_v * max(n_dot_l, 0.0001));
+  let kd = (1.0 - f) * (1.0 - metallic);
+  let direct = (kd * base.rgb / PI + specular) * lighting.light_color.rgb * n_dot_l;
+
+  // image based lighting
+  let f_ibl = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+  let kd_ibl = (1.0 - f_ibl) * (1.0 - metallic);
+  let irradiance = textureSample(irradiance_map, ibl_sampler, n).rgb;
+  let r = reflect(-v, n);
+  let prefiltered = textureSampleLevel(prefiltered_map, ibl_sampler, r, roughness * lighting.ibl.x).rgb;
+  let brdf = textureSample(brdf_lut, ibl_sampler, vec2f(n_dot_v, roughness)).rg;
+  let ao = mix(1.0, ao_sample, material.occlusion_strength);
+  let ambient = (kd_ibl * irradiance * base.rgb + prefiltered * (f_ibl * brdf.x + brdf.y)) * ao * lighting.ibl.y;
+
+  return vec4f(ambient + direct + emissive, base.a);
+}
\ No newline at end of file
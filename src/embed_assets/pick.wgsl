@@ -0,0 +1,14 @@
+// object ids for pixel exact picking, see Renderer::pick_pixel
+#include "mvp.wgsl"
+
+@group(1) @binding(0) var<uniform> pick_id: vec4u;
+
+@vertex
+fn vertexMain(@location(0) pos: vec3f) -> @builtin(position) vec4f {
+  return mvp.proj * mvp.view * mvp.model * vec4f(pos, 1.0);
+}
+
+@fragment
+fn fragmentMain() -> @location(0) u32 {
+  return pick_id.x;
+}
\ No newline at end of file
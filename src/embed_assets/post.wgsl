@@ -0,0 +1,164 @@
+@group(0) @binding(1) var tx_sampler: sampler;
+@group(0) @binding(2) var texture1: texture_2d<f32>;
+@group(0) @binding(3) var texture2: texture_2d<f32>;
+
+@group(1) @binding(0) var<uniform> params: vec4f;
+
+struct VertIn {
+  @location(0) pos: vec3f,
+  @location(1) uv: vec2f,
+  @location(2) normal: vec3f,
+}
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+}
+
+fn luma(c: vec3f) -> f32 {
+  return dot(c, vec3f(0.2126, 0.7152, 0.0722));
+}
+
+@vertex
+fn vertexMain(input: VertIn) -> VertOut {
+  var out: VertOut;
+  out.pos = vec4f(input.pos.xy, 0.0, 1.0);
+  out.uv = input.uv;
+  return out;
+}
+
+// copy input to output
+@fragment
+fn fragmentCopy(input: VertOut) -> @location(0) vec4f {
+  return vec4f(textureSample(texture1, tx_sampler, input.uv).rgb, 1.0);
+}
+
+// params: threshold, intensity, spread, -
+@fragment
+fn fragmentBright(input: VertOut) -> @location(0) vec4f {
+  let c = textureSample(texture1, tx_sampler, input.uv).rgb;
+  let l = luma(c);
+  let contrib = max(l - params.x, 0.0) / max(l, 0.0001);
+  return vec4f(c * contrib, 1.0);
+}
+
+fn blur(uv: vec2f, dir: vec2f) -> vec4f {
+  var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+  let texel_step = dir * params.z / vec2f(textureDimensions(texture1));
+  var c = textureSample(texture1, tx_sampler, uv).rgb * weights[0];
+  for (var i = 1; i < 5; i++) {
+    let offset = texel_step * f32(i);
+    c += textureSample(texture1, tx_sampler, uv + offset).rgb * weights[i];
+    c += textureSample(texture1, tx_sampler, uv - offset).rgb * weights[i];
+  }
+  return vec4f(c, 1.0);
+}
+
+@fragment
+fn fragmentBlurH(input: VertOut) -> @location(0) vec4f {
+  return blur(input.uv, vec2f(1.0, 0.0));
+}
+
+@fragment
+fn fragmentBlurV(input: VertOut) -> @location(0) vec4f {
+  return blur(input.uv, vec2f(0.0, 1.0));
+}
+
+@fragment
+fn fragmentBloom(input: VertOut) -> @location(0) vec4f {
+  let base = textureSample(texture1, tx_sampler, input.uv).rgb;
+  let bloom = textureSample(texture2, tx_sampler, input.uv).rgb;
+  return vec4f(base + bloom * params.y, 1.0);
+}
+
+// params: exposure, -, -, -
+@fragment
+fn fragmentAces(input: VertOut) -> @location(0) vec4f {
+  let x = textureSample(texture1, tx_sampler, input.uv).rgb * params.x;
+  let c = (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+  return vec4f(clamp(c, vec3f(0.0), vec3f(1.0)), 1.0);
+}
+
+@fragment
+fn fragmentReinhard(input: VertOut) -> @location(0) vec4f {
+  let x = textureSample(texture1, tx_sampler, input.uv).rgb * params.x;
+  return vec4f(x / (vec3f(1.0) + x), 1.0);
+}
+
+// params: span max, reduce mul, reduce min, -
+@fragment
+fn fragmentFxaa(input: VertOut) -> @location(0) vec4f {
+  let texel = 1.0 / vec2f(textureDimensions(texture1));
+  let uv = input.uv;
+  let rgb_nw = textureSample(texture1, tx_sampler, uv + vec2f(-1.0, -1.0) * texel).rgb;
+  let rgb_ne = textureSample(texture1, tx_sampler, uv + vec2f(1.0, -1.0) * texel).rgb;
+  let rgb_sw = textureSample(texture1, tx_sampler, uv + vec2f(-1.0, 1.0) * texel).rgb;
+  let rgb_se = textureSample(texture1, tx_sampler, uv + vec2f(1.0, 1.0) * texel).rgb;
+  let rgb_m = textureSample(texture1, tx_sampler, uv).rgb;
+  let l_nw = luma(rgb_nw);
+  let l_ne = luma(rgb_ne);
+  let l_sw = luma(rgb_sw);
+  let l_se = luma(rgb_se);
+  let l_m = luma(rgb_m);
+  let l_min = min(l_m, min(min(l_nw, l_ne), min(l_sw, l_se)));
+  let l_max = max(l_m, max(max(l_nw, l_ne), max(l_sw, l_se)));
+  // find edge direction
+  var dir = vec2f(-((l_nw + l_ne) - (l_sw + l_se)), (l_nw + l_sw) - (l_ne + l_se));
+  let dir_reduce = max((l_nw + l_ne + l_sw + l_se) * 0.25 * params.y, params.z);
+  let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+  dir = clamp(dir * rcp_dir_min, vec2f(-params.x), vec2f(params.x)) * texel;
+  // blend along edge
+  let rgb_a = 0.5 * (
+    textureSample(texture1, tx_sampler, uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+    textureSample(texture1, tx_sampler, uv + dir * (2.0 / 3.0 - 0.5)).rgb
+  );
+  let rgb_b = rgb_a * 0.5 + 0.25 * (
+    textureSample(texture1, tx_sampler, uv + dir * -0.5).rgb +
+    textureSample(texture1, tx_sampler, uv + dir * 0.5).rgb
+  );
+  let l_b = luma(rgb_b);
+  let use_a = l_b < l_min || l_b > l_max;
+  return vec4f(select(rgb_b, rgb_a, use_a), 1.0);
+}
+
+// params: lut size, strength, -, -
+// note: lut is a horizontal strip of size * size slices, with blue increasing per slice
+@fragment
+fn fragmentLut(input: VertOut) -> @location(0) vec4f {
+  let c = clamp(textureSample(texture1, tx_sampler, input.uv).rgb, vec3f(0.0), vec3f(1.0));
+  let n = params.x;
+  let slice = c.b * (n - 1.0);
+  let slice0 = floor(slice);
+  let slice1 = min(slice0 + 1.0, n - 1.0);
+  let x = (c.r * (n - 1.0) + 0.5) / (n * n);
+  let y = (c.g * (n - 1.0) + 0.5) / n;
+  let g0 = textureSample(texture2, tx_sampler, vec2f(x + slice0 / n, y)).rgb;
+  let g1 = textureSample(texture2, tx_sampler, vec2f(x + slice1 / n, y)).rgb;
+  let graded = mix(g0, g1, slice - slice0);
+  return vec4f(mix(c, graded, params.y), 1.0);
+}
+
+// params: intensity, radius, smoothness, -
+@fragment
+fn fragmentVignette(input: VertOut) -> @location(0) vec4f {
+  let c = textureSample(texture1, tx_sampler, input.uv).rgb;
+  let d = distance(input.uv, vec2f(0.5));
+  let v = 1.0 - smoothstep(params.y - params.z, params.y, d);
+  return vec4f(c * mix(1.0, v, params.x), 1.0);
+}
+
+// params: gamma, -, -, -
+@fragment
+fn fragmentGamma(input: VertOut) -> @location(0) vec4f {
+  let c = textureSample(texture1, tx_sampler, input.uv).rgb;
+  return vec4f(pow(max(c, vec3f(0.0)), vec3f(1.0 / params.x)), 1.0);
+}
+
+// resolve weighted blended transparency, texture1 is accumulation and texture2 revealage
+@fragment
+fn fragmentOitComposite(input: VertOut) -> @location(0) vec4f {
+  let accum = textureSample(texture1, tx_sampler, input.uv);
+  let reveal = textureSample(texture2, tx_sampler, input.uv).r;
+  let c = accum.rgb / clamp(accum.a, 0.0001, 50000.0);
+  return vec4f(c, 1.0 - reveal);
+}
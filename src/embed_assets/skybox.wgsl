@@ -0,0 +1,41 @@
+// cubemap drawn behind the scene, using only the rotation of the camera
+@group(0) @binding(0) var<uniform> mvp: MVP;
+@group(0) @binding(1) var tx_sampler: sampler;
+@group(0) @binding(2) var texture1: texture_cube<f32>;
+
+struct MVP {
+  model: mat4x4<f32>,
+  view: mat4x4<f32>,
+  proj: mat4x4<f32>,
+}
+
+struct VertIn {
+  @location(0) pos: vec3f,
+  @location(1) uv: vec2f,
+  @location(2) normal: vec3f,
+}
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) dir: vec3f,
+}
+
+@vertex
+fn vertexMain(input: VertIn) -> VertOut {
+  var out: VertOut;
+  out.pos = vec4f(input.pos.xy, 1.0, 1.0);
+  // view space ray through vertex (offset for off-center projections), rotated back into world space
+  let view_dir = vec3f(
+    (input.pos.x + mvp.proj[2][0]) / mvp.proj[0][0],
+    (input.pos.y + mvp.proj[2][1]) / mvp.proj[1][1],
+    -1.0,
+  );
+  let rot = mat3x3f(mvp.view[0].xyz, mvp.view[1].xyz, mvp.view[2].xyz);
+  out.dir = transpose(rot) * view_dir;
+  return out;
+}
+
+@fragment
+fn fragmentMain(input: VertOut) -> @location(0) vec4f {
+  return vec4f(textureSample(texture1, tx_sampler, normalize(input.dir)).rgb, 1.0);
+}
\ No newline at end of file
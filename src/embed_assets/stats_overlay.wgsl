@@ -0,0 +1,61 @@
+// frame time graph drawn in screen space, see RStatsOverlay in wgpu_renderer/stats_overlay.rs
+#define CUSTOM_VERT_OUT
+#include "vertex.wgsl"
+
+// rect is (x, y, width, height) in pixels from the top left, screen is (width, height, target ms, max ms),
+// info is (sample count, oldest sample, _, _)
+struct Graph {
+  rect: vec4f,
+  screen: vec4f,
+  info: vec4u,
+  samples: array<vec4f, 16>,
+}
+
+@group(1) @binding(0) var<uniform> graph: Graph;
+
+struct VertOut {
+  @builtin(position) pos: vec4f,
+  @location(0) uv: vec2f,
+}
+
+@vertex
+fn vertexMain(input: VertIn) -> VertOut {
+  var out: VertOut;
+  let px = graph.rect.xy + input.uv * graph.rect.zw;
+  out.pos = vec4f(px.x / graph.screen.x * 2.0 - 1.0, 1.0 - px.y / graph.screen.y * 2.0, 0.0, 1.0);
+  out.uv = input.uv;
+  return out;
+}
+
+fn sample_at(i: u32) -> f32 {
+  let slot = (graph.info.y + i) % 64u;
+  return graph.samples[slot / 4u][slot % 4u];
+}
+
+@fragment
+fn fragmentMain(input: VertOut) -> @location(0) vec4f {
+  let target_ms = graph.screen.z;
+  let max_ms = graph.screen.w;
+  let height = 1.0 - input.uv.y;
+  let px = 1.0 / graph.rect.w;
+  // target frame time reference line
+  if (abs(height - target_ms / max_ms) < px) {
+    return vec4f(1.0, 1.0, 1.0, 0.6);
+  }
+  let count = graph.info.x;
+  let i = u32(input.uv.x * 64.0);
+  // newest samples on the right
+  if (i + count >= 64u) {
+    let ms = sample_at(i + count - 64u);
+    if (height < ms / max_ms) {
+      if (ms <= target_ms) {
+        return vec4f(0.2, 0.8, 0.3, 0.9);
+      }
+      if (ms <= target_ms * 2.0) {
+        return vec4f(0.9, 0.8, 0.2, 0.9);
+      }
+      return vec4f(0.9, 0.25, 0.2, 0.9);
+    }
+  }
+  return vec4f(0.0, 0.0, 0.0, 0.5);
+}
\ No newline at end of file
@@ -1,25 +1,6 @@
-@group(0) @binding(0) var<uniform> mvp: MVP;
-@group(0) @binding(1) var txSampler: sampler;
-@group(0) @binding(2) var texture1: texture_2d<f32>;
-@group(0) @binding(3) var texture2: texture_2d<f32>;
-
-struct MVP {
-  model: mat4x4<f32>,
-  view: mat4x4<f32>,
-  proj: mat4x4<f32>,
-}
-
-struct VertIn {
-  @location(0) pos: vec3f,
-  @location(1) uv: vec2f,
-  @location(2) normal: vec3f,
-}
-
-struct VertOut {
-  @builtin(position) pos: vec4f,
-  @location(0) uv: vec2f,
-  @location(1) normal: vec3f,
-}
+#include "mvp.wgsl"
+#include "textures.wgsl"
+#include "vertex.wgsl"
 
 @vertex
 fn vertexMain(input: VertIn) -> VertOut {
@@ -32,7 +13,7 @@ fn vertexMain(input: VertIn) -> VertOut {
 
 @fragment
 fn fragmentMain(input: VertOut) -> @location(0) vec4f {
-  var tx = textureSample(texture1, txSampler, input.uv);
+  var tx = textureSample(texture1, tx_sampler, input.uv);
   if (tx.a < 0.0001) {
     discard;
   }
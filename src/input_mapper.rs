@@ -23,7 +23,8 @@ struct MouseCache {
   forward: InputState,
   scroll: f32, // + for up, - for down
   position_can_update: bool,
-  last_position: PhysicalPosition<f64>
+  last_position: PhysicalPosition<f64>,
+  cursor_position: Option<PhysicalPosition<f64>>,
 }
 
 #[derive(Debug)]
@@ -31,6 +32,18 @@ pub struct InputCache {
   pub move_x: f32,
   pub move_y: f32,
   pub move_z: f32,
+  // held movement keys as -1/0/1 (right, up, backward)
+  pub key_x: f32,
+  pub key_y: f32,
+  pub key_z: f32,
+  // cursor movement in pixels + scroll lines (+ for up) since the last cleanup
+  pub cursor_dx: f32,
+  pub cursor_dy: f32,
+  pub scroll: f32,
+  pub left_held: bool,
+  pub right_held: bool,
+  pub middle_held: bool,
+  pub cursor_locked: bool,
   retain: bool
 }
 
@@ -61,12 +74,23 @@ impl InputHandler {
       forward: InputState::None,
       scroll: 0.0,
       position_can_update: true,
-      last_position: PhysicalPosition { x: 0.0, y: 0.0 }
+      last_position: PhysicalPosition { x: 0.0, y: 0.0 },
+      cursor_position: None,
     };
     let input_cache = InputCache {
       move_x: 0.0,
       move_y: 0.0,
       move_z: 0.0,
+      key_x: 0.0,
+      key_y: 0.0,
+      key_z: 0.0,
+      cursor_dx: 0.0,
+      cursor_dy: 0.0,
+      scroll: 0.0,
+      left_held: false,
+      right_held: false,
+      middle_held: false,
+      cursor_locked: false,
       retain: false,
     };
 
@@ -95,26 +119,38 @@ impl InputHandler {
           InputAction::Up => {
             if key_state == InputState::Press { self.input_cache.move_y += 5.0 }
             if key_state == InputState::Release { self.input_cache.move_y -= 5.0 }
+            if key_state == InputState::Press { self.input_cache.key_y += 1.0 }
+            if key_state == InputState::Release { self.input_cache.key_y -= 1.0 }
           }
           InputAction::Down => {
             if key_state == InputState::Press { self.input_cache.move_y += -5.0 }
             if key_state == InputState::Release { self.input_cache.move_y -= -5.0 }
+            if key_state == InputState::Press { self.input_cache.key_y += -1.0 }
+            if key_state == InputState::Release { self.input_cache.key_y -= -1.0 }
           }
           InputAction::Left => {
             if key_state == InputState::Press { self.input_cache.move_x += -5.0 }
             if key_state == InputState::Release { self.input_cache.move_x -= -5.0 }
+            if key_state == InputState::Press { self.input_cache.key_x += -1.0 }
+            if key_state == InputState::Release { self.input_cache.key_x -= -1.0 }
           }
           InputAction::Right => {
             if key_state == InputState::Press { self.input_cache.move_x += 5.0 }
             if key_state == InputState::Release { self.input_cache.move_x -= 5.0 }
+            if key_state == InputState::Press { self.input_cache.key_x += 1.0 }
+            if key_state == InputState::Release { self.input_cache.key_x -= 1.0 }
           }
           InputAction::Fwd => {
             if key_state == InputState::Press { self.input_cache.move_z += -5.0 }
             if key_state == InputState::Release { self.input_cache.move_z -= -5.0 }
+            if key_state == InputState::Press { self.input_cache.key_z += -1.0 }
+            if key_state == InputState::Release { self.input_cache.key_z -= -1.0 }
           }
           InputAction::Bkwd => {
             if key_state == InputState::Press { self.input_cache.move_z += 5.0 }
             if key_state == InputState::Release { self.input_cache.move_z -= 5.0 }
+            if key_state == InputState::Press { self.input_cache.key_z += 1.0 }
+            if key_state == InputState::Release { self.input_cache.key_z -= 1.0 }
           }
         }
         self.input_cache.retain = true;
@@ -128,14 +164,17 @@ impl InputHandler {
       MouseButton::Left => {
         if state == ElementState::Pressed { self.mouse_cache.left = InputState::Press }
         else if state == ElementState::Released { self.mouse_cache.left = InputState::Release }
+        self.input_cache.left_held = state == ElementState::Pressed;
       }
       MouseButton::Right => {
         if state == ElementState::Pressed { self.mouse_cache.right = InputState::Press }
         else if state == ElementState::Released { self.mouse_cache.right = InputState::Release }
+        self.input_cache.right_held = state == ElementState::Pressed;
       }
       MouseButton::Middle => {
         if state == ElementState::Pressed { self.mouse_cache.middle = InputState::Press }
         else if state == ElementState::Released { self.mouse_cache.middle = InputState::Release }
+        self.input_cache.middle_held = state == ElementState::Pressed;
       }
       MouseButton::Forward => {
         if state == ElementState::Pressed { self.mouse_cache.forward = InputState::Press }
@@ -153,6 +192,7 @@ impl InputHandler {
     match delta {
       MouseScrollDelta::LineDelta(_x, y) => {
         self.mouse_cache.scroll = y;
+        self.input_cache.scroll += y;
         self.input_cache.move_z = -8.0 * y;
         self.input_cache.retain = false;
       }
@@ -161,6 +201,12 @@ impl InputHandler {
   }
 
   pub fn winit_cursor_event(&mut self, position: PhysicalPosition<f64>) {
+    // every movement counts towards camera controllers
+    if let Some(last) = self.mouse_cache.cursor_position {
+      self.input_cache.cursor_dx += (position.x - last.x) as f32;
+      self.input_cache.cursor_dy += (position.y - last.y) as f32;
+    }
+    self.mouse_cache.cursor_position = Some(position);
     if self.mouse_cache.position_can_update {
       let delta_x: f64 = position.x - self.mouse_cache.last_position.x;
       let delta_y: f64 = position.y - self.mouse_cache.last_position.y;
@@ -191,6 +237,9 @@ impl InputHandler {
     else if self.mouse_cache.forward == InputState::Release { self.mouse_cache.forward = InputState::None }
     self.mouse_cache.scroll = 0.0;
     self.mouse_cache.position_can_update = true;
+    self.input_cache.cursor_dx = 0.0;
+    self.input_cache.cursor_dy = 0.0;
+    self.input_cache.scroll = 0.0;
     // clean up input cache
     if !self.input_cache.retain {
       self.input_cache.move_x = 0.0;
@@ -199,6 +248,14 @@ impl InputHandler {
     }
   }
 
+  // center is where the cursor gets warped back to while locked, so warping is not read as movement
+  pub fn set_cursor_lock(&mut self, center: Option<PhysicalPosition<f64>>) {
+    self.input_cache.cursor_locked = center.is_some();
+    if center.is_some() {
+      self.mouse_cache.cursor_position = center;
+    }
+  }
+
   pub fn output(&self) -> &InputCache {
     &self.input_cache
   }
@@ -12,6 +12,7 @@ use winit::window::{Window, WindowId, CursorGrabMode};
 mod wgpu_renderer;
 mod app;
 mod input_mapper;
+mod camera_controller;
 
 use wgpu_renderer::Renderer;
 use app::AppEventLoop;
@@ -143,6 +144,7 @@ impl ApplicationHandler for ControlFlowApp<'_> {
 						if let Some(win) = &self.window {
 							let x = self.window_size.0 / 2.0;
 							let y = self.window_size.1 / 2.0;
+							let center = PhysicalPosition{ x: x as f64, y: y as f64 };
 							if state == ElementState::Pressed && !repeat {
 								println!("lock cursor");
 								win.set_cursor_grab(CursorGrabMode::Confined).unwrap();
@@ -155,6 +157,11 @@ impl ApplicationHandler for ControlFlowApp<'_> {
 							} else {
 								win.set_cursor_position(PhysicalPosition{ x, y }).unwrap();
 							}
+							// warping back to center is not camera movement
+							if let Some(app_base) = &mut self.app_event_loop {
+								let lock = if state == ElementState::Pressed { Some(center) } else { None };
+								app_base.input_handler.set_cursor_lock(lock);
+							}
 						}
 					}
 					_ => ()
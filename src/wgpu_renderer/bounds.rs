@@ -0,0 +1,314 @@
+#![allow(dead_code)]
+use super::{Mat4, Vec3, RCamera, RObjectId};
+
+// axis aligned bounding box + enclosing sphere, in object space unless transformed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RBounds {
+  pub min: [f32; 3],
+  pub max: [f32; 3],
+  pub center: [f32; 3],
+  pub radius: f32,
+}
+impl RBounds {
+  // returns None without any position
+  pub fn from_positions(positions: &[[f32; 3]]) -> Option<Self> {
+    let first = positions.first()?;
+    let mut min = *first;
+    let mut max = *first;
+    for p in positions {
+      for i in 0..3 {
+        min[i] = f32::min(min[i], p[i]);
+        max[i] = f32::max(max[i], p[i]);
+      }
+    }
+    let center = [(min[0] + max[0]) * 0.5, (min[1] + max[1]) * 0.5, (min[2] + max[2]) * 0.5];
+    let radius = positions.iter()
+      .map(|p| Vec3::magnitude(&Vec3::subtract(p, &center)))
+      .fold(0.0, f32::max);
+    Some(Self { min, max, center, radius })
+  }
+
+  pub fn corners(&self) -> [[f32; 3]; 8] {
+    let (a, b) = (self.min, self.max);
+    [
+      [a[0], a[1], a[2]], [b[0], a[1], a[2]], [a[0], b[1], a[2]], [b[0], b[1], a[2]],
+      [a[0], a[1], b[2]], [b[0], a[1], b[2]], [a[0], b[1], b[2]], [b[0], b[1], b[2]],
+    ]
+  }
+
+  // bounds enclosing the transformed box, ie world space bounds from a model matrix
+  pub fn transform(&self, mat: &[f32; 16]) -> RBounds {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for c in self.corners() {
+      let p = Mat4::multiply_vec4(mat, &[c[0], c[1], c[2], 1.0]);
+      for i in 0..3 {
+        min[i] = f32::min(min[i], p[i]);
+        max[i] = f32::max(max[i], p[i]);
+      }
+    }
+    let c = Mat4::multiply_vec4(mat, &[self.center[0], self.center[1], self.center[2], 1.0]);
+    // sphere grows with the largest axis scale
+    let scale = (0..3)
+      .map(|col| Vec3::magnitude(&[mat[col * 4], mat[col * 4 + 1], mat[col * 4 + 2]]))
+      .fold(0.0, f32::max);
+    RBounds { min, max, center: [c[0], c[1], c[2]], radius: self.radius * scale }
+  }
+}
+
+// clip volume planes (left, right, bottom, top, near, far) as normalized [a, b, c, d],
+// with points inside satisfying a*x + b*y + c*z + d >= 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RFrustum {
+  pub planes: [[f32; 4]; 6],
+}
+impl RFrustum {
+  // extract planes from a column major view projection matrix, with depth in [0, 1]
+  pub fn from_matrix(view_proj: &[f32; 16]) -> Self {
+    let row = |i: usize| [view_proj[i], view_proj[4 + i], view_proj[8 + i], view_proj[12 + i]];
+    let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+    let add = |a: [f32; 4], b: [f32; 4], s: f32| [a[0] + s * b[0], a[1] + s * b[1], a[2] + s * b[2], a[3] + s * b[3]];
+    let mut planes = [
+      add(r3, r0, 1.0),
+      add(r3, r0, -1.0),
+      add(r3, r1, 1.0),
+      add(r3, r1, -1.0),
+      r2,
+      add(r3, r2, -1.0),
+    ];
+    for p in &mut planes {
+      let n = Vec3::magnitude(&[p[0], p[1], p[2]]);
+      if n > 0.0 {
+        *p = [p[0] / n, p[1] / n, p[2] / n, p[3] / n];
+      }
+    }
+    Self { planes }
+  }
+
+  pub fn from_camera(cam: &RCamera, width: u32, height: u32) -> Self {
+    let view_proj = Mat4::multiply(&cam.proj_matrix(width, height), &cam.view_matrix());
+    Self::from_matrix(&view_proj)
+  }
+
+  pub fn intersects_sphere(&self, center: &[f32; 3], radius: f32) -> bool {
+    self.planes.iter().all(|p| p[0] * center[0] + p[1] * center[1] + p[2] * center[2] + p[3] >= -radius)
+  }
+
+  // conservative, boxes near frustum corners may pass while outside
+  pub fn intersects_aabb(&self, min: &[f32; 3], max: &[f32; 3]) -> bool {
+    self.planes.iter().all(|p| {
+      // corner furthest along the plane normal
+      let x = if p[0] >= 0.0 { max[0] } else { min[0] };
+      let y = if p[1] >= 0.0 { max[1] } else { min[1] };
+      let z = if p[2] >= 0.0 { max[2] } else { min[2] };
+      p[0] * x + p[1] * y + p[2] * z + p[3] >= 0.0
+    })
+  }
+
+  pub fn intersects_bounds(&self, bounds: &RBounds) -> bool {
+    self.intersects_sphere(&bounds.center, bounds.radius) && self.intersects_aabb(&bounds.min, &bounds.max)
+  }
+}
+
+// half line from origin along direction, see RCamera::screen_to_ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RRay {
+  pub origin: [f32; 3],
+  pub direction: [f32; 3],
+}
+impl RRay {
+  pub fn new(origin: [f32; 3], direction: [f32; 3]) -> Self {
+    Self { origin, direction: Vec3::normalize(&direction) }
+  }
+
+  pub fn at(&self, t: f32) -> [f32; 3] {
+    [self.origin[0] + self.direction[0] * t, self.origin[1] + self.direction[1] * t, self.origin[2] + self.direction[2] * t]
+  }
+
+  // ray in another space, ie object space from an inverse model matrix
+  // note: direction is not renormalized, so distances along the ray stay in the original units
+  pub fn transform(&self, mat: &[f32; 16]) -> RRay {
+    let o = Mat4::multiply_vec4(mat, &[self.origin[0], self.origin[1], self.origin[2], 1.0]);
+    let d = Mat4::multiply_vec4(mat, &[self.direction[0], self.direction[1], self.direction[2], 0.0]);
+    RRay { origin: [o[0], o[1], o[2]], direction: [d[0], d[1], d[2]] }
+  }
+
+  // distance to where the ray enters the box, 0 when starting inside
+  pub fn intersect_aabb(&self, min: &[f32; 3], max: &[f32; 3]) -> Option<f32> {
+    let mut t_min = 0.0f32;
+    let mut t_max = f32::MAX;
+    for i in 0..3 {
+      if self.direction[i] == 0.0 {
+        if self.origin[i] < min[i] || self.origin[i] > max[i] { return None; }
+        continue;
+      }
+      let inv = 1.0 / self.direction[i];
+      let t0 = (min[i] - self.origin[i]) * inv;
+      let t1 = (max[i] - self.origin[i]) * inv;
+      t_min = f32::max(t_min, f32::min(t0, t1));
+      t_max = f32::min(t_max, f32::max(t0, t1));
+      if t_min > t_max { return None; }
+    }
+    Some(t_min)
+  }
+
+  // distance to a triangle hit from either side (Moller-Trumbore)
+  pub fn intersect_triangle(&self, a: &[f32; 3], b: &[f32; 3], c: &[f32; 3]) -> Option<f32> {
+    let e1 = Vec3::subtract(b, a);
+    let e2 = Vec3::subtract(c, a);
+    let p = Vec3::cross(&self.direction, &e2);
+    let det = Vec3::dot(&e1, &p);
+    if det.abs() < f32::EPSILON { return None; }
+    let inv = 1.0 / det;
+    let s = Vec3::subtract(&self.origin, a);
+    let u = Vec3::dot(&s, &p) * inv;
+    if !(0.0..=1.0).contains(&u) { return None; }
+    let q = Vec3::cross(&s, &e1);
+    let v = Vec3::dot(&self.direction, &q) * inv;
+    if v < 0.0 || u + v > 1.0 { return None; }
+    let t = Vec3::dot(&e2, &q) * inv;
+    if t >= 0.0 { Some(t) } else { None }
+  }
+
+  // closest triangle of a mesh, indices empty for an unindexed triangle list
+  pub fn intersect_mesh(&self, positions: &[[f32; 3]], indices: &[u32]) -> Option<f32> {
+    let triangle = |i: usize| match indices.is_empty() {
+      true => [i * 3, i * 3 + 1, i * 3 + 2],
+      false => [indices[i * 3] as usize, indices[i * 3 + 1] as usize, indices[i * 3 + 2] as usize],
+    };
+    let count = if indices.is_empty() { positions.len() / 3 } else { indices.len() / 3 };
+    (0..count)
+      .filter_map(|i| {
+        let [a, b, c] = triangle(i);
+        self.intersect_triangle(&positions[a], &positions[b], &positions[c])
+      })
+      .reduce(f32::min)
+  }
+}
+
+// closest object along a ray, see Renderer::pick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RPickHit {
+  pub object_id: RObjectId,
+  pub distance: f32,
+  pub point: [f32; 3],
+}
+
+#[cfg(test)]
+mod bounds_tests {
+  use super::*;
+
+  #[test]
+  fn bounds_from_positions() {
+    let b = RBounds::from_positions(&[[-1.0, 0.0, 2.0], [3.0, -2.0, 2.0], [1.0, 2.0, 4.0]]).unwrap();
+    assert_eq!(b.min, [-1.0, -2.0, 2.0]);
+    assert_eq!(b.max, [3.0, 2.0, 4.0]);
+    assert_eq!(b.center, [1.0, 0.0, 3.0]);
+    assert_eq!(b.radius, 3.0);
+    assert!(RBounds::from_positions(&[]).is_none());
+    // translated + scaled
+    let mat = Mat4::multiply(&Mat4::translate(10.0, 0.0, 0.0), &Mat4::scale(2.0, 1.0, 1.0));
+    let t = b.transform(&mat);
+    assert_eq!(t.min, [8.0, -2.0, 2.0]);
+    assert_eq!(t.max, [16.0, 2.0, 4.0]);
+    assert_eq!(t.center, [12.0, 0.0, 3.0]);
+    assert_eq!(t.radius, b.radius * 2.0);
+  }
+
+  #[test]
+  fn frustum_culls_outside() {
+    let mut cam = RCamera::new_persp(60.0, 1.0, 100.0);
+    cam.position = [0.0, 0.0, 10.0];
+    let f = RFrustum::from_camera(&cam, 800, 600);
+    let unit = RBounds::from_positions(&[[-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]]).unwrap();
+    let at = |x: f32, y: f32, z: f32| unit.transform(&Mat4::translate(x, y, z));
+    assert!(f.intersects_bounds(&at(0.0, 0.0, 0.0)));
+    // behind the camera, beyond the far plane, off to the side
+    assert!(!f.intersects_bounds(&at(0.0, 0.0, 20.0)));
+    assert!(!f.intersects_bounds(&at(0.0, 0.0, -100.0)));
+    assert!(!f.intersects_bounds(&at(30.0, 0.0, 0.0)));
+    assert!(!f.intersects_bounds(&at(0.0, 10.0, 0.0)));
+    // partially inside
+    assert!(f.intersects_bounds(&at(0.0, 0.0, -90.5)));
+    assert!(f.intersects_bounds(&at(7.5, 0.0, 0.0)));
+    // ortho frustum spans the window in pixels
+    let f = RFrustum::from_camera(&RCamera::new_ortho(0.0, 1000.0), 800, 600);
+    assert!(f.intersects_bounds(&at(400.5, 0.0, 0.0)));
+    assert!(!f.intersects_bounds(&at(402.0, 0.0, 0.0)));
+    assert!(!f.intersects_bounds(&at(0.0, 0.0, 102.0)));
+  }
+
+  #[test]
+  fn ray_intersections() {
+    let ray = RRay::new([0.0, 0.0, 10.0], [0.0, 0.0, -2.0]);
+    assert_eq!(ray.direction, [0.0, 0.0, -1.0]);
+    assert_eq!(ray.intersect_aabb(&[-1.0, -1.0, -1.0], &[1.0, 1.0, 1.0]), Some(9.0));
+    assert_eq!(ray.intersect_aabb(&[2.0, -1.0, -1.0], &[3.0, 1.0, 1.0]), None);
+    // starting inside, box behind
+    assert_eq!(RRay::new([0.0; 3], [1.0, 0.0, 0.0]).intersect_aabb(&[-1.0; 3], &[1.0; 3]), Some(0.0));
+    assert_eq!(ray.intersect_aabb(&[-1.0, -1.0, 11.0], &[1.0, 1.0, 12.0]), None);
+    let (a, b, c) = ([-1.0, -1.0, 2.0], [1.0, -1.0, 2.0], [0.0, 1.0, 2.0]);
+    assert_eq!(ray.intersect_triangle(&a, &b, &c), Some(8.0));
+    assert_eq!(ray.intersect_triangle(&[2.0, 0.0, 0.0], &[3.0, 0.0, 0.0], &[2.0, 1.0, 0.0]), None);
+    // closest of an indexed + unindexed mesh
+    let positions = [a, b, c, [-1.0, -1.0, 5.0], [1.0, -1.0, 5.0], [0.0, 1.0, 5.0]];
+    assert_eq!(ray.intersect_mesh(&positions, &[0, 1, 2, 3, 4, 5]), Some(5.0));
+    assert_eq!(ray.intersect_mesh(&positions, &[]), Some(5.0));
+    // distances stay in world units through an object transform
+    let model = Mat4::multiply(&Mat4::translate(0.0, 0.0, -4.0), &Mat4::scale(2.0, 2.0, 2.0));
+    let local = ray.transform(&Mat4::inverse(&model));
+    let t = local.intersect_triangle(&a, &b, &c).unwrap();
+    assert!((t - 10.0).abs() < 1e-4);
+  }
+
+  #[test]
+  fn screen_projection_round_trip() {
+    let mut cam = RCamera::new_persp(60.0, 1.0, 100.0);
+    cam.position = [0.0, 0.0, 10.0];
+    // center pixel looks straight ahead
+    let ray = cam.screen_to_ray(400.0, 300.0, 800, 600);
+    assert!(Vec3::magnitude(&Vec3::subtract(&ray.direction, &[0.0, 0.0, -1.0])) < 1e-4);
+    let p = [2.0, 1.0, -5.0];
+    let screen = cam.world_to_screen(&p, 800, 600).unwrap();
+    assert!(screen[0] > 400.0 && screen[1] < 300.0);
+    assert!(screen[2] > 0.0 && screen[2] < 1.0);
+    let ray = cam.screen_to_ray(screen[0], screen[1], 800, 600);
+    let t = Vec3::magnitude(&Vec3::subtract(&p, &ray.origin));
+    assert!(Vec3::magnitude(&Vec3::subtract(&ray.at(t), &p)) < 1e-2);
+    assert!(cam.world_to_screen(&[0.0, 0.0, 20.0], 800, 600).is_none());
+    // ortho cameras map pixels to world units
+    let cam = RCamera::new_ortho(0.0, 1000.0);
+    let ray = cam.screen_to_ray(500.0, 100.0, 800, 600);
+    assert!((ray.origin[0] - 100.0).abs() < 1e-3 && (ray.origin[1] - 200.0).abs() < 1e-3);
+    let screen = cam.world_to_screen(&[100.0, 200.0, 0.0], 800, 600).unwrap();
+    assert!((screen[0] - 500.0).abs() < 1e-3 && (screen[1] - 100.0).abs() < 1e-3);
+  }
+
+  #[test]
+  fn custom_projections() {
+    // ortho extents in world units, zoom halves the visible area around its center
+    let mut cam = RCamera::new_ortho(0.0, 1000.0);
+    cam.extents = Some([0.0, 20.0, 0.0, 10.0]);
+    let screen = cam.world_to_screen(&[5.0, 5.0, 0.0], 800, 600).unwrap();
+    assert!((screen[0] - 200.0).abs() < 1e-3 && (screen[1] - 300.0).abs() < 1e-3);
+    cam.zoom = 2.0;
+    let screen = cam.world_to_screen(&[5.0, 5.0, 0.0], 800, 600).unwrap();
+    assert!((screen[0] - 0.0).abs() < 1e-3 && (screen[1] - 300.0).abs() < 1e-3);
+    assert!((cam.screen_size(&[0.0; 3], 1.0, 600) - 0.4).abs() < 1e-5);
+    // off-center frustum with an infinite far plane still produces rays + culls nothing far away
+    let mut cam = RCamera::new_frustum(0.0, 1.0, -0.5, 0.5, 1.0, f32::INFINITY);
+    cam.position = [0.0, 0.0, 10.0];
+    let ray = cam.screen_to_ray(0.0, 300.0, 800, 600);
+    assert!(Vec3::magnitude(&Vec3::subtract(&ray.direction, &[0.0, 0.0, -1.0])) < 1e-4);
+    let f = RFrustum::from_camera(&cam, 800, 600);
+    let far = RBounds::from_positions(&[[1000.0, 0.0, -5000.0], [1002.0, 1.0, -5002.0]]).unwrap();
+    assert!(f.intersects_bounds(&far));
+    assert!(!f.intersects_bounds(&far.transform(&Mat4::translate(-2000.0, 0.0, 0.0))));
+    // oblique clip plane at z = 0 culls everything between the camera and the plane
+    cam.clip_plane = Some([0.0, 0.0, -1.0, 0.0]);
+    let f = RFrustum::from_camera(&cam, 800, 600);
+    let between = RBounds::from_positions(&[[0.1, -0.1, 4.0], [0.2, 0.1, 5.0]]).unwrap();
+    assert!(!f.intersects_bounds(&between));
+    assert!(f.intersects_bounds(&between.transform(&Mat4::translate(0.0, 0.0, -10.0))));
+  }
+}
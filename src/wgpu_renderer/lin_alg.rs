@@ -18,12 +18,11 @@ impl Mat4 {
       0.0, 0.0, 0.0, 1.0
     ]
   }
+  // far of f32::INFINITY leaves out the far plane
   pub fn perspective(fov_y: f32, aspect_ratio: f32, near: f32, far: f32) -> [f32; 16] {
     let f = f32::tan(PI * 0.5 - 0.5 * fov_y * PI / 180.0);
-    let range = 1.0 / (near - far);
     let a = f / aspect_ratio;
-    let c = far * range;
-    let d = near * far * range;
+    let (c, d) = Mat4::perspective_depth(near, far);
     [
       a, 0.0, 0.0, 0.0,
       0.0, f, 0.0, 0.0,
@@ -31,6 +30,49 @@ impl Mat4 {
       0.0, 0.0, d, 0.0
     ]
   }
+  // off-center perspective from the bounds of the near plane, ie per eye vr or portal views
+  pub fn frustum(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> [f32; 16] {
+    let a = 2.0 * near / (right - left);
+    let b = 2.0 * near / (top - bottom);
+    let e = (right + left) / (right - left);
+    let g = (top + bottom) / (top - bottom);
+    let (c, d) = Mat4::perspective_depth(near, far);
+    [
+      a, 0.0, 0.0, 0.0,
+      0.0, b, 0.0, 0.0,
+      e, g, c, -1.0,
+      0.0, 0.0, d, 0.0
+    ]
+  }
+  fn perspective_depth(near: f32, far: f32) -> (f32, f32) {
+    if far.is_infinite() {
+      return (-1.0, -near);
+    }
+    let range = 1.0 / (near - far);
+    (far * range, near * far * range)
+  }
+  // replaces the near plane of a projection with a view space plane (a, b, c, d), ie a portal or mirror.
+  // the far plane is tilted to keep depth in 0..1, trading away some depth precision
+  // note: the camera has to be on the negative side of the plane (d < 0)
+  pub fn oblique(proj: &[f32; 16], plane: &[f32; 4]) -> [f32; 16] {
+    // view space frustum corner opposite of the plane
+    let q = Mat4::multiply_vec4(&Mat4::inverse(proj), &[plane[0].signum(), plane[1].signum(), 1.0, 1.0]);
+    let s = 1.0 / (plane[0] * q[0] + plane[1] * q[1] + plane[2] * q[2] + plane[3] * q[3]);
+    let mut out = *proj;
+    for i in 0..4 {
+      out[i * 4 + 2] = plane[i] * s;
+    }
+    out
+  }
+  // flips depth from 0..1 to 1..0 after a projection, ie multiply(&reverse_z(), &proj)
+  pub fn reverse_z() -> [f32; 16] {
+    [
+      1.0, 0.0, 0.0, 0.0,
+      0.0, 1.0, 0.0, 0.0,
+      0.0, 0.0, -1.0, 0.0,
+      0.0, 0.0, 1.0, 1.0
+    ]
+  }
   pub fn ortho(left: f32, right: f32, top: f32, bottom: f32, near: f32, far: f32) -> [f32; 16] {
     let a = 2.0 / (right - left);
     let b = 2.0 / (top - bottom);
@@ -352,6 +394,40 @@ mod lin_alg_tests {
     ]);
   }
   #[test]
+  fn mat4_frustum() {
+    // symmetric frustum matches perspective
+    let f = f32::tan(40.0 * PI / 180.0);
+    let o = Mat4::frustum(-f * 1.5, f * 1.5, -f, f, 1.0, 1000.0);
+    let p = Mat4::perspective(80.0, 1.5, 1.0, 1000.0);
+    for i in 0..16 {
+      assert!((o[i] - p[i]).abs() < 1e-5);
+    }
+    // off-center near plane corner lands on the clip space corner
+    let o = Mat4::frustum(0.0, 2.0, -1.0, 3.0, 1.0, f32::INFINITY);
+    let c = Mat4::multiply_vec4(&o, &[2.0, 3.0, -1.0, 1.0]);
+    assert_eq!([c[0] / c[3], c[1] / c[3], c[2] / c[3]], [1.0, 1.0, 0.0]);
+    // infinite far plane approaches depth 1
+    let c = Mat4::multiply_vec4(&o, &[0.0, 0.0, -1e6, 1.0]);
+    assert!(c[2] / c[3] < 1.0 && c[2] / c[3] > 0.999);
+  }
+  #[test]
+  fn mat4_oblique_and_reverse_z() {
+    let p = Mat4::perspective(60.0, 1.0, 1.0, 100.0);
+    // near plane at z = -5, tilted
+    let plane = [0.0, 0.6, -0.8, -5.0];
+    let o = Mat4::oblique(&p, &plane);
+    let depth = |m: &[f32; 16], v: [f32; 4]| { let c = Mat4::multiply_vec4(m, &v); c[2] / c[3] };
+    // points on the plane land on the near plane, points in front of it are clipped
+    assert!(depth(&o, [0.0, 0.0, -6.25, 1.0]).abs() < 1e-4);
+    assert!(depth(&o, [0.0, 3.0, -4.0, 1.0]).abs() < 1e-4);
+    assert!(depth(&o, [0.0, 0.0, -6.0, 1.0]) < 0.0);
+    assert!(depth(&o, [0.0, 0.0, -20.0, 1.0]) > 0.0);
+    // reversed depth
+    let r = Mat4::multiply(&Mat4::reverse_z(), &p);
+    assert!((depth(&r, [0.0, 0.0, -1.0, 1.0]) - 1.0).abs() < 1e-5);
+    assert!(depth(&r, [0.0, 0.0, -100.0, 1.0]).abs() < 1e-5);
+  }
+  #[test]
   fn mat4_rotate1() {
     let a = Mat4::rotate(&[0.0, 0.0, 1.0], 30.0);
     let b = Mat4::rotate_euler(0.0, 0.0, 30.0);
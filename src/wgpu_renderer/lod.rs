@@ -0,0 +1,304 @@
+#![allow(dead_code)]
+use std::{cmp::Ordering, collections::{BinaryHeap, HashMap}};
+use super::{RVertex, Vec3};
+
+// level of detail for a metric growing with distance (camera distance, or inverse screen size),
+// thresholds[i] is where level i + 1 starts. levels only change once the metric is past
+// the threshold by the hysteresis ratio, to avoid popping back and forth at the boundary
+pub fn select_lod(metric: f32, thresholds: &[f32], current: usize, hysteresis: f32) -> usize {
+  let mut level = usize::min(current, thresholds.len());
+  while level < thresholds.len() && metric > thresholds[level] * (1.0 + hysteresis) {
+    level += 1;
+  }
+  while level > 0 && metric < thresholds[level - 1] * (1.0 - hysteresis) {
+    level -= 1;
+  }
+  level
+}
+
+// quadric error metric simplification (Garland & Heckbert), collapsing edges until the
+// triangle count drops to target_ratio of the input
+// - vertices sharing a position are welded, so uv/normal seams do not tear open
+// - open borders are kept in place and collapses flipping a triangle are skipped,
+//   so the result can stay above the target count
+// - vertices keep their uv + normal when moved onto the collapsed position
+// note: empty indices treat vertices as a plain triangle list (ie ModelLoader::load_obj output)
+pub fn simplify_mesh(vertices: &[RVertex], indices: &[u32], target_ratio: f32) -> (Vec<RVertex>, Vec<u32>) {
+  let indices: Vec<u32> = match indices.is_empty() {
+    true => (0..vertices.len() as u32).collect(),
+    false => indices.to_vec(),
+  };
+  let tri_count = indices.len() / 3;
+  let target = (tri_count as f32 * target_ratio.clamp(0.0, 1.0)).ceil() as usize;
+
+  // weld corners by position
+  let mut positions: Vec<[f32; 3]> = Vec::new();
+  let mut vertex_pos: Vec<usize> = Vec::with_capacity(vertices.len());
+  {
+    let mut sorted: Vec<usize> = (0..vertices.len()).collect();
+    let key = |v: &RVertex| v.position.map(f32::to_bits);
+    sorted.sort_by_key(|i| key(&vertices[*i]));
+    vertex_pos.resize(vertices.len(), 0);
+    for (n, i) in sorted.iter().enumerate() {
+      if n == 0 || key(&vertices[sorted[n - 1]]) != key(&vertices[*i]) {
+        positions.push(vertices[*i].position);
+      }
+      vertex_pos[*i] = positions.len() - 1;
+    }
+  }
+  let mut tris: Vec<[usize; 3]> = indices.chunks_exact(3)
+    .map(|t| [vertex_pos[t[0] as usize], vertex_pos[t[1] as usize], vertex_pos[t[2] as usize]])
+    .collect();
+  let mut tri_alive: Vec<bool> = tris.iter().map(|t| t[0] != t[1] && t[1] != t[2] && t[0] != t[2]).collect();
+  let mut alive_count = tri_alive.iter().filter(|a| **a).count();
+
+  // per position quadrics, triangles and neighbours
+  let mut quadrics = vec![Quadric::default(); positions.len()];
+  let mut pos_tris: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+  let mut edges: Vec<(usize, usize)> = Vec::new();
+  for (t, tri) in tris.iter().enumerate() {
+    if !tri_alive[t] { continue; }
+    let q = Quadric::from_triangle(&positions[tri[0]], &positions[tri[1]], &positions[tri[2]]);
+    for k in 0..3 {
+      quadrics[tri[k]].add(&q);
+      pos_tris[tri[k]].push(t);
+      let (a, b) = (tri[k], tri[(k + 1) % 3]);
+      edges.push((usize::min(a, b), usize::max(a, b)));
+    }
+  }
+  edges.sort_unstable();
+  // edges used by a single triangle are on an open border
+  let mut locked = vec![false; positions.len()];
+  let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+  let mut e = 0;
+  while e < edges.len() {
+    let run = edges[e..].iter().take_while(|x| **x == edges[e]).count();
+    let (a, b) = edges[e];
+    if run == 1 {
+      locked[a] = true;
+      locked[b] = true;
+    }
+    neighbours[a].push(b);
+    neighbours[b].push(a);
+    e += run;
+  }
+
+  let mut version = vec![0u32; positions.len()];
+  let mut removed = vec![false; positions.len()];
+  let mut heap: BinaryHeap<Collapse> = BinaryHeap::new();
+  let push = |heap: &mut BinaryHeap<Collapse>, quadrics: &[Quadric], version: &[u32], a: usize, b: usize| {
+    let q = quadrics[a].sum(&quadrics[b]);
+    // move the unlocked vertex onto the other one, cheapest direction first
+    let mut options = Vec::with_capacity(2);
+    if !locked[a] { options.push((q.error(&positions[b]), a, b)); }
+    if !locked[b] { options.push((q.error(&positions[a]), b, a)); }
+    if let Some((cost, from, to)) = options.into_iter().min_by(|x, y| x.0.total_cmp(&y.0)) {
+      heap.push(Collapse { cost, from, to, versions: [version[from], version[to]] });
+    }
+  };
+  for (a, nbs) in neighbours.iter().enumerate() {
+    for b in nbs.iter().copied().filter(|b| *b > a) {
+      push(&mut heap, &quadrics, &version, a, b);
+    }
+  }
+
+  while alive_count > target {
+    let c = match heap.pop() {
+      Some(c) => c,
+      None => break,
+    };
+    if removed[c.from] || removed[c.to] || c.versions != [version[c.from], version[c.to]] { continue; }
+    if collapse_flips(&tris, &tri_alive, &pos_tris[c.from], &positions, c.from, c.to) { continue; }
+    // triangles sharing the edge disappear, the rest are reconnected to the kept vertex
+    let moved = std::mem::take(&mut pos_tris[c.from]);
+    for t in moved {
+      if !tri_alive[t] { continue; }
+      if tris[t].contains(&c.to) {
+        tri_alive[t] = false;
+        alive_count -= 1;
+        continue;
+      }
+      for p in tris[t].iter_mut().filter(|p| **p == c.from) {
+        *p = c.to;
+      }
+      pos_tris[c.to].push(t);
+    }
+    removed[c.from] = true;
+    let q = quadrics[c.from];
+    quadrics[c.to].add(&q);
+    version[c.to] += 1;
+    let old = std::mem::take(&mut neighbours[c.from]);
+    for n in old {
+      neighbours[n].retain(|x| *x != c.from);
+      if n != c.to && !neighbours[c.to].contains(&n) {
+        neighbours[c.to].push(n);
+        neighbours[n].push(c.to);
+      }
+    }
+    for n in neighbours[c.to].clone() {
+      push(&mut heap, &quadrics, &version, c.to, n);
+    }
+  }
+
+  // emit remaining triangles, dropping unused vertices and merging the ones made identical
+  let mut unique: HashMap<[u32; 8], u32> = HashMap::new();
+  let mut out_vertices: Vec<RVertex> = Vec::new();
+  let mut out_indices: Vec<u32> = Vec::with_capacity(alive_count * 3);
+  for (t, corners) in indices.chunks_exact(3).enumerate() {
+    if !tri_alive[t] { continue; }
+    for (k, i) in corners.iter().enumerate() {
+      let v = RVertex { position: positions[tris[t][k]], ..vertices[*i as usize] };
+      let key: [u32; 8] = bytemuck::cast(v);
+      let n = *unique.entry(key).or_insert_with(|| {
+        out_vertices.push(v);
+        out_vertices.len() as u32 - 1
+      });
+      out_indices.push(n);
+    }
+  }
+  (out_vertices, out_indices)
+}
+
+// true if moving position `from` onto `to` turns any remaining triangle around
+fn collapse_flips(tris: &[[usize; 3]], alive: &[bool], around: &[usize], positions: &[[f32; 3]], from: usize, to: usize) -> bool {
+  around.iter().filter(|t| alive[**t] && !tris[**t].contains(&to)).any(|t| {
+    let p = tris[*t].map(|i| positions[i]);
+    let q = tris[*t].map(|i| if i == from { positions[to] } else { positions[i] });
+    let before = Vec3::cross(&Vec3::subtract(&p[1], &p[0]), &Vec3::subtract(&p[2], &p[0]));
+    let after = Vec3::cross(&Vec3::subtract(&q[1], &q[0]), &Vec3::subtract(&q[2], &q[0]));
+    Vec3::dot(&before, &after) <= 0.0
+  })
+}
+
+// symmetric 4x4 error quadric, upper triangle only
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+impl Quadric {
+  // area weighted plane of the triangle
+  fn from_triangle(a: &[f32; 3], b: &[f32; 3], c: &[f32; 3]) -> Self {
+    let n = Vec3::cross(&Vec3::subtract(b, a), &Vec3::subtract(c, a));
+    let area = Vec3::magnitude(&n) as f64 * 0.5;
+    let n = Vec3::normalize(&n);
+    let (x, y, z) = (n[0] as f64, n[1] as f64, n[2] as f64);
+    let d = -(x * a[0] as f64 + y * a[1] as f64 + z * a[2] as f64);
+    let q = [x * x, x * y, x * z, x * d, y * y, y * z, y * d, z * z, z * d, d * d];
+    Self(q.map(|v| v * area))
+  }
+
+  fn add(&mut self, other: &Quadric) {
+    for i in 0..10 {
+      self.0[i] += other.0[i];
+    }
+  }
+
+  fn sum(&self, other: &Quadric) -> Quadric {
+    let mut q = *self;
+    q.add(other);
+    q
+  }
+
+  fn error(&self, p: &[f32; 3]) -> f32 {
+    let q = &self.0;
+    let (x, y, z) = (p[0] as f64, p[1] as f64, p[2] as f64);
+    let e = q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+      + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+      + q[7] * z * z + 2.0 * q[8] * z
+      + q[9];
+    e.max(0.0) as f32
+  }
+}
+
+// edge collapse candidate, invalid once either vertex changed since it was queued
+#[derive(Debug)]
+struct Collapse {
+  cost: f32,
+  from: usize,
+  to: usize,
+  versions: [u32; 2],
+}
+impl PartialEq for Collapse {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+impl Eq for Collapse {}
+impl PartialOrd for Collapse {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl Ord for Collapse {
+  // lowest cost first out of the max heap
+  fn cmp(&self, other: &Self) -> Ordering {
+    other.cost.total_cmp(&self.cost)
+  }
+}
+
+#[cfg(test)]
+mod lod_tests {
+  use super::*;
+  use crate::wgpu_renderer::{Primitives, RBounds};
+
+  #[test]
+  fn lod_hysteresis() {
+    let thresholds = [10.0, 20.0];
+    assert_eq!(select_lod(5.0, &thresholds, 0, 0.1), 0);
+    // stays until past the threshold by 10%
+    assert_eq!(select_lod(10.5, &thresholds, 0, 0.1), 0);
+    assert_eq!(select_lod(11.5, &thresholds, 0, 0.1), 1);
+    assert_eq!(select_lod(9.5, &thresholds, 1, 0.1), 1);
+    assert_eq!(select_lod(8.5, &thresholds, 1, 0.1), 0);
+    // jumps several levels at once
+    assert_eq!(select_lod(50.0, &thresholds, 0, 0.1), 2);
+    assert_eq!(select_lod(1.0, &thresholds, 2, 0.1), 0);
+    assert_eq!(select_lod(1.0, &[], 3, 0.1), 0);
+  }
+
+  #[test]
+  fn simplify_sphere() {
+    let (v, i) = Primitives::sphere(1.0, 32, 16);
+    let tri_count = i.len() / 3;
+    let (sv, si) = simplify_mesh(&v, &i, 0.25);
+    assert!(si.len() / 3 <= tri_count / 4 + 1, "{} of {}", si.len() / 3, tri_count);
+    assert!(si.len() / 3 >= tri_count / 8);
+    assert!(sv.len() < v.len());
+    assert!(si.iter().all(|i| (*i as usize) < sv.len()));
+    // overall shape is kept
+    let a = RBounds::from_positions(&v.iter().map(|v| v.position).collect::<Vec<_>>()).unwrap();
+    let b = RBounds::from_positions(&sv.iter().map(|v| v.position).collect::<Vec<_>>()).unwrap();
+    assert!((0..3).all(|k| (a.max[k] - b.max[k]).abs() < 0.15 && (a.min[k] - b.min[k]).abs() < 0.15));
+    // unchanged at full ratio
+    let (fv, fi) = simplify_mesh(&v, &i, 1.0);
+    assert_eq!(fi.len(), i.len());
+    assert!(fv.len() <= v.len());
+  }
+
+  #[test]
+  fn simplify_keeps_borders() {
+    // flat open grid collapses its interior, the border stays in place
+    let n = 8;
+    let mut v: Vec<RVertex> = Vec::new();
+    let mut i: Vec<u32> = Vec::new();
+    for y in 0..=n {
+      for x in 0..=n {
+        v.push(RVertex { position: [x as f32, y as f32, 0.0], uv: [0.0, 0.0], normal: [0.0, 0.0, 1.0] });
+      }
+    }
+    for y in 0..n {
+      for x in 0..n {
+        let a = y * (n + 1) + x;
+        i.extend_from_slice(&[a, a + 1, a + n + 2, a + n + 2, a + n + 1, a]);
+      }
+    }
+    let (sv, si) = simplify_mesh(&v, &i, 0.1);
+    assert!(si.len() < i.len() / 2, "{} of {}", si.len(), i.len());
+    let border = |verts: &[RVertex]| verts.iter()
+      .filter(|v| v.position.iter().take(2).any(|p| *p == 0.0 || *p == n as f32))
+      .count();
+    assert_eq!(border(&sv), border(&v));
+    // unindexed triangle lists are supported
+    let cube = Primitives::cube(1.0, 1.0, 1.0);
+    let (_, ci) = simplify_mesh(&cube, &[], 0.5);
+    assert!(ci.len() <= cube.len());
+  }
+}
@@ -16,4 +16,25 @@ mod util;
 pub use util::*;
 
 mod model_loader;
-pub use model_loader::*;
\ No newline at end of file
+pub use model_loader::*;
+
+mod render_graph;
+pub use render_graph::*;
+
+mod reflect;
+pub use reflect::*;
+
+mod preprocess;
+pub use preprocess::*;
+
+mod particles;
+pub use particles::*;
+
+mod bounds;
+pub use bounds::*;
+
+mod lod;
+pub use lod::*;
+
+mod stats_overlay;
+pub use stats_overlay::*;
\ No newline at end of file
@@ -0,0 +1,613 @@
+#![allow(dead_code)]
+use bytemuck::{Pod, Zeroable};
+
+use super::{
+  Renderer,
+  RCamera,
+  RVertex,
+  RTextureId,
+  RPipelineId,
+  RObjectId,
+  RComputeId,
+  RPipelineSetup,
+  RComputeSetup,
+  RBindingSetup,
+  RObjectSetup,
+  RObjectUpdate,
+  Primitives,
+  Vec3,
+  PI,
+};
+
+// particle layout shared by the cpu simulation, compute shader and instance buffer
+// note: particles with age >= lifetime are dead, and drawn as degenerate quads
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct RParticle {
+  pub position: [f32; 3],
+  pub age: f32,
+  pub velocity: [f32; 3],
+  pub lifetime: f32,
+}
+impl RParticle {
+  pub fn alive(&self) -> bool {
+    self.age < self.lifetime
+  }
+}
+
+// helper for building particle system
+// note: color and size curves are evenly spaced keys over a particle lifetime (max 8 each),
+// the texture sheet is split into columns x rows frames, played sheet_cycles times per lifetime
+#[derive(Debug)]
+pub struct RParticleSetup<'a> {
+  pub max_particles: u32,
+  pub emitter: u8,
+  pub radius: f32,
+  pub direction: [f32; 3],
+  pub cone_angle: f32,
+  pub mesh_vertices: &'a [RVertex],
+  pub mesh_indices: &'a [u32],
+  pub spawn_rate: f32,
+  pub lifetime: [f32; 2],
+  pub speed: [f32; 2],
+  pub gravity: [f32; 3],
+  pub drag: f32,
+  pub color_over_life: Vec<[f32; 4]>,
+  pub size_over_life: Vec<f32>,
+  pub texture_id: Option<RTextureId>,
+  pub sheet_size: [u32; 2],
+  pub sheet_cycles: f32,
+  pub blend_mode: u8,
+  pub simulation: u8,
+  pub seed: u32,
+}
+impl Default for RParticleSetup<'_> {
+  fn default() -> Self {
+    RParticleSetup {
+      max_particles: 1000,
+      emitter: RParticleSetup::EMITTER_POINT,
+      radius: 1.0,
+      direction: [0.0, 1.0, 0.0],
+      cone_angle: 25.0,
+      mesh_vertices: &[],
+      mesh_indices: &[],
+      spawn_rate: 100.0,
+      lifetime: [1.0, 2.0],
+      speed: [1.0, 2.0],
+      gravity: [0.0, -9.8, 0.0],
+      drag: 0.0,
+      color_over_life: vec![[1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 0.0]],
+      size_over_life: vec![0.1],
+      texture_id: None,
+      sheet_size: [1, 1],
+      sheet_cycles: 1.0,
+      blend_mode: RPipelineSetup::BLEND_MODE_ADDITIVE,
+      simulation: RParticleSetup::SIMULATION_GPU,
+      seed: 0,
+    }
+  }
+}
+impl RParticleSetup<'_> {
+  // emitter shape constants
+  // note: cone emits around direction from a disc of radius, mesh emits from the
+  // surface of mesh_vertices + mesh_indices along the triangle normals
+  pub const EMITTER_POINT: u8 = 0;
+  pub const EMITTER_SPHERE: u8 = 1;
+  pub const EMITTER_CONE: u8 = 2;
+  pub const EMITTER_MESH: u8 = 3;
+  // simulation constants
+  pub const SIMULATION_GPU: u8 = 0;
+  pub const SIMULATION_CPU: u8 = 1;
+  pub const MAX_CURVE_KEYS: usize = 8;
+}
+
+// -- CPU SIMULATION --
+// emitter triangle with the running total of triangle areas, for area weighted sampling
+#[derive(Debug, Clone, Copy)]
+struct REmitterTriangle {
+  points: [[f32; 3]; 3],
+  normal: [f32; 3],
+  cdf: f32,
+}
+
+#[derive(Debug)]
+pub struct RParticleSim {
+  pub particles: Vec<RParticle>,
+  pub origin: [f32; 3],
+  emitter: u8,
+  radius: f32,
+  direction: [f32; 3],
+  cone_angle: f32,
+  triangles: Vec<REmitterTriangle>,
+  spawn_rate: f32,
+  spawn_accum: f32,
+  lifetime: [f32; 2],
+  speed: [f32; 2],
+  gravity: [f32; 3],
+  drag: f32,
+  rng: u32,
+}
+impl RParticleSim {
+  pub fn new(setup: &RParticleSetup) -> Self {
+    let mut triangles: Vec<REmitterTriangle> = Vec::new();
+    let mut total_area = 0.0;
+    if setup.emitter == RParticleSetup::EMITTER_MESH {
+      for tri in setup.mesh_indices.chunks_exact(3) {
+        let Some(points) = tri.iter()
+          .map(|i| setup.mesh_vertices.get(*i as usize).map(|v| v.position))
+          .collect::<Option<Vec<[f32; 3]>>>() else { continue };
+        let cross = Vec3::cross(&Vec3::subtract(&points[1], &points[0]), &Vec3::subtract(&points[2], &points[0]));
+        let area = Vec3::magnitude(&cross) / 2.0;
+        if area <= 0.0 { continue; }
+        total_area += area;
+        triangles.push(REmitterTriangle {
+          points: [points[0], points[1], points[2]],
+          normal: Vec3::normalize(&cross),
+          cdf: total_area,
+        });
+      }
+      if triangles.is_empty() {
+        eprintln!("Err: Mesh emitter has no triangles, emitting from a point");
+      }
+    }
+    Self {
+      particles: vec![RParticle::zeroed(); setup.max_particles as usize],
+      origin: [0.0, 0.0, 0.0],
+      emitter: setup.emitter,
+      radius: setup.radius,
+      direction: Vec3::normalize(&setup.direction),
+      cone_angle: setup.cone_angle * PI / 180.0,
+      triangles,
+      spawn_rate: setup.spawn_rate,
+      spawn_accum: 0.0,
+      lifetime: setup.lifetime,
+      speed: setup.speed,
+      gravity: setup.gravity,
+      drag: setup.drag,
+      rng: pcg_hash(setup.seed),
+    }
+  }
+
+  pub fn alive_count(&self) -> usize {
+    self.particles.iter().filter(|p| p.alive()).count()
+  }
+
+  // number of particles to spawn over dt, fractions carry over to the next step
+  pub fn spawn_count(&mut self, dt: f32) -> u32 {
+    self.spawn_accum += self.spawn_rate * dt;
+    let count = self.spawn_accum.floor();
+    self.spawn_accum -= count;
+    count as u32
+  }
+
+  // integrate live particles, then spawn new ones at the emission rate
+  pub fn update(&mut self, dt: f32) {
+    let count = self.spawn_count(dt);
+    self.step(dt, count);
+  }
+
+  pub fn step(&mut self, dt: f32, spawn: u32) {
+    let damping = f32::max(0.0, 1.0 - self.drag * dt);
+    for p in self.particles.iter_mut().filter(|p| p.alive()) {
+      for i in 0..3 {
+        p.velocity[i] = (p.velocity[i] + self.gravity[i] * dt) * damping;
+        p.position[i] += p.velocity[i] * dt;
+      }
+      p.age += dt;
+    }
+    self.emit(spawn);
+  }
+
+  // spawn into dead slots, returns the number of particles spawned
+  pub fn emit(&mut self, count: u32) -> u32 {
+    let mut spawned = 0;
+    for i in 0..self.particles.len() {
+      if spawned == count { break; }
+      if self.particles[i].alive() { continue; }
+      self.rng = pcg_hash(self.rng);
+      self.particles[i] = self.spawn(self.rng);
+      spawned += 1;
+    }
+    spawned
+  }
+
+  fn spawn(&self, seed: u32) -> RParticle {
+    let mut rng = RRandom(seed);
+    let (offset, dir) = match self.emitter {
+      RParticleSetup::EMITTER_SPHERE => {
+        let dir = rng.unit_vector();
+        let r = self.radius * rng.next().cbrt();
+        ([dir[0] * r, dir[1] * r, dir[2] * r], dir)
+      }
+      RParticleSetup::EMITTER_CONE => {
+        let (tangent, bitangent) = basis(&self.direction);
+        // direction within the cone angle around the axis
+        let cos_theta = 1.0 + (self.cone_angle.cos() - 1.0) * rng.next();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.next();
+        let dir = Vec3::normalize(&[
+          tangent[0] * phi.cos() * sin_theta + bitangent[0] * phi.sin() * sin_theta + self.direction[0] * cos_theta,
+          tangent[1] * phi.cos() * sin_theta + bitangent[1] * phi.sin() * sin_theta + self.direction[1] * cos_theta,
+          tangent[2] * phi.cos() * sin_theta + bitangent[2] * phi.sin() * sin_theta + self.direction[2] * cos_theta,
+        ]);
+        // start from a disc of radius around the axis
+        let r = self.radius * rng.next().sqrt();
+        let a = 2.0 * PI * rng.next();
+        let offset = [
+          (tangent[0] * a.cos() + bitangent[0] * a.sin()) * r,
+          (tangent[1] * a.cos() + bitangent[1] * a.sin()) * r,
+          (tangent[2] * a.cos() + bitangent[2] * a.sin()) * r,
+        ];
+        (offset, dir)
+      }
+      RParticleSetup::EMITTER_MESH if !self.triangles.is_empty() => {
+        let total = self.triangles.last().unwrap().cdf;
+        let target = rng.next() * total;
+        let tri = self.triangles.iter().find(|t| t.cdf >= target).unwrap_or(self.triangles.last().unwrap());
+        // uniform barycentric coordinates
+        let su = rng.next().sqrt();
+        let v = rng.next();
+        let (b0, b1) = (1.0 - su, v * su);
+        let b2 = 1.0 - b0 - b1;
+        let p = tri.points;
+        let offset = [
+          p[0][0] * b0 + p[1][0] * b1 + p[2][0] * b2,
+          p[0][1] * b0 + p[1][1] * b1 + p[2][1] * b2,
+          p[0][2] * b0 + p[1][2] * b1 + p[2][2] * b2,
+        ];
+        (offset, tri.normal)
+      }
+      _ => ([0.0, 0.0, 0.0], rng.unit_vector()),
+    };
+    let speed = lerp(self.speed[0], self.speed[1], rng.next());
+    RParticle {
+      position: Vec3::add(&self.origin, &offset),
+      age: 0.0,
+      velocity: [dir[0] * speed, dir[1] * speed, dir[2] * speed],
+      lifetime: lerp(self.lifetime[0], self.lifetime[1], rng.next()),
+    }
+  }
+}
+
+// pcg hash, matches pcg_hash in embed_assets/particle_sim.wgsl
+fn pcg_hash(v: u32) -> u32 {
+  let state = v.wrapping_mul(747796405).wrapping_add(2891336453);
+  let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+  (word >> 22) ^ word
+}
+
+struct RRandom(u32);
+impl RRandom {
+  // uniform value in [0, 1)
+  fn next(&mut self) -> f32 {
+    self.0 = pcg_hash(self.0);
+    (self.0 >> 8) as f32 / 16777216.0
+  }
+  fn unit_vector(&mut self) -> [f32; 3] {
+    let z = 2.0 * self.next() - 1.0;
+    let phi = 2.0 * PI * self.next();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    [r * phi.cos(), r * phi.sin(), z]
+  }
+}
+
+fn basis(axis: &[f32; 3]) -> ([f32; 3], [f32; 3]) {
+  let up = if axis[1].abs() < 0.999 { [0.0, 1.0, 0.0] } else { [1.0, 0.0, 0.0] };
+  let tangent = Vec3::normalize(&Vec3::cross(axis, &up));
+  (tangent, Vec3::cross(axis, &tangent))
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+  a + (b - a) * t
+}
+
+// piecewise linear curve through evenly spaced keys, t in [0, 1]
+pub fn sample_curve<const N: usize>(keys: &[[f32; N]], t: f32) -> [f32; N] {
+  match keys.len() {
+    0 => [1.0; N],
+    1 => keys[0],
+    len => {
+      let x = t.clamp(0.0, 1.0) * (len - 1) as f32;
+      let i = usize::min(x.floor() as usize, len - 2);
+      let f = x - i as f32;
+      let mut out = [0.0; N];
+      for c in 0..N {
+        out[c] = lerp(keys[i][c], keys[i + 1][c], f);
+      }
+      out
+    }
+  }
+}
+
+// texture sheet frame shown at t in [0, 1] of a particle lifetime
+pub fn sheet_frame(sheet_size: [u32; 2], cycles: f32, t: f32) -> u32 {
+  let frames = u32::max(1, sheet_size[0] * sheet_size[1]);
+  (t.clamp(0.0, 0.9999) * cycles * frames as f32).floor() as u32 % frames
+}
+
+// -- RENDERER INTEGRATION --
+// compute shader parameters, layout matches SimParams in embed_assets/particle_sim.wgsl
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct RParticleSimParams {
+  origin: [f32; 3],
+  dt: f32,
+  gravity: [f32; 3],
+  drag: f32,
+  direction: [f32; 3],
+  radius: f32,
+  lifetime: [f32; 2],
+  speed: [f32; 2],
+  emitter: u32,
+  cone_angle: f32,
+  spawn_count: u32,
+  seed: u32,
+  max_particles: u32,
+  triangle_count: u32,
+  _padding: [u32; 2],
+}
+
+// appearance parameters, layout matches Look in embed_assets/particle.wgsl
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct RParticleLook {
+  colors: [[f32; 4]; 8],
+  sizes: [f32; 8],
+  counts: [u32; 4],
+  sheet: [f32; 4],
+}
+
+const WORKGROUP_SIZE: u32 = 64;
+
+// instanced billboards simulated by a compute shader, or on the cpu
+// note: particles are simulated in world space, moving position only moves the emitter
+#[derive(Debug)]
+pub struct RParticleSystem {
+  pub position: [f32; 3],
+  pub visible: bool,
+  pub pipeline_id: RPipelineId,
+  pub object_id: RObjectId,
+  pub compute_id: RComputeId,
+  pub sim: RParticleSim,
+  max_particles: u32,
+  simulation: u8,
+  pending_burst: u32,
+  frame: u32,
+}
+impl RParticleSystem {
+  pub fn new(renderer: &mut Renderer, setup: RParticleSetup) -> Self {
+    let sim = RParticleSim::new(&setup);
+    let max_particles = u32::max(1, setup.max_particles);
+
+    // particle storage is written by the compute pass (or the cpu), and read as instance data
+    let triangle_bytes: Vec<f32> = sim.triangles.iter().flat_map(|t| [
+      t.points[0][0], t.points[0][1], t.points[0][2], t.cdf,
+      t.points[1][0], t.points[1][1], t.points[1][2], 0.0,
+      t.points[2][0], t.points[2][1], t.points[2][2], 0.0,
+      t.normal[0], t.normal[1], t.normal[2], 0.0,
+    ]).collect();
+    let compute_id = renderer.add_compute_pipeline(RComputeSetup {
+      shader: include_str!("../embed_assets/particle_sim.wgsl"),
+      entry_fn: "simulate",
+      bindings: vec![
+        RBindingSetup {
+          bind_group: 0,
+          bind_slot: 0,
+          binding_type: RBindingSetup::BINDING_TYPE_STORAGE_RW,
+          size_in_bytes: (std::mem::size_of::<RParticle>() * max_particles as usize) as u64,
+          ..Default::default()
+        },
+        RBindingSetup {
+          bind_group: 0,
+          bind_slot: 1,
+          binding_type: RBindingSetup::BINDING_TYPE_UNIFORM,
+          size_in_bytes: std::mem::size_of::<RParticleSimParams>() as u64,
+          ..Default::default()
+        },
+        RBindingSetup {
+          bind_group: 0,
+          bind_slot: 2,
+          binding_type: RBindingSetup::BINDING_TYPE_STORAGE_RW,
+          size_in_bytes: 4,
+          ..Default::default()
+        },
+        RBindingSetup {
+          bind_group: 0,
+          bind_slot: 3,
+          binding_type: RBindingSetup::BINDING_TYPE_STORAGE,
+          size_in_bytes: u64::max(64, triangle_bytes.len() as u64 * 4),
+          ..Default::default()
+        },
+      ],
+      ..Default::default()
+    });
+    if !triangle_bytes.is_empty() {
+      renderer.update_compute_buffer(compute_id, 0, 3, 0, bytemuck::cast_slice(&triangle_bytes));
+    }
+
+    let defines = if setup.texture_id.is_some() { vec!["HAS_TEXTURE"] } else { Vec::new() };
+    let pipeline_id = renderer.add_pipeline(RPipelineSetup {
+      shader: include_str!("../embed_assets/particle.wgsl"),
+      defines,
+      max_obj_count: 1,
+      texture1_id: setup.texture_id,
+      instance_attributes: vec![wgpu::VertexFormat::Float32x4, wgpu::VertexFormat::Float32x4],
+      bindings: vec![RBindingSetup {
+        bind_group: 1,
+        bind_slot: 0,
+        visibility: RBindingSetup::VISIBILITY_BOTH,
+        binding_type: RBindingSetup::BINDING_TYPE_UNIFORM,
+        size_in_bytes: std::mem::size_of::<RParticleLook>() as u64,
+        ..Default::default()
+      }],
+      blend_mode: setup.blend_mode,
+      alpha_to_coverage: false,
+      depth_write: false,
+      transparent: true,
+      ..Default::default()
+    });
+    let mut look = RParticleLook::zeroed();
+    let colors = &setup.color_over_life[..usize::min(setup.color_over_life.len(), RParticleSetup::MAX_CURVE_KEYS)];
+    let sizes = &setup.size_over_life[..usize::min(setup.size_over_life.len(), RParticleSetup::MAX_CURVE_KEYS)];
+    look.colors[..colors.len()].copy_from_slice(colors);
+    look.sizes[..sizes.len()].copy_from_slice(sizes);
+    look.counts = [colors.len() as u32, sizes.len() as u32, u32::max(1, setup.sheet_size[0]), u32::max(1, setup.sheet_size[1])];
+    look.sheet = [setup.sheet_cycles, 0.0, 0.0, 0.0];
+    renderer.update_binding_buffer(pipeline_id, 1, 0, 0, bytemuck::bytes_of(&look));
+
+    let (vertex_data, indices) = Primitives::rect_indexed(1.0, 1.0, 0.0);
+    let object_id = renderer.add_object(RObjectSetup {
+      pipeline_id,
+      vertex_data,
+      indices,
+      instances: max_particles,
+      instance_buffer: renderer.compute_buffer(compute_id, 0, 0),
+      ..Default::default()
+    });
+
+    Self {
+      position: [0.0, 0.0, 0.0],
+      visible: true,
+      pipeline_id,
+      object_id,
+      compute_id,
+      sim,
+      max_particles,
+      simulation: setup.simulation,
+      pending_burst: 0,
+      frame: 0,
+    }
+  }
+
+  // spawn count particles on the next update, on top of the spawn rate
+  pub fn emit(&mut self, count: u32) {
+    self.pending_burst += count;
+  }
+
+  pub fn update(&mut self, renderer: &mut Renderer, dt: f32, camera: Option<&RCamera>) {
+    let spawn = self.sim.spawn_count(dt) + self.pending_burst;
+    self.pending_burst = 0;
+    self.sim.origin = self.position;
+    if self.simulation == RParticleSetup::SIMULATION_CPU {
+      self.sim.step(dt, spawn);
+      renderer.update_compute_buffer(self.compute_id, 0, 0, 0, bytemuck::cast_slice(&self.sim.particles));
+    } else {
+      self.frame = self.frame.wrapping_add(1);
+      let params = RParticleSimParams {
+        origin: self.position,
+        dt,
+        gravity: self.sim.gravity,
+        drag: self.sim.drag,
+        direction: self.sim.direction,
+        radius: self.sim.radius,
+        lifetime: self.sim.lifetime,
+        speed: self.sim.speed,
+        emitter: self.sim.emitter as u32,
+        cone_angle: self.sim.cone_angle,
+        spawn_count: spawn,
+        seed: pcg_hash(self.sim.rng ^ self.frame),
+        max_particles: self.max_particles,
+        triangle_count: self.sim.triangles.len() as u32,
+        _padding: [0; 2],
+      };
+      renderer.update_compute_buffer(self.compute_id, 0, 1, 0, bytemuck::bytes_of(&params));
+      renderer.update_compute_buffer(self.compute_id, 0, 2, 0, &[0; 4]);
+      renderer.dispatch(self.compute_id, [self.max_particles.div_ceil(WORKGROUP_SIZE), 1, 1]);
+    }
+    renderer.update_object(RObjectUpdate {
+      object_id: self.object_id,
+      visible: self.visible,
+      camera,
+      ..Default::default()
+    });
+  }
+}
+
+#[cfg(test)]
+mod particles_tests {
+  use super::*;
+
+  fn setup(emitter: u8) -> RParticleSetup<'static> {
+    RParticleSetup {
+      max_particles: 64,
+      emitter,
+      spawn_rate: 10.0,
+      lifetime: [1.0, 1.0],
+      speed: [2.0, 2.0],
+      gravity: [0.0, 0.0, 0.0],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn spawn_and_expire() {
+    let mut sim = RParticleSim::new(&setup(RParticleSetup::EMITTER_POINT));
+    sim.update(0.25);
+    sim.update(0.25);
+    assert_eq!(sim.alive_count(), 5);
+    // first particles expire after their lifetime
+    sim.update(0.8);
+    assert_eq!(sim.alive_count(), 3 + 8);
+    sim.spawn_rate = 0.0;
+    sim.update(1.0);
+    assert_eq!(sim.alive_count(), 0);
+    // bursts are capped at max particles
+    assert_eq!(sim.emit(100), 64);
+  }
+
+  #[test]
+  fn integrate_velocity() {
+    let mut sim = RParticleSim::new(&RParticleSetup { gravity: [0.0, -10.0, 0.0], drag: 0.5, ..setup(RParticleSetup::EMITTER_POINT) });
+    sim.origin = [1.0, 2.0, 3.0];
+    sim.emit(1);
+    let p = sim.particles[0];
+    assert_eq!(p.position, [1.0, 2.0, 3.0]);
+    assert!((Vec3::magnitude(&p.velocity) - 2.0).abs() < 1e-4);
+    sim.step(0.1, 0);
+    let q = sim.particles[0];
+    let expected_vy = (p.velocity[1] - 1.0) * 0.95;
+    assert!((q.velocity[1] - expected_vy).abs() < 1e-5);
+    assert!((q.position[1] - (2.0 + expected_vy * 0.1)).abs() < 1e-5);
+    assert!((q.age - 0.1).abs() < 1e-6);
+  }
+
+  #[test]
+  fn emitter_shapes() {
+    let mut sphere = RParticleSim::new(&RParticleSetup { radius: 0.5, ..setup(RParticleSetup::EMITTER_SPHERE) });
+    sphere.emit(64);
+    assert!(sphere.particles.iter().all(|p| Vec3::magnitude(&p.position) <= 0.5 + 1e-5));
+
+    let mut cone = RParticleSim::new(&RParticleSetup { direction: [0.0, 0.0, 2.0], cone_angle: 30.0, ..setup(RParticleSetup::EMITTER_CONE) });
+    cone.emit(64);
+    let min_cos = (30.0f32 * PI / 180.0).cos() - 1e-4;
+    assert!(cone.particles.iter().all(|p| Vec3::normalize(&p.velocity)[2] >= min_cos));
+    assert!(cone.particles.iter().all(|p| p.position[2].abs() < 1e-5));
+
+    let (vertices, indices) = Primitives::rect_indexed(2.0, 2.0, 0.0);
+    let mut mesh = RParticleSim::new(&RParticleSetup {
+      mesh_vertices: &vertices,
+      mesh_indices: &indices,
+      ..setup(RParticleSetup::EMITTER_MESH)
+    });
+    mesh.origin = [0.0, 0.0, 1.0];
+    mesh.emit(64);
+    assert!(mesh.particles.iter().all(|p| {
+      (p.position[2] - 1.0).abs() < 1e-5 && p.position[0].abs() <= 1.0 + 1e-5 && p.position[1].abs() <= 1.0 + 1e-5
+    }));
+    assert!(mesh.particles.iter().all(|p| p.velocity[0].abs() < 1e-5 && p.velocity[1].abs() < 1e-5));
+  }
+
+  #[test]
+  fn curves_and_sheet() {
+    let colors = [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0], [0.0, 0.0, 1.0, 0.0]];
+    assert_eq!(sample_curve(&colors, 0.0), colors[0]);
+    assert_eq!(sample_curve(&colors, 0.25), [0.5, 0.5, 0.0, 1.0]);
+    assert_eq!(sample_curve(&colors, 1.0), colors[2]);
+    assert_eq!(sample_curve(&[[0.2]], 0.7), [0.2]);
+    assert_eq!(sample_curve::<1>(&[], 0.7), [1.0]);
+    assert_eq!(sheet_frame([4, 2], 1.0, 0.0), 0);
+    assert_eq!(sheet_frame([4, 2], 1.0, 0.5), 4);
+    assert_eq!(sheet_frame([4, 2], 1.0, 1.0), 7);
+    assert_eq!(sheet_frame([4, 2], 2.0, 0.5), 0);
+  }
+}
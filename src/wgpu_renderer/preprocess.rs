@@ -0,0 +1,282 @@
+use std::{fs, path::{Path, PathBuf}};
+
+// renderer provided snippets, resolved by #include when no file of the same name exists
+const SHADER_LIBRARY: [(&str, &str); 4] = [
+  ("mvp.wgsl", include_str!("../embed_assets/lib/mvp.wgsl")),
+  ("textures.wgsl", include_str!("../embed_assets/lib/textures.wgsl")),
+  ("vertex.wgsl", include_str!("../embed_assets/lib/vertex.wgsl")),
+  ("oit.wgsl", include_str!("../embed_assets/lib/oit.wgsl")),
+];
+
+// nested includes deeper than this are treated as an include cycle
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+pub fn shader_library_snippet(name: &str) -> Option<&'static str> {
+  SHADER_LIBRARY.iter().find(|(n, _)| *n == name).map(|(_, s)| *s)
+}
+
+// preprocessed wgsl, each line of code maps back to the file + line it came from
+// note: files only lists includes read from disk, for hot reloading
+#[derive(Debug, Clone, Default)]
+pub struct RShaderSource {
+  pub code: String,
+  pub lines: Vec<(String, u32)>,
+  pub files: Vec<PathBuf>,
+}
+impl RShaderSource {
+  // file + line number of a (1 based) line of the preprocessed code
+  pub fn source_line(&self, line: u32) -> Option<(&str, u32)> {
+    if line == 0 { return None; }
+    self.lines.get(line as usize - 1).map(|(f, l)| (f.as_str(), *l))
+  }
+
+  // rewrite naga "wgsl:line:column" locations to the original file + line
+  pub fn map_error(&self, error: &str) -> String {
+    let mut out = String::with_capacity(error.len());
+    let mut rest = error;
+    while let Some(i) = rest.find("wgsl:") {
+      out.push_str(&rest[..i]);
+      let after = &rest[i + 5..];
+      let digits = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+      match after[..digits].parse::<u32>().ok().and_then(|l| self.source_line(l)) {
+        Some((file, line)) => out.push_str(&format!("{}:{}", file, line)),
+        None => out.push_str(&rest[i..i + 5 + digits]),
+      }
+      rest = &after[digits..];
+    }
+    out.push_str(rest);
+    out
+  }
+}
+
+// expand #include, #define, #undef, #ifdef, #ifndef, #else and #endif directives
+// - includes are looked up in include_dir first, then in the built-in snippet library,
+//   and are only expanded once per shader
+// - defines given as "NAME" or "NAME=VALUE", defines with a value replace whole words
+// - sources without any directive are passed through unchanged
+pub fn preprocess_shader(
+  source: &str,
+  name: &str,
+  defines: &[String],
+  include_dir: Option<&Path>,
+) -> Result<RShaderSource, String> {
+  let mut state = Preprocessor {
+    defines: Vec::new(),
+    included: Vec::new(),
+    out: RShaderSource::default(),
+  };
+  // later defines override earlier ones
+  for d in defines {
+    let (key, value) = d.split_once('=').unwrap_or((d, ""));
+    state.define(key.trim(), value.trim());
+  }
+  if !has_directives(source) {
+    state.out.code = source.to_owned();
+    state.out.lines = (1..=source.lines().count() as u32).map(|l| (name.to_owned(), l)).collect();
+    return Ok(state.out);
+  }
+  state.process(source, name, include_dir, 0)?;
+  Ok(state.out)
+}
+
+fn has_directives(source: &str) -> bool {
+  source.lines().any(|l| l.trim_start().starts_with('#'))
+}
+
+struct Preprocessor {
+  defines: Vec<(String, String)>,
+  included: Vec<String>,
+  out: RShaderSource,
+}
+
+// state of an #ifdef block
+struct Conditional {
+  parent_active: bool,
+  taken: bool,
+  has_else: bool,
+}
+
+impl Preprocessor {
+  fn define(&mut self, key: &str, value: &str) {
+    self.defines.retain(|(k, _)| k != key);
+    self.defines.push((key.to_owned(), value.to_owned()));
+  }
+
+  fn is_defined(&self, name: &str) -> bool {
+    self.defines.iter().any(|(k, _)| k == name)
+  }
+
+  fn process(&mut self, source: &str, name: &str, dir: Option<&Path>, depth: usize) -> Result<(), String> {
+    if depth > MAX_INCLUDE_DEPTH {
+      return Err(format!("{}: includes nested deeper than {}", name, MAX_INCLUDE_DEPTH));
+    }
+    let mut stack: Vec<Conditional> = Vec::new();
+    let mut active = true;
+    for (i, line) in source.lines().enumerate() {
+      let line_no = i as u32 + 1;
+      let trimmed = line.trim();
+      if !trimmed.starts_with('#') {
+        if active {
+          let code = self.substitute(line);
+          self.out.code.push_str(&code);
+          self.out.code.push('\n');
+          self.out.lines.push((name.to_owned(), line_no));
+        }
+        continue;
+      }
+      // strip trailing comments from directives
+      let directive = trimmed.split("//").next().unwrap_or("").trim();
+      let (keyword, arg) = match directive.split_once(char::is_whitespace) {
+        Some((k, a)) => (k, a.trim()),
+        None => (directive, ""),
+      };
+      let err = |msg: String| format!("{}:{}: {}", name, line_no, msg);
+      match keyword {
+        "#ifdef" | "#ifndef" => {
+          if arg.is_empty() { return Err(err(format!("{} without a name", keyword))); }
+          let cond = self.is_defined(arg) == (keyword == "#ifdef");
+          stack.push(Conditional { parent_active: active, taken: cond, has_else: false });
+          active = active && cond;
+        }
+        "#else" => {
+          let top = stack.last_mut().ok_or_else(|| err("#else without #ifdef".to_owned()))?;
+          if top.has_else { return Err(err("duplicate #else".to_owned())); }
+          top.has_else = true;
+          active = top.parent_active && !top.taken;
+        }
+        "#endif" => {
+          let top = stack.pop().ok_or_else(|| err("#endif without #ifdef".to_owned()))?;
+          active = top.parent_active;
+        }
+        _ if !active => (),
+        "#define" => {
+          let (key, value) = match arg.split_once(char::is_whitespace) {
+            Some((k, v)) => (k, v.trim()),
+            None => (arg, ""),
+          };
+          if key.is_empty() { return Err(err("#define without a name".to_owned())); }
+          self.define(key, value);
+        }
+        "#undef" => {
+          self.defines.retain(|(k, _)| k != arg);
+        }
+        "#include" => {
+          let file = arg.trim_matches('"');
+          if file.is_empty() || !arg.starts_with('"') || !arg.ends_with('"') || arg.len() < 2 {
+            return Err(err(format!("expected #include \"file\", found {}", directive)));
+          }
+          self.include(file, dir, depth).map_err(err)?;
+        }
+        _ => return Err(err(format!("unknown directive {}", keyword))),
+      }
+    }
+    if !stack.is_empty() {
+      return Err(format!("{}: missing #endif", name));
+    }
+    Ok(())
+  }
+
+  fn include(&mut self, file: &str, dir: Option<&Path>, depth: usize) -> Result<(), String> {
+    if let Some(path) = dir.map(|d| d.join(file)).filter(|p| p.is_file()) {
+      let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone()).to_string_lossy().into_owned();
+      if self.included.contains(&key) { return Ok(()); }
+      self.included.push(key);
+      let source = fs::read_to_string(&path)
+        .map_err(|e| format!("could not read include {:?} - {}", path, e))?;
+      self.out.files.push(path.clone());
+      let name = path.to_string_lossy().into_owned();
+      return self.process(&source, &name, path.parent(), depth + 1);
+    }
+    match shader_library_snippet(file) {
+      Some(source) => {
+        let key = format!("<renderer>/{}", file);
+        if self.included.contains(&key) { return Ok(()); }
+        self.included.push(key.clone());
+        self.process(source, &key, None, depth + 1)
+      }
+      None => Err(format!("could not find include \"{}\"", file)),
+    }
+  }
+
+  // replace whole word identifiers with their defined value
+  fn substitute(&self, line: &str) -> String {
+    if self.defines.iter().all(|(_, v)| v.is_empty()) {
+      return line.to_owned();
+    }
+    let mut out = String::with_capacity(line.len());
+    let mut word = String::new();
+    for c in line.chars().chain(std::iter::once('\n')) {
+      if c.is_ascii_alphanumeric() || c == '_' {
+        word.push(c);
+        continue;
+      }
+      if !word.is_empty() {
+        match self.defines.iter().find(|(k, v)| *k == word && !v.is_empty()) {
+          Some((_, v)) => out.push_str(v),
+          None => out.push_str(&word),
+        }
+        word.clear();
+      }
+      if c != '\n' { out.push(c); }
+    }
+    out
+  }
+}
+
+#[cfg(test)]
+mod preprocess_tests {
+  use super::*;
+  use crate::wgpu_renderer::reflect_shader;
+
+  #[test]
+  fn defines_and_conditionals() {
+    let source = "#define SCALE 2.0\n#ifdef HAS_FOG\nfog\n#ifndef LIGHT\nno_light\n#endif\n#else\nclear\n#endif\nx * SCALE + SCALE_2";
+    let out = preprocess_shader(source, "test.wgsl", &["HAS_FOG".to_owned()], None).unwrap();
+    assert_eq!(out.code, "fog\nno_light\nx * 2.0 + SCALE_2\n");
+    assert_eq!(out.lines, vec![("test.wgsl".to_owned(), 3), ("test.wgsl".to_owned(), 5), ("test.wgsl".to_owned(), 10)]);
+    let out = preprocess_shader(source, "test.wgsl", &["LIGHT=1".to_owned()], None).unwrap();
+    assert_eq!(out.code, "clear\nx * 2.0 + SCALE_2\n");
+    assert!(preprocess_shader("#ifdef A\n", "a.wgsl", &[], None).unwrap_err().contains("missing #endif"));
+    assert_eq!(preprocess_shader("x\n#endif", "a.wgsl", &[], None).unwrap_err(), "a.wgsl:2: #endif without #ifdef");
+  }
+
+  #[test]
+  fn includes_map_lines() {
+    let source = "#include \"mvp.wgsl\"\n#include \"mvp.wgsl\"\nfn f() -> f32 { return 1.0 }";
+    let out = preprocess_shader(source, "main.wgsl", &[], None).unwrap();
+    assert_eq!(out.code.matches("struct MVP").count(), 1);
+    assert!(!out.code.contains("joint_transforms"));
+    let line = out.code.lines().count() as u32;
+    assert_eq!(out.source_line(line), Some(("main.wgsl", 3)));
+    assert_eq!(out.source_line(1).unwrap().0, "<renderer>/mvp.wgsl");
+    // naga locations point at the original file
+    let err = reflect_shader(&out.code).unwrap_err();
+    assert!(out.map_error(&err).contains("main.wgsl:3:"));
+    assert!(preprocess_shader("#include \"missing.wgsl\"", "a.wgsl", &[], None).is_err());
+  }
+
+  #[test]
+  fn built_in_shaders_validate() {
+    let shaders = [
+      include_str!("../embed_assets/base.wgsl"),
+      include_str!("../embed_assets/text.wgsl"),
+      include_str!("../embed_assets/stats_overlay.wgsl"),
+      include_str!("../embed_assets/pick.wgsl"),
+    ];
+    let flags = [
+      vec![],
+      vec!["HAS_TEXTURE2".to_owned(), "REVERSED_Z".to_owned()],
+      vec!["HAS_SKINNING".to_owned(), "MAX_JOINTS=4".to_owned()],
+    ];
+    for shader in shaders {
+      for defines in &flags {
+        let out = preprocess_shader(shader, "shader.wgsl", defines, None).unwrap();
+        if let Err(e) = reflect_shader(&out.code) { panic!("{}", out.map_error(&e)); }
+      }
+    }
+    for defines in [vec![], vec!["HAS_TEXTURE".to_owned()]] {
+      let out = preprocess_shader(include_str!("../embed_assets/particle.wgsl"), "particle.wgsl", &defines, None).unwrap();
+      if let Err(e) = reflect_shader(&out.code) { panic!("{}", out.map_error(&e)); }
+    }
+  }
+}
@@ -0,0 +1,314 @@
+use naga::{
+  front::wgsl,
+  proc::Layouter,
+  valid::{Capabilities, ValidationFlags, Validator},
+  AddressSpace, ImageClass, ImageDimension, ScalarKind, ShaderStage, StorageAccess, TypeInner,
+};
+
+use super::{RPipelineSetup, RUniformSetup, RBindingSetup, RSamplerSetup};
+
+// resource binding declared in a wgsl module
+// note: comparison marks depth textures and comparison samplers,
+// filterable is false for integer textures
+#[derive(Debug, Clone, PartialEq)]
+pub struct RReflectedBinding {
+  pub bind_group: u32,
+  pub bind_slot: u32,
+  pub binding_type: u8,
+  pub texture_dimension: u8,
+  pub comparison: bool,
+  pub filterable: bool,
+  pub size_in_bytes: u64,
+  pub used_by: Vec<String>,
+}
+
+// bindings and entry points of a wgsl module
+#[derive(Debug, Clone, Default)]
+pub struct RShaderReflection {
+  pub bindings: Vec<RReflectedBinding>,
+  pub vertex_entries: Vec<String>,
+  pub fragment_entries: Vec<String>,
+  pub compute_entries: Vec<String>,
+}
+
+// parse + validate wgsl, errors are formatted against the source
+pub fn reflect_shader(source: &str) -> Result<RShaderReflection, String> {
+  let module = wgsl::parse_str(source).map_err(|e| e.emit_to_string(source))?;
+  let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+    .validate(&module)
+    .map_err(|e| e.emit_to_string(source))?;
+  let mut layouter = Layouter::default();
+  layouter.update(module.to_ctx()).map_err(|e| e.to_string())?;
+
+  let mut reflection = RShaderReflection::default();
+  for ep in &module.entry_points {
+    let entries = match ep.stage {
+      ShaderStage::Vertex => &mut reflection.vertex_entries,
+      ShaderStage::Fragment => &mut reflection.fragment_entries,
+      ShaderStage::Compute => &mut reflection.compute_entries,
+    };
+    entries.push(ep.name.clone());
+  }
+  for (handle, var) in module.global_variables.iter() {
+    let Some(res) = &var.binding else { continue };
+    let inner = &module.types[var.ty].inner;
+    let mut binding = RReflectedBinding {
+      bind_group: res.group,
+      bind_slot: res.binding,
+      binding_type: RBindingSetup::BINDING_TYPE_TEXTURE,
+      texture_dimension: RPipelineSetup::TEXTURE_DIM_2D,
+      comparison: false,
+      filterable: true,
+      size_in_bytes: 0,
+      used_by: module.entry_points.iter().enumerate()
+        .filter(|(i, _)| !info.get_entry_point(*i)[handle].is_empty())
+        .map(|(_, ep)| ep.name.clone())
+        .collect(),
+    };
+    match (var.space, inner) {
+      (AddressSpace::Uniform, _) => {
+        binding.binding_type = RBindingSetup::BINDING_TYPE_UNIFORM;
+        binding.size_in_bytes = layouter[var.ty].size as u64;
+      }
+      (AddressSpace::Storage { access }, _) => {
+        binding.binding_type = if access.contains(StorageAccess::STORE) {
+          RBindingSetup::BINDING_TYPE_STORAGE_RW
+        } else {
+          RBindingSetup::BINDING_TYPE_STORAGE
+        };
+        // runtime sized arrays report the size of a single element
+        binding.size_in_bytes = layouter[var.ty].size as u64;
+      }
+      (_, TypeInner::Sampler { comparison }) => {
+        binding.binding_type = RBindingSetup::BINDING_TYPE_SAMPLER;
+        binding.comparison = *comparison;
+      }
+      (_, TypeInner::Image { dim, arrayed, class }) => {
+        binding.texture_dimension = match (dim, arrayed) {
+          (ImageDimension::Cube, _) => RPipelineSetup::TEXTURE_DIM_CUBE,
+          (ImageDimension::D3, _) => RPipelineSetup::TEXTURE_DIM_3D,
+          (_, true) => RPipelineSetup::TEXTURE_DIM_2D_ARRAY,
+          _ => RPipelineSetup::TEXTURE_DIM_2D,
+        };
+        match class {
+          ImageClass::Sampled { kind, .. } => binding.filterable = *kind == ScalarKind::Float,
+          ImageClass::Depth { .. } => binding.comparison = true,
+          ImageClass::Storage { .. } => binding.binding_type = RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE,
+        }
+      }
+      _ => continue,
+    }
+    reflection.bindings.push(binding);
+  }
+  reflection.bindings.sort_by_key(|b| (b.bind_group, b.bind_slot));
+  Ok(reflection)
+}
+
+impl RShaderReflection {
+  pub fn binding(&self, bind_group: u32, bind_slot: u32) -> Option<&RReflectedBinding> {
+    self.bindings.iter().find(|b| b.bind_group == bind_group && b.bind_slot == bind_slot)
+  }
+
+  // stages of the given entry points reading the binding, unused bindings are visible to both
+  pub fn visibility(&self, binding: &RReflectedBinding, vertex_fn: &str, fragment_fn: &str) -> u8 {
+    match used_stages(binding, vertex_fn, fragment_fn) {
+      (true, false) => RBindingSetup::VISIBILITY_VERTEX,
+      (false, true) => RBindingSetup::VISIBILITY_FRAGMENT,
+      _ => RBindingSetup::VISIBILITY_BOTH,
+    }
+  }
+}
+
+fn used_stages(binding: &RReflectedBinding, vertex_fn: &str, fragment_fn: &str) -> (bool, bool) {
+  (binding.used_by.iter().any(|f| f == vertex_fn), binding.used_by.iter().any(|f| f == fragment_fn))
+}
+
+fn covers_visibility(visibility: u8, binding: &RReflectedBinding, vertex_fn: &str, fragment_fn: &str) -> bool {
+  let (in_vertex, in_fragment) = used_stages(binding, vertex_fn, fragment_fn);
+  (!in_vertex || visibility != RBindingSetup::VISIBILITY_FRAGMENT)
+    && (!in_fragment || visibility != RBindingSetup::VISIBILITY_VERTEX)
+}
+
+// fill in custom uniforms/bindings missing from the setup, and correct
+// sizes and visibility that would fail pipeline validation
+pub fn reflect_pipeline_setup(setup: &mut RPipelineSetup, reflection: &RShaderReflection, uniform_stride: u32) {
+  if !reflection.vertex_entries.iter().any(|f| f == setup.vertex_fn) {
+    eprintln!("Err: Vertex entry point {} not found, available: {:?}", setup.vertex_fn, reflection.vertex_entries);
+  }
+  for fragment_fn in std::iter::once(setup.fragment_fn).chain(setup.oit_fragment_fn) {
+    if !reflection.fragment_entries.iter().any(|f| f == fragment_fn) {
+      eprintln!("Err: Fragment entry point {} not found, available: {:?}", fragment_fn, reflection.fragment_entries);
+    }
+  }
+  let (vertex_fn, fragment_fn) = (setup.vertex_fn, setup.fragment_fn);
+  let reserved = |group: u32| match group {
+    2 => setup.use_materials,
+    3 => setup.shading == RPipelineSetup::SHADING_PBR,
+    _ => group == 0,
+  };
+  let custom: Vec<&RReflectedBinding> = reflection.bindings.iter().filter(|b| !reserved(b.bind_group)).collect();
+
+  // check given uniforms + bindings against the shader
+  for u in &mut setup.uniforms {
+    let Some(b) = reflection.binding(1, u.bind_slot) else { continue };
+    if b.binding_type != RBindingSetup::BINDING_TYPE_UNIFORM {
+      eprintln!("Err: Binding {} in group 1 is not a uniform buffer in the shader", u.bind_slot);
+      continue;
+    }
+    if (u.size_in_bytes as u64) < b.size_in_bytes {
+      eprintln!("Err: Uniform {} is {} bytes in the shader, got {}", u.bind_slot, b.size_in_bytes, u.size_in_bytes);
+      u.size_in_bytes = b.size_in_bytes as u32;
+    }
+    if u.size_in_bytes > uniform_stride {
+      eprintln!("Err: Uniform {} exceeds {} bytes, use a uniform binding instead", u.bind_slot, uniform_stride);
+    }
+    if !covers_visibility(u.visibility, b, vertex_fn, fragment_fn) {
+      eprintln!("Err: Uniform {} is used by a stage outside its visibility", u.bind_slot);
+      u.visibility = RUniformSetup::VISIBILITY_BOTH;
+    }
+  }
+  for s in &mut setup.bindings {
+    let Some(b) = reflection.binding(s.bind_group, s.bind_slot) else { continue };
+    if b.binding_type != s.binding_type {
+      eprintln!("Err: Binding {} in group {} does not match its type in the shader", s.bind_slot, s.bind_group);
+      continue;
+    }
+    if s.size_in_bytes < b.size_in_bytes {
+      eprintln!("Err: Binding {} in group {} is {} bytes in the shader, got {}",
+        s.bind_slot, s.bind_group, b.size_in_bytes, s.size_in_bytes);
+      s.size_in_bytes = b.size_in_bytes;
+    }
+    if !covers_visibility(s.visibility, b, vertex_fn, fragment_fn) {
+      eprintln!("Err: Binding {} in group {} is used by a stage outside its visibility", s.bind_slot, s.bind_group);
+      s.visibility = RBindingSetup::VISIBILITY_BOTH;
+    }
+  }
+
+  // fill in bindings missing from the setup, small group 1 uniforms become per object
+  // uniforms when group 1 is otherwise undeclared, everything else is shared per pipeline
+  let group1: Vec<&&RReflectedBinding> = custom.iter().filter(|b| b.bind_group == 1).collect();
+  let given_uniforms = !setup.uniforms.is_empty();
+  let auto_uniforms = !given_uniforms
+    && !setup.bindings.iter().any(|s| s.bind_group == 1)
+    && !group1.is_empty()
+    && group1.iter().all(|b| b.binding_type == RBindingSetup::BINDING_TYPE_UNIFORM && b.size_in_bytes <= uniform_stride as u64);
+  for b in custom {
+    let declared = setup.uniforms.iter().any(|u| b.bind_group == 1 && u.bind_slot == b.bind_slot)
+      || setup.bindings.iter().any(|s| s.bind_group == b.bind_group && s.bind_slot == b.bind_slot);
+    if declared { continue; }
+    let visibility = reflection.visibility(b, vertex_fn, fragment_fn);
+    if b.bind_group == 1 && given_uniforms {
+      eprintln!("Err: Shader binding {} in group 1 is missing from the custom uniforms", b.bind_slot);
+      continue;
+    }
+    if b.bind_group == 1 && auto_uniforms {
+      setup.uniforms.push(RUniformSetup { bind_slot: b.bind_slot, visibility, size_in_bytes: b.size_in_bytes as u32 });
+      continue;
+    }
+    if b.binding_type == RBindingSetup::BINDING_TYPE_TEXTURE && (b.comparison || !b.filterable) {
+      eprintln!("Err: Texture binding {} in group {} needs a texture to infer its sample type, declare it in bindings",
+        b.bind_slot, b.bind_group);
+      continue;
+    }
+    if b.binding_type == RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE {
+      eprintln!("Err: Storage texture binding {} in group {} needs a texture to infer its format, declare it in bindings",
+        b.bind_slot, b.bind_group);
+      continue;
+    }
+    setup.bindings.push(RBindingSetup {
+      bind_group: b.bind_group,
+      bind_slot: b.bind_slot,
+      visibility,
+      binding_type: b.binding_type,
+      texture_dimension: b.texture_dimension,
+      sampler: RSamplerSetup {
+        compare: if b.comparison { Some(wgpu::CompareFunction::LessEqual) } else { None },
+        ..Default::default()
+      },
+      size_in_bytes: b.size_in_bytes,
+      ..Default::default()
+    });
+  }
+}
+
+#[cfg(test)]
+mod reflect_tests {
+  use super::*;
+
+  const SHADER: &str = "
+    struct Params { color: vec4f, scale: vec2f }
+    @group(0) @binding(0) var<uniform> mvp: mat4x4f;
+    @group(1) @binding(0) var<uniform> params: Params;
+    @group(2) @binding(0) var<storage, read> points: array<vec4f>;
+    @group(2) @binding(1) var tex: texture_2d_array<f32>;
+    @group(2) @binding(2) var smp: sampler;
+
+    @vertex
+    fn vertexMain(@builtin(vertex_index) i: u32) -> @builtin(position) vec4f {
+      return mvp * points[i] * params.scale.x;
+    }
+
+    @fragment
+    fn fragmentMain() -> @location(0) vec4f {
+      return textureSample(tex, smp, vec2f(0.5), 0) * params.color;
+    }
+  ";
+
+  #[test]
+  fn reflect_bindings() {
+    let reflection = reflect_shader(SHADER).unwrap();
+    assert_eq!(reflection.vertex_entries, vec!["vertexMain"]);
+    assert_eq!(reflection.fragment_entries, vec!["fragmentMain"]);
+    assert_eq!(reflection.bindings.len(), 5);
+    let params = reflection.binding(1, 0).unwrap();
+    assert_eq!(params.binding_type, RBindingSetup::BINDING_TYPE_UNIFORM);
+    assert_eq!(params.size_in_bytes, 32);
+    assert_eq!(reflection.visibility(params, "vertexMain", "fragmentMain"), RBindingSetup::VISIBILITY_BOTH);
+    let points = reflection.binding(2, 0).unwrap();
+    assert_eq!(points.binding_type, RBindingSetup::BINDING_TYPE_STORAGE);
+    assert_eq!(reflection.visibility(points, "vertexMain", "fragmentMain"), RBindingSetup::VISIBILITY_VERTEX);
+    let tex = reflection.binding(2, 1).unwrap();
+    assert_eq!(tex.texture_dimension, RPipelineSetup::TEXTURE_DIM_2D_ARRAY);
+    assert!(reflect_shader("fn broken(").is_err());
+  }
+
+  #[test]
+  fn fill_pipeline_setup() {
+    let reflection = reflect_shader(SHADER).unwrap();
+    let mut setup = RPipelineSetup { shader: SHADER, ..Default::default() };
+    reflect_pipeline_setup(&mut setup, &reflection, 256);
+    assert_eq!(setup.uniforms.len(), 1);
+    assert_eq!(setup.uniforms[0].size_in_bytes, 32);
+    assert_eq!(setup.bindings.len(), 3);
+    assert_eq!(setup.bindings[2].binding_type, RBindingSetup::BINDING_TYPE_SAMPLER);
+
+    // undersized uniform is grown to the reflected size
+    let mut setup = RPipelineSetup {
+      shader: SHADER,
+      uniforms: vec![RUniformSetup { bind_slot: 0, visibility: RUniformSetup::VISIBILITY_VERTEX, size_in_bytes: 16 }],
+      ..Default::default()
+    };
+    reflect_pipeline_setup(&mut setup, &reflection, 256);
+    assert_eq!(setup.uniforms[0].size_in_bytes, 32);
+    assert_eq!(setup.uniforms[0].visibility, RUniformSetup::VISIBILITY_BOTH);
+    // undeclared bindings are still filled in
+    assert_eq!(setup.bindings.len(), 3);
+  }
+
+  #[test]
+  fn reflect_compute_bindings() {
+    let reflection = reflect_shader("
+      @group(0) @binding(0) var<storage, read_write> particles: array<vec4f>;
+      @group(0) @binding(1) var output: texture_storage_2d<rgba8unorm, write>;
+
+      @compute @workgroup_size(64)
+      fn computeMain(@builtin(global_invocation_id) id: vec3u) {
+        particles[id.x] += vec4f(0.1);
+        textureStore(output, id.xy, particles[id.x]);
+      }
+    ").unwrap();
+    assert_eq!(reflection.compute_entries, vec!["computeMain"]);
+    assert_eq!(reflection.binding(0, 0).unwrap().binding_type, RBindingSetup::BINDING_TYPE_STORAGE_RW);
+    assert_eq!(reflection.binding(0, 1).unwrap().binding_type, RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE);
+  }
+}
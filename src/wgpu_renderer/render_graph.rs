@@ -0,0 +1,211 @@
+#![allow(dead_code)]
+
+use super::{RCamera, RPipelineId, RTextureId};
+
+#[derive(Debug, PartialEq)]
+pub enum RGraphError {
+  Cycle,
+  ReadWriteConflict(usize),
+}
+
+// output of a render pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RPassTarget {
+  Surface,
+  Texture(RTextureId),
+}
+
+// helper for declaring a single pass in the render graph
+// - viewport: (x, y, width, height) in pixels from the top left, projections use its aspect
+// - scissor: (x, y, width, height) in pixels, drawing outside of it is discarded
+// - camera: draws every object from this camera instead of the one passed to update_object
+// note: clearing always covers the whole target, load the target for passes drawing into
+// a region of an earlier pass
+#[derive(Debug)]
+pub struct RGraphPass<'a> {
+  pub label: &'a str,
+  pub target: RPassTarget,
+  pub inputs: Vec<RTextureId>,
+  pub pipeline_ids: Vec<RPipelineId>,
+  pub color_load: u8,
+  pub clear_color: Option<[f64; 4]>,
+  pub depth_load: u8,
+  pub viewport: Option<[f32; 4]>,
+  pub scissor: Option<[u32; 4]>,
+  pub camera: Option<RCamera>,
+}
+impl Default for RGraphPass<'_> {
+  fn default() -> Self {
+    RGraphPass {
+      label: "graph-pass",
+      target: RPassTarget::Surface,
+      inputs: Vec::new(),
+      pipeline_ids: Vec::new(),
+      color_load: RGraphPass::LOAD_OP_CLEAR,
+      clear_color: None,
+      depth_load: RGraphPass::LOAD_OP_CLEAR,
+      viewport: None,
+      scissor: None,
+      camera: None,
+    }
+  }
+}
+impl RGraphPass<'_> {
+  // load op constants
+  pub const LOAD_OP_CLEAR: u8 = 0;
+  pub const LOAD_OP_LOAD: u8 = 1;
+
+  // viewport + scissor limited to a target of the given size, None when nothing is left to draw
+  pub fn clipped_rects(&self, width: u32, height: u32) -> Option<([f32; 4], [u32; 4])> {
+    let [x, y, w, h] = self.viewport.unwrap_or([0.0, 0.0, width as f32, height as f32]);
+    let x0 = x.clamp(0.0, width as f32);
+    let y0 = y.clamp(0.0, height as f32);
+    let viewport = [x0, y0, (x + w).clamp(0.0, width as f32) - x0, (y + h).clamp(0.0, height as f32) - y0];
+    let [sx, sy, sw, sh] = self.scissor.unwrap_or([0, 0, width, height]);
+    let sx0 = u32::min(sx, width);
+    let sy0 = u32::min(sy, height);
+    let scissor = [sx0, sy0, u32::min(sx.saturating_add(sw), width) - sx0, u32::min(sy.saturating_add(sh), height) - sy0];
+    if viewport[2] < 1.0 || viewport[3] < 1.0 || scissor[2] == 0 || scissor[3] == 0 {
+      return None;
+    }
+    Some((viewport, scissor))
+  }
+}
+
+// collection of passes making up a single frame
+// note: passes are re-ordered so that every pass writing to a texture
+// runs before the passes reading from it
+#[derive(Debug, Default)]
+pub struct RRenderGraph<'a> {
+  pub passes: Vec<RGraphPass<'a>>,
+}
+impl<'a> RRenderGraph<'a> {
+  pub fn new() -> Self {
+    Self { passes: Vec::new() }
+  }
+  pub fn add_pass(&mut self, pass: RGraphPass<'a>) -> usize {
+    self.passes.push(pass);
+    self.passes.len() - 1
+  }
+  pub fn with_pass(mut self, pass: RGraphPass<'a>) -> Self {
+    self.passes.push(pass);
+    self
+  }
+  // returns pass indices in execution order
+  pub fn order(&self) -> Result<Vec<usize>, RGraphError> {
+    let count = self.passes.len();
+    // build dependency edges
+    let mut deps: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for (i, pass) in self.passes.iter().enumerate() {
+      if let RPassTarget::Texture(t) = pass.target {
+        if pass.inputs.contains(&t) {
+          return Err(RGraphError::ReadWriteConflict(i));
+        }
+      }
+      for (j, other) in self.passes.iter().enumerate() {
+        if i == j { continue; }
+        // writers of an input texture run first
+        let reads_other = match other.target {
+          RPassTarget::Texture(t) => pass.inputs.contains(&t),
+          RPassTarget::Surface => false,
+        };
+        // passes sharing a target keep declaration order
+        let same_target = j < i && other.target == pass.target;
+        if reads_other || same_target {
+          deps[i].push(j);
+        }
+      }
+    }
+    // topological sort, preferring declaration order
+    let mut done = vec![false; count];
+    let mut order: Vec<usize> = Vec::with_capacity(count);
+    while order.len() < count {
+      let next = (0..count).find(|i| !done[*i] && deps[*i].iter().all(|d| done[*d]));
+      match next {
+        Some(i) => {
+          done[i] = true;
+          order.push(i);
+        }
+        None => return Err(RGraphError::Cycle),
+      }
+    }
+    Ok(order)
+  }
+}
+
+#[cfg(test)]
+mod render_graph_tests {
+  use super::*;
+
+  #[test]
+  fn order_by_dependency() {
+    let graph = RRenderGraph::new()
+      .with_pass(RGraphPass {
+        label: "screen",
+        inputs: vec![RTextureId(1)],
+        ..Default::default()
+      })
+      .with_pass(RGraphPass {
+        label: "blur",
+        target: RPassTarget::Texture(RTextureId(1)),
+        inputs: vec![RTextureId(0)],
+        ..Default::default()
+      })
+      .with_pass(RGraphPass {
+        label: "scene",
+        target: RPassTarget::Texture(RTextureId(0)),
+        ..Default::default()
+      });
+    assert_eq!(graph.order(), Ok(vec![2, 1, 0]));
+  }
+
+  #[test]
+  fn order_keeps_declaration() {
+    let graph = RRenderGraph::new()
+      .with_pass(RGraphPass { label: "a", ..Default::default() })
+      .with_pass(RGraphPass { label: "b", target: RPassTarget::Texture(RTextureId(0)), ..Default::default() })
+      .with_pass(RGraphPass { label: "c", color_load: RGraphPass::LOAD_OP_LOAD, ..Default::default() });
+    assert_eq!(graph.order(), Ok(vec![0, 1, 2]));
+  }
+
+  #[test]
+  fn order_cycle() {
+    let graph = RRenderGraph::new()
+      .with_pass(RGraphPass {
+        target: RPassTarget::Texture(RTextureId(0)),
+        inputs: vec![RTextureId(1)],
+        ..Default::default()
+      })
+      .with_pass(RGraphPass {
+        target: RPassTarget::Texture(RTextureId(1)),
+        inputs: vec![RTextureId(0)],
+        ..Default::default()
+      });
+    assert_eq!(graph.order(), Err(RGraphError::Cycle));
+  }
+
+  #[test]
+  fn order_read_write_conflict() {
+    let graph = RRenderGraph::new()
+      .with_pass(RGraphPass {
+        target: RPassTarget::Texture(RTextureId(0)),
+        inputs: vec![RTextureId(0)],
+        ..Default::default()
+      });
+    assert_eq!(graph.order(), Err(RGraphError::ReadWriteConflict(0)));
+  }
+
+  #[test]
+  fn clip_viewport_and_scissor() {
+    let full = RGraphPass::default();
+    assert_eq!(full.clipped_rects(800, 600), Some(([0.0, 0.0, 800.0, 600.0], [0, 0, 800, 600])));
+    let right = RGraphPass {
+      viewport: Some([400.0, 0.0, 500.0, 600.0]),
+      scissor: Some([600, 100, 100, 100]),
+      ..Default::default()
+    };
+    assert_eq!(right.clipped_rects(800, 600), Some(([400.0, 0.0, 400.0, 600.0], [600, 100, 100, 100])));
+    let outside = RGraphPass { viewport: Some([900.0, 0.0, 100.0, 100.0]), ..Default::default() };
+    assert_eq!(outside.clipped_rects(800, 600), None);
+  }
+}
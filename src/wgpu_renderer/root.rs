@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
-use std::{fs, path::Path, sync::Arc, num::NonZeroU64};
+use std::{fs, path::{Path, PathBuf}, sync::Arc, num::NonZeroU64, time::{Instant, SystemTime}};
+use std::sync::{Mutex, atomic::{AtomicBool, AtomicU64, Ordering}};
 use winit::window::Window;
 use image::{io::Reader as ImageReader, DynamicImage, GenericImageView};
 use bytemuck::{Pod, Zeroable};
@@ -15,11 +16,36 @@ use super::{
   RPipelineSetup,
   RUniformSetup,
   RObjectSetup,
+  RMaterialSetup,
+  RSamplerSetup,
+  RBindingSetup,
+  RComputeSetup,
+  // shader reflection
+  reflect_shader,
+  reflect_pipeline_setup,
+  // shader preprocessing
+  preprocess_shader,
+  RShaderSource,
+  RPbrParams,
+  RPbrLighting,
+  RPostEffectSetup,
+  RendererConfig,
   RObjectUpdate,
   RCamera,
+  RenderStats,
+  // culling + level of detail
+  RBounds,
+  RFrustum,
+  RRay,
+  RPickHit,
+  select_lod,
   // for text
   draw_str,
   RStringInputs,
+  // render graph
+  RRenderGraph,
+  RGraphPass,
+  RPassTarget,
 };
 
 // -- HELPER STRUCTS --
@@ -55,12 +81,204 @@ pub struct RVertexAnim {
 #[derive(Debug)]
 pub struct RObject {
   pub visible: bool,
-  v_buffer: wgpu::Buffer,
+  mesh: RMeshId,
   v_count: usize,
   pipe_index: usize,
-  index_buffer: Option<wgpu::Buffer>,
-  index_count: u32,
   instances: u32,
+  cam_distance: f32,
+  material: Option<RMaterialId>,
+  vertex_source: Option<RComputeBuffer>,
+  instance_source: Option<RComputeBuffer>,
+  bounds: Option<RBounds>,
+  world_bounds: Option<RBounds>,
+  model: [f32; 16],
+  // camera from the last update, passes without their own camera draw with it
+  camera: RCamera,
+  // per view slot of the mvp buffer, slot 0 is the window view written by update_object
+  in_frustum: Vec<bool>,
+  lods: Vec<(RMeshId, f32)>,
+  lod_mode: u8,
+  lod_hysteresis: f32,
+  lod_level: usize,
+}
+
+// vertex + index range of a mesh buffer, shareable across objects
+#[derive(Debug)]
+pub struct RMesh {
+  buffer: usize,
+  vertex_type: u8,
+  base_vertex: u32,
+  v_count: u32,
+  first_index: u32,
+  index_count: u32,
+  bounds: Option<RBounds>,
+  // kept on the cpu for picking, indices are relative to the mesh
+  positions: Vec<[f32; 3]>,
+  indices: Vec<u32>,
+}
+
+// geometry of one or more meshes, merged meshes share a single buffer
+#[derive(Debug)]
+pub struct RMeshBuffer {
+  v_buffer: wgpu::Buffer,
+  index_buffer: Option<wgpu::Buffer>,
+}
+
+// render pass state set by previous draws, used to skip redundant calls
+#[derive(Debug, Default)]
+struct RDrawState {
+  pipeline: Option<Id<RenderPipeline>>,
+  stencil_reference: Option<u32>,
+  vertex_buffer: Option<Id<Buffer>>,
+  instance_buffer: Option<Id<Buffer>>,
+  index_buffer: Option<Id<Buffer>>,
+  material: Option<RMaterialId>,
+  // mvp slot of the view drawn by the pass
+  view_slot: usize,
+  stats: RenderStats,
+}
+
+// queue counting the bytes uploaded through it, derefs to the wgpu queue for everything else
+#[derive(Debug)]
+pub struct RQueue {
+  queue: wgpu::Queue,
+  uploaded: AtomicU64,
+}
+impl RQueue {
+  fn new(queue: wgpu::Queue) -> Self {
+    Self { queue, uploaded: AtomicU64::new(0) }
+  }
+
+  pub fn write_buffer(&self, buffer: &Buffer, offset: BufferAddress, data: &[u8]) {
+    self.uploaded.fetch_add(data.len() as u64, Ordering::Relaxed);
+    self.queue.write_buffer(buffer, offset, data);
+  }
+
+  pub fn write_texture(&self, texture: ImageCopyTexture, data: &[u8], data_layout: ImageDataLayout, size: Extent3d) {
+    self.uploaded.fetch_add(data.len() as u64, Ordering::Relaxed);
+    self.queue.write_texture(texture, data, data_layout, size);
+  }
+
+  // bytes uploaded since the last call
+  fn take_uploaded(&self) -> u64 {
+    self.uploaded.swap(0, Ordering::Relaxed)
+  }
+}
+impl std::ops::Deref for RQueue {
+  type Target = wgpu::Queue;
+  fn deref(&self) -> &wgpu::Queue {
+    &self.queue
+  }
+}
+
+// object ids rendered into an integer texture, for pixel exact picking
+#[derive(Debug)]
+struct RPicker {
+  shader_mod: ShaderModule,
+  id_layout: BindGroupLayout,
+  // id variant of each pipeline, sharing its group 0 layout
+  variants: Vec<(usize, RenderPipeline)>,
+  id_buffer: Buffer,
+  id_bind_group: BindGroup,
+  target: Texture,
+  zbuffer: Texture,
+  readback: Buffer,
+}
+
+// timestamp queries written at the start + end of each render pass, read back a few frames later
+#[derive(Debug)]
+struct RProfiler {
+  query_set: QuerySet,
+  resolve_buffer: Buffer,
+  readback_buffer: Buffer,
+  // passes timed in the current frame, filled while passes borrow the renderer
+  labels: Mutex<Vec<String>>,
+  // labels of the frame being read back
+  pending: Option<Vec<String>>,
+  mapped: Arc<AtomicBool>,
+  timings: Vec<(String, f32)>,
+}
+impl RProfiler {
+  const MAX_PASSES: u32 = 32;
+
+  fn new(device: &Device) -> Self {
+    let size = Self::MAX_PASSES as u64 * 2 * QUERY_SIZE as u64;
+    Self {
+      query_set: device.create_query_set(&QuerySetDescriptor {
+        label: Some("profiler-query-set"),
+        ty: QueryType::Timestamp,
+        count: Self::MAX_PASSES * 2,
+      }),
+      resolve_buffer: device.create_buffer(&BufferDescriptor {
+        label: Some("profiler-resolve-buffer"),
+        size,
+        usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+      }),
+      readback_buffer: device.create_buffer(&BufferDescriptor {
+        label: Some("profiler-readback-buffer"),
+        size,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+      }),
+      labels: Mutex::new(Vec::new()),
+      pending: None,
+      mapped: Arc::new(AtomicBool::new(false)),
+      timings: Vec::new(),
+    }
+  }
+
+  // query indices for the next pass, None while reading back or out of queries
+  fn begin_pass(&self, label: &str) -> Option<RenderPassTimestampWrites<'_>> {
+    let mut labels = self.labels.lock().unwrap();
+    if self.pending.is_some() || labels.len() as u32 >= Self::MAX_PASSES { return None; }
+    let index = labels.len() as u32 * 2;
+    labels.push(label.to_owned());
+    Some(RenderPassTimestampWrites {
+      query_set: &self.query_set,
+      beginning_of_pass_write_index: Some(index),
+      end_of_pass_write_index: Some(index + 1),
+    })
+  }
+
+  // copy this frame's queries into the readback buffer, true when passes were timed
+  fn resolve(&mut self, encoder: &mut CommandEncoder) -> bool {
+    let labels = std::mem::take(self.labels.get_mut().unwrap());
+    if labels.is_empty() { return false; }
+    let count = labels.len() as u32 * 2;
+    encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+    encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, count as u64 * QUERY_SIZE as u64);
+    self.pending = Some(labels);
+    true
+  }
+
+  // map once the resolve is submitted
+  fn request_readback(&self) {
+    let mapped = Arc::clone(&self.mapped);
+    self.readback_buffer.slice(..).map_async(MapMode::Read, move |r| {
+      if r.is_ok() { mapped.store(true, Ordering::Release); }
+    });
+  }
+
+  // pick up timings once the readback buffer is mapped
+  fn collect(&mut self, period: f32) {
+    if !self.mapped.load(Ordering::Acquire) { return; }
+    if let Some(labels) = self.pending.take() {
+      let data = self.readback_buffer.slice(..).get_mapped_range();
+      let ticks: &[u64] = bytemuck::cast_slice(&data);
+      self.timings = labels.into_iter().enumerate().map(|(i, label)| {
+        let ms = ticks[i * 2 + 1].saturating_sub(ticks[i * 2]) as f32 * period / 1_000_000.0;
+        (label, ms)
+      }).collect();
+    }
+    self.readback_buffer.unmap();
+    self.mapped.store(false, Ordering::Release);
+  }
+
+  fn destroy(&self) {
+    self.resolve_buffer.destroy();
+    self.readback_buffer.destroy();
+  }
 }
 
 #[derive(Debug)]
@@ -69,47 +287,236 @@ pub struct RBindGroup {
   entries: Vec<wgpu::Buffer>,
 }
 
+// pipeline wide resources of one bind group, buffers are kept across rebuilds
+#[derive(Debug)]
+pub struct RResourceGroup {
+  index: u32,
+  bindings: Vec<RBindingSetup>,
+  buffers: Vec<Option<wgpu::Buffer>>,
+  layout: wgpu::BindGroupLayout,
+  bind_group: wgpu::BindGroup,
+}
+
 #[derive(Debug)]
 pub struct RPipeline {
-  pipe: wgpu::RenderPipeline,
+  shader_mod: wgpu::ShaderModule,
+  layout: wgpu::PipelineLayout,
+  bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+  variants: Vec<(wgpu::TextureFormat, u32, wgpu::RenderPipeline)>,
+  vertex_fn: String,
+  fragment_fn: String,
+  cull_mode: Option<Face>,
+  polygon_mode: PolygonMode,
+  topology: PrimitiveTopology,
+  alpha_to_coverage: bool,
+  blend: Option<BlendState>,
+  depth_stencil: DepthStencilState,
+  stencil_reference: u32,
+  transparent: bool,
+  oit_fragment_fn: Option<String>,
+  oit_variants: Vec<(u32, wgpu::RenderPipeline)>,
+  use_materials: bool,
+  shading: u8,
+  frustum_cull: bool,
   objects: Vec<RObject>,
   max_obj_count: usize,
+  // views the mvp buffer has room for, each holding max_obj_count objects
+  view_slots: usize,
   vertex_type: u8,
   max_joints_count: u32,
+  instance_attributes: Vec<VertexAttribute>,
+  instance_stride: u64,
+  texture1_id: Option<RTextureId>,
+  texture2_id: Option<RTextureId>,
+  texture_dims: [u8; 2],
+  samplers: (RSamplerSetup, Option<RSamplerSetup>),
   bind_group0: RBindGroup,
   bind_group1: Option<RBindGroup>,
+  uniform_sizes: Vec<u32>,
+  resource_groups: Vec<RResourceGroup>,
+  shader_name: String,
+  // source before preprocessing, to recompile with other defines
+  shader_source: String,
+  defines: Vec<String>,
+  include_dir: Option<PathBuf>,
   // bind_group2: Option<RBindGroup>,
   // bind_group3: Option<RBindGroup>,
 }
 
+// msaa + depth attachments owned by a single render target
+#[derive(Debug)]
+pub struct RAttachment {
+  target: RPassTarget,
+  msaa: Option<wgpu::Texture>,
+  zbuffer: wgpu::Texture,
+}
+
+// passes making up a single post processing effect
+#[derive(Debug)]
+pub struct RPostEffect {
+  effect: u8,
+  passes: Vec<(RPipelineId, RTextureId)>,
+  output: RTextureId,
+}
+
+// textures, sampler and params shared by any number of objects
+#[derive(Debug)]
+pub struct RMaterial {
+  textures: Vec<RTextureId>,
+  sampler: wgpu::Sampler,
+  params: wgpu::Buffer,
+  bind_group: wgpu::BindGroup,
+}
+
+// image based lighting resources shared by pbr pipelines
+#[derive(Debug)]
+pub struct RIbl {
+  layout: wgpu::BindGroupLayout,
+  bind_group: wgpu::BindGroup,
+  sampler: wgpu::Sampler,
+  irradiance: wgpu::Texture,
+  prefiltered: wgpu::Texture,
+  brdf_lut: wgpu::Texture,
+  lighting: wgpu::Buffer,
+}
+
+// pipelines used while precomputing image based lighting
+struct RIblPasses {
+  shader_mod: wgpu::ShaderModule,
+  layout: wgpu::BindGroupLayout,
+  pipeline_layout: wgpu::PipelineLayout,
+  pipelines: Vec<(String, wgpu::TextureFormat, wgpu::RenderPipeline)>,
+  source: wgpu::Texture,
+}
+
+// compute shader + the resource groups it reads and writes
+#[derive(Debug)]
+pub struct RComputePipeline {
+  shader_mod: wgpu::ShaderModule,
+  pipeline: wgpu::ComputePipeline,
+  resource_groups: Vec<RResourceGroup>,
+}
+
+// shader file backing a pipeline + the files it includes, polled for changes
+#[derive(Debug)]
+struct RShaderWatch {
+  pipeline_id: RPipelineId,
+  path: PathBuf,
+  files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+// downsampling pipelines used to fill texture mip chains, one per texture format
+#[derive(Debug)]
+struct RMipBlit {
+  shader_mod: wgpu::ShaderModule,
+  layout: wgpu::BindGroupLayout,
+  pipeline_layout: wgpu::PipelineLayout,
+  sampler: wgpu::Sampler,
+  pipelines: Vec<(wgpu::TextureFormat, wgpu::RenderPipeline)>,
+}
+
+// accumulation targets for weighted blended transparency, per render target
+#[derive(Debug)]
+pub struct ROitTarget {
+  target: RPassTarget,
+  accum: RTextureId,
+  reveal: RTextureId,
+  accum_msaa: Option<wgpu::Texture>,
+  reveal_msaa: Option<wgpu::Texture>,
+  composite: RPipelineId,
+}
+
+#[derive(Debug)]
+pub struct RPostStack {
+  scene_texture: RTextureId,
+  present_pipeline: RPipelineId,
+  effects: Vec<RPostEffect>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RObjectId (pub usize, pub usize);
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RPipelineId (pub usize);
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RTextureId (pub usize);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RPostEffectId (pub usize);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RMaterialId (pub usize);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RMeshId (pub usize);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RComputeId (pub usize);
+
+// storage buffer binding of a compute pipeline, bound as vertex or instance buffer of render objects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RComputeBuffer {
+  pub compute_id: RComputeId,
+  pub bind_group: u32,
+  pub bind_slot: u32,
+}
+
+const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+const OIT_ACCUM_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+const OIT_REVEAL_FORMAT: TextureFormat = TextureFormat::R16Float;
+const IBL_IRRADIANCE_SIZE: u32 = 32;
+const IBL_PREFILTERED_SIZE: u32 = 128;
+const IBL_PREFILTERED_MIPS: u32 = 6;
+const IBL_BRDF_LUT_SIZE: u32 = 256;
+const PICK_FORMAT: TextureFormat = TextureFormat::R32Uint;
+const PICK_DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
 
 // -- PRIMARY RENDERER INTERFACE --
 #[derive(Debug)]
 pub struct Renderer<'a> {
   surface: wgpu::Surface<'a>,
   surface_format: wgpu::TextureFormat,
+  depth_format: wgpu::TextureFormat,
+  reversed_z: bool,
+  // requested msaa count, sample_count is the effective one clamped to the surface + depth formats
+  requested_sample_count: u32,
+  sample_count: u32,
+  adapter: wgpu::Adapter,
   device: wgpu::Device,
-  queue: wgpu::Queue,
+  queue: RQueue,
   pub config: wgpu::SurfaceConfiguration,
-  msaa: wgpu::Texture,
-  zbuffer: wgpu::Texture,
   limits: wgpu::Limits,
   pub default_cam: RCamera,
   pub clear_color: wgpu::Color,
   pub pipelines: Vec<RPipeline>,
+  pub compute_pipelines: Vec<RComputePipeline>,
   pub textures: Vec<wgpu::Texture>,
+  transient_textures: Vec<(RTextureId, f32)>,
+  attachments: Vec<RAttachment>,
+  post_stack: Option<RPostStack>,
+  oit_enabled: bool,
+  oit_targets: Vec<ROitTarget>,
+  materials: Vec<RMaterial>,
+  meshes: Vec<RMesh>,
+  mesh_buffers: Vec<RMeshBuffer>,
+  material_layout: Option<wgpu::BindGroupLayout>,
+  default_material: Option<RMaterialId>,
+  ibl: Option<RIbl>,
+  skybox: Option<RObjectId>,
+  skybox_enabled: bool,
+  mip_blit: Option<RMipBlit>,
+  shader_watches: Vec<RShaderWatch>,
+  frustum_culling: bool,
+  frame_stats: RenderStats,
+  stats: RenderStats,
+  last_present: Option<Instant>,
+  profiler: Option<RProfiler>,
+  picker: Option<RPicker>,
   font_cache: Option<Vec<u8>>,
 }
 
 impl<'a> Renderer<'a> {
   // Creating some of the wgpu types requires async code
   pub async fn new(window: Arc<Window>) -> Renderer<'a> {
+    Renderer::new_with_config(window, RendererConfig::default()).await
+  }
+
+  pub async fn new_with_config(window: Arc<Window>, renderer_config: RendererConfig) -> Renderer<'a> {
     let size = window.inner_size();
 
     // The instance is a handle to our GPU
@@ -133,7 +540,14 @@ impl<'a> Renderer<'a> {
     // grab device & queue from adapter
     let (device, queue) = adapter.request_device(
       &wgpu::DeviceDescriptor {
-        required_features: wgpu::Features::POLYGON_MODE_LINE | wgpu::Features::POLYGON_MODE_POINT,
+        required_features: wgpu::Features::POLYGON_MODE_LINE | wgpu::Features::POLYGON_MODE_POINT
+          | (adapter.features() & (
+            // enable optional features when available
+            wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES |
+            wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER |
+            wgpu::Features::DEPTH32FLOAT_STENCIL8 |
+            wgpu::Features::TIMESTAMP_QUERY
+          )),
         required_limits: { wgpu::Limits::default() },
         label: None,
       },
@@ -160,54 +574,56 @@ impl<'a> Renderer<'a> {
       desired_maximum_frame_latency: 2,
     };
 
-    let texture_size = wgpu::Extent3d {
-      width: config.width,
-      height: config.height,
-      depth_or_array_layers: 1,
-    };
-
-    // create msaa texture
-    let msaa = device.create_texture(&wgpu::TextureDescriptor {
-      label: Some("msaa-texture"),
-      size: texture_size,
-      sample_count: 4,
-      mip_level_count: 1,
-      dimension: wgpu::TextureDimension::D2,
-      format: surface_format,
-      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-      view_formats: &[]
-    });
-
-    // create zbuffer texture
-    let zbuffer = device.create_texture(&wgpu::TextureDescriptor {
-      label: Some("zbuffer-texture"),
-      size: texture_size,
-      sample_count: 4,
-      mip_level_count: 1,
-      dimension: wgpu::TextureDimension::D2,
-      format: wgpu::TextureFormat::Depth24Plus,
-      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-      view_formats: &[]
-    });
-
     // create default camera setup
     let default_cam = RCamera::new_ortho(0.0, 1000.0);
 
-    return Self {
+    let mut renderer = Self {
       surface,
       surface_format,
+      depth_format: TextureFormat::Depth24Plus,
+      reversed_z: false,
+      requested_sample_count: 1,
+      sample_count: 1,
+      adapter,
       device,
-      queue,
+      queue: RQueue::new(queue),
       config,
       pipelines: Vec::new(),
+      compute_pipelines: Vec::new(),
       textures: Vec::new(),
-      msaa,
-      zbuffer,
+      transient_textures: Vec::new(),
+      attachments: Vec::new(),
+      post_stack: None,
+      oit_enabled: false,
+      oit_targets: Vec::new(),
+      materials: Vec::new(),
+      meshes: Vec::new(),
+      mesh_buffers: Vec::new(),
+      material_layout: None,
+      default_material: None,
+      ibl: None,
+      skybox: None,
+      skybox_enabled: false,
+      mip_blit: None,
+      shader_watches: Vec::new(),
+      frustum_culling: true,
+      frame_stats: RenderStats::default(),
+      stats: RenderStats::default(),
+      last_present: None,
+      profiler: None,
+      picker: None,
       limits: Limits::default(),
       clear_color: Color { r: 0.01, g: 0.01, b: 0.02, a: 1.0 },
       default_cam,
       font_cache: None,
     };
+    renderer.set_depth_format(renderer_config.depth_format);
+    if renderer_config.reversed_z {
+      renderer.set_reversed_z(true);
+    }
+    renderer.set_sample_count(renderer_config.sample_count);
+    renderer.prepare_ibl();
+    renderer
   }
 
   pub fn resize_canvas(&mut self, width: u32, height: u32) {
@@ -216,39 +632,117 @@ impl<'a> Renderer<'a> {
       self.config.height = height;
       self.surface.configure(&self.device, &self.config);
 
-      let texture_size = wgpu::Extent3d {
-        width,
-        height,
-        depth_or_array_layers: 1,
-      };
+      // drop msaa + zbuffer textures, these get rebuilt on demand
+      self.clear_attachments();
 
-      // remake msaa texture
-      let msaa = self.device.create_texture(&wgpu::TextureDescriptor {
-        label: Some("msaa-texture"),
-        size: texture_size,
-        sample_count: 4,
-        mip_level_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: self.surface_format,
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        view_formats: &[]
-      });
-      self.msaa.destroy();
-      self.msaa = msaa;
-
-      // remake zbuffer texture
-      let zbuffer = self.device.create_texture(&wgpu::TextureDescriptor {
-        label: Some("zbuffer-texture"),
-        size: texture_size,
-        sample_count: 4,
-        mip_level_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Depth24Plus,
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        view_formats: &[]
-      });
-      self.zbuffer.destroy();
-      self.zbuffer = zbuffer;
+      // resize window-relative textures
+      for i in 0..self.transient_textures.len() {
+        let (texture_id, scale) = self.transient_textures[i];
+        let w = u32::max(1, (width as f32 * scale) as u32);
+        let h = u32::max(1, (height as f32 * scale) as u32);
+        self.update_texture_size(texture_id, None, w, h);
+        for p in 0..self.pipelines.len() {
+          let pipeline = &self.pipelines[p];
+          if pipeline.texture1_id == Some(texture_id) || pipeline.texture2_id == Some(texture_id) {
+            self.rebuild_bind_group0(RPipelineId(p));
+          }
+        }
+      }
+    }
+  }
+
+  fn create_attachments(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    depth_format: wgpu::TextureFormat,
+    sample_count: u32,
+  ) -> (Option<wgpu::Texture>, wgpu::Texture) {
+    let texture_size = wgpu::Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    };
+
+    // create msaa texture
+    let msaa = Renderer::create_msaa_texture(device, width, height, format, sample_count);
+
+    // create zbuffer texture
+    let zbuffer = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("zbuffer-texture"),
+      size: texture_size,
+      sample_count,
+      mip_level_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: depth_format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+      view_formats: &[]
+    });
+
+    (msaa, zbuffer)
+  }
+
+  fn create_msaa_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+  ) -> Option<wgpu::Texture> {
+    if sample_count < 2 { return None; }
+    Some(device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("msaa-texture"),
+      size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+      sample_count,
+      mip_level_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+      view_formats: &[]
+    }))
+  }
+
+  fn clear_attachments(&mut self) {
+    for att in &mut self.attachments {
+      if let Some(msaa) = &att.msaa {
+        msaa.destroy();
+      }
+      att.zbuffer.destroy();
+    }
+    self.attachments.clear();
+  }
+
+  // note: falls back to the highest count supported by the surface and depth formats
+  pub fn set_sample_count(&mut self, sample_count: u32) {
+    if ![1, 2, 4, 8].contains(&sample_count) {
+      eprintln!("Err: Sample count {} must be 1, 2, 4 or 8", sample_count);
+      return;
+    }
+    self.requested_sample_count = sample_count;
+    self.sample_count = self.supported_sample_count(self.surface_format);
+    if self.sample_count != sample_count {
+      eprintln!("Err: Sample count {} is not supported by device, using {}", sample_count, self.sample_count);
+    }
+    // rebuild attachments + pipelines
+    self.clear_attachments();
+    self.rebuild_pipeline_variants();
+  }
+
+  pub fn sample_count(&self) -> u32 {
+    self.sample_count
+  }
+
+  // highest count up to the requested sample count usable with format
+  fn supported_sample_count(&self, format: TextureFormat) -> u32 {
+    clamp_sample_count(self.requested_sample_count, self.format_flags(format), self.format_flags(self.depth_format))
+  }
+
+  fn format_flags(&self, format: TextureFormat) -> TextureFormatFeatureFlags {
+    if self.device.features().contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+      self.adapter.get_texture_format_features(format).flags
+    } else {
+      format.guaranteed_format_features(self.device.features()).flags
     }
   }
 
@@ -271,6 +765,13 @@ impl<'a> Renderer<'a> {
   }
 
   pub fn add_texture(&mut self, width: u32, height: u32, texture_path: Option<&Path>, use_device_format: bool) -> RTextureId {
+    let tex_format = if use_device_format { self.surface_format }
+    else { TextureFormat::Rgba8Unorm };
+    self.add_texture_with_format(width, height, texture_path, tex_format)
+  }
+
+  // note: depth formats cannot be loaded from file, and 32-bit float formats are not filterable
+  pub fn add_texture_with_format(&mut self, width: u32, height: u32, texture_path: Option<&Path>, tex_format: TextureFormat) -> RTextureId {
     let id = self.textures.len();
     let mut texture_size = Extent3d { width, height, depth_or_array_layers: 1 };
     let mut texture_data: Option<DynamicImage> = None;
@@ -294,71 +795,156 @@ impl<'a> Renderer<'a> {
       };
     }
 
+    // only image textures get a mip chain, render targets are drawn at a single level
+    let mip_level_count = if texture_data.is_some() && self.supports_mipmaps(tex_format) {
+      mip_level_count(texture_size.width, texture_size.height)
+    } else { 1 };
+
     // create texture
-    let tex_format = if use_device_format { self.surface_format } 
-    else { TextureFormat::Rgba8Unorm };
     let texture = self.device.create_texture(&TextureDescriptor {
       label: Some("input-texture"),
       size: texture_size,
       sample_count: 1,
-      mip_level_count: 1,
+      mip_level_count,
       dimension: TextureDimension::D2,
       format: tex_format,
-      usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+      usage: Renderer::texture_usage(tex_format),
       view_formats: &[]
     });
     if let Some(img) = texture_data {
       // copy image into texture
-      self.queue.write_texture(
-        ImageCopyTexture {
-          texture: &texture,
-          mip_level: 0,
-          origin: Origin3d::ZERO,
-          aspect: TextureAspect::All,
-        }, 
-        &img.to_rgba8(),
-        ImageDataLayout {
-          offset: 0,
-          bytes_per_row: Some(4 * texture_size.width),
-          rows_per_image: Some(texture_size.height),
-        },
-        texture_size
-      );
+      self.write_image(&texture, &img);
     }
     // add to cache
     self.textures.push(texture);
+    self.generate_mipmaps(RTextureId(id));
     RTextureId(id)
   }
 
+  // 2d texture array from equally sized images, one layer per image (ie sprite sheets, terrain layers)
+  pub fn add_texture_array(&mut self, layer_paths: &[&Path], tex_format: TextureFormat) -> RTextureId {
+    let mut layers: Vec<DynamicImage> = Vec::with_capacity(layer_paths.len());
+    for path in layer_paths {
+      match ImageReader::open(path).map(|r| r.decode()) {
+        Ok(Ok(img)) => layers.push(img),
+        _ => eprintln!("Err: Could not load texture layer {:?}", path),
+      }
+    }
+    let (width, height) = layers.first().map(|l| l.dimensions()).unwrap_or((1, 1));
+    let mip_level_count = if self.supports_mipmaps(tex_format) { mip_level_count(width, height) } else { 1 };
+    let texture = self.device.create_texture(&TextureDescriptor {
+      label: Some("input-texture-array"),
+      size: Extent3d { width, height, depth_or_array_layers: u32::max(1, layer_paths.len() as u32) },
+      sample_count: 1,
+      mip_level_count,
+      dimension: TextureDimension::D2,
+      format: tex_format,
+      usage: Renderer::texture_usage(tex_format),
+      view_formats: &[]
+    });
+    for (layer, img) in layers.iter().enumerate() {
+      if img.dimensions() != (width, height) {
+        eprintln!("Err: Texture array layers must be of equal size");
+        continue;
+      }
+      self.write_image_layer(&texture, img, layer as u32);
+    }
+    self.textures.push(texture);
+    let texture_id = RTextureId(self.textures.len() - 1);
+    self.generate_mipmaps(texture_id);
+    texture_id
+  }
+
+  pub fn update_texture_layer(&mut self, texture_id: RTextureId, layer: u32, texture_path: &Path) {
+    let texture = &self.textures[texture_id.0];
+    if layer >= texture.depth_or_array_layers() || texture.dimension() != TextureDimension::D2 {
+      eprintln!("Err: Texture layer {} out of range", layer);
+      return;
+    }
+    match ImageReader::open(texture_path).map(|r| r.decode()) {
+      Ok(Ok(img)) if img.dimensions() != (texture.width(), texture.height()) => {
+        eprintln!("Err: Texture array layers must be of equal size");
+      }
+      Ok(Ok(img)) => {
+        self.write_image_layer(texture, &img, layer);
+        self.generate_mipmaps(texture_id);
+      }
+      _ => eprintln!("Err: Could not load image file {:?}", texture_path),
+    }
+  }
+
+  // volume texture (ie 3d LUTs, density fields), data is tightly packed texels in x, y, z order
+  pub fn add_texture_3d(&mut self, width: u32, height: u32, depth: u32, tex_format: TextureFormat, data: Option<&[u8]>) -> RTextureId {
+    let texture = self.device.create_texture(&TextureDescriptor {
+      label: Some("input-texture-3d"),
+      size: Extent3d { width, height, depth_or_array_layers: depth },
+      sample_count: 1,
+      mip_level_count: 1,
+      dimension: TextureDimension::D3,
+      format: tex_format,
+      usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | storage_usage(tex_format),
+      view_formats: &[]
+    });
+    self.textures.push(texture);
+    let texture_id = RTextureId(self.textures.len() - 1);
+    if let Some(bytes) = data {
+      self.update_texture_3d(texture_id, bytes);
+    }
+    texture_id
+  }
+
+  pub fn update_texture_3d(&mut self, texture_id: RTextureId, data: &[u8]) {
+    let texture = &self.textures[texture_id.0];
+    let size = texture.size();
+    let texel_size = texture.format().block_copy_size(None).unwrap_or(0);
+    let expected = (texel_size * size.width * size.height * size.depth_or_array_layers) as usize;
+    if texture.dimension() != TextureDimension::D3 || texel_size == 0 || data.len() != expected {
+      eprintln!("Err: 3d texture data must be {} bytes, got {}", expected, data.len());
+      return;
+    }
+    self.queue.write_texture(
+      ImageCopyTexture {
+        texture,
+        mip_level: 0,
+        origin: Origin3d::ZERO,
+        aspect: TextureAspect::All,
+      },
+      data,
+      ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(texel_size * size.width),
+        rows_per_image: Some(size.height),
+      },
+      size
+    );
+  }
+
+  // texture sized relative to the window, resized automatically with the canvas
+  pub fn add_transient_texture(&mut self, scale: f32, use_device_format: bool) -> RTextureId {
+    let tex_format = if use_device_format { self.surface_format }
+    else { TextureFormat::Rgba8Unorm };
+    self.add_transient_texture_with_format(scale, tex_format)
+  }
+
+  pub fn add_transient_texture_with_format(&mut self, scale: f32, tex_format: TextureFormat) -> RTextureId {
+    let width = u32::max(1, (self.config.width as f32 * scale) as u32);
+    let height = u32::max(1, (self.config.height as f32 * scale) as u32);
+    let texture_id = self.add_texture_with_format(width, height, None, tex_format);
+    self.transient_textures.push((texture_id, scale));
+    texture_id
+  }
+
   pub fn update_texture(&mut self, texture_id: RTextureId, texture_path: &Path) {
-    let texture = &mut self.textures[texture_id.0];
+    let texture = &self.textures[texture_id.0];
     match ImageReader::open(texture_path) {
       Ok(img_file) => match img_file.decode() {
+        Ok(img_data) if img_data.dimensions() != (texture.width(), texture.height()) => {
+          eprintln!("Err: Image size does not match texture size");
+        }
         Ok(img_data) => {
-          // get data from image file
-          let rgba8 = img_data.to_rgba8();
-          let dimensions = img_data.dimensions();
-          let texture_size = Extent3d { 
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1
-          };
           // write to texture
-          self.queue.write_texture(
-            ImageCopyTexture {
-              texture: &texture,
-              mip_level: 0,
-              origin: Origin3d::ZERO,
-              aspect: TextureAspect::All,
-            },
-            &rgba8,
-            ImageDataLayout {
-              offset: 0,
-              bytes_per_row: Some(4 * dimensions.0),
-              rows_per_image: Some(dimensions.1),
-            },
-            texture_size
-          );
+          self.write_image(texture, &img_data);
+          self.generate_mipmaps(texture_id);
         }
         Err(..) => {
           eprintln!("Err: Could not decode image file");
@@ -370,46 +956,344 @@ impl<'a> Renderer<'a> {
     }
   }
 
-  pub fn update_texture_size(&mut self, texture_id: RTextureId, pipeline_id: Option<RPipelineId>, width: u32, height: u32) {
-    let old_texture = &mut self.textures[texture_id.0];
-
-    // make new texture
-    let texture_size = Extent3d { width, height, depth_or_array_layers: 1 };
-    let new_texture = self.device.create_texture(&TextureDescriptor {
-      label: Some("input-texture"),
-      size: texture_size,
-      sample_count: 1,
-      mip_level_count: 1,
-      dimension: TextureDimension::D2,
-      format: old_texture.format(),
-      usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-      view_formats: &[]
-    });
-    old_texture.destroy();
-    self.textures[texture_id.0] = new_texture;
-
-    // update bind group
-    if let Some(p_id) = pipeline_id {
-      let new_bind_id = {
-        let pipeline = &self.pipelines[p_id.0];
-        let pipe = &pipeline.pipe;
-        self.add_bind_group0(pipe, pipeline.max_obj_count, Some(texture_id), None, pipeline.vertex_type, pipeline.max_joints_count) // TODO: handle resizing second texture
-      };
-      let pipeline = &mut self.pipelines[p_id.0];
-      pipeline.bind_group0 = new_bind_id;
+  fn texture_usage(format: TextureFormat) -> TextureUsages {
+    if format.is_depth_stencil_format() {
+      TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+    } else {
+      TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | storage_usage(format)
     }
   }
 
-  pub fn add_pipeline(&mut self, setup: RPipelineSetup) -> RPipelineId {
-    let id: usize = self.pipelines.len();
+  // convert image into texel data matching texture format
+  fn write_image(&self, texture: &Texture, img: &DynamicImage) {
+    self.write_image_layer(texture, img, 0);
+  }
 
-    // translate cullmode
-    let cull_mode: Option<Face> = match setup.cull_mode {
-      1 => Some(Face::Back),
-      2 => Some(Face::Front),
-      _ => None
+  fn write_image_layer(&self, texture: &Texture, img: &DynamicImage, layer: u32) {
+    let (width, height) = img.dimensions();
+    let (bytes, texel_size): (Vec<u8>, u32) = match texture.format() {
+      TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => (img.to_rgba8().into_raw(), 4),
+      TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+        let mut data = img.to_rgba8().into_raw();
+        for px in data.chunks_exact_mut(4) {
+          px.swap(0, 2);
+        }
+        (data, 4)
+      }
+      TextureFormat::Rgba16Float => {
+        let data: Vec<u16> = img.to_rgba32f().into_raw().into_iter().map(f32_to_f16).collect();
+        (bytemuck::cast_slice(&data).to_vec(), 8)
+      }
+      TextureFormat::Rgba32Float => (bytemuck::cast_slice(&img.to_rgba32f().into_raw()).to_vec(), 16),
+      TextureFormat::R8Unorm => (img.to_luma8().into_raw(), 1),
+      TextureFormat::R32Float => (bytemuck::cast_slice(&img.to_luma32f().into_raw()).to_vec(), 4),
+      f => {
+        eprintln!("Err: Cannot load image into texture format {:?}", f);
+        return;
+      }
     };
-
+    self.queue.write_texture(
+      ImageCopyTexture {
+        texture,
+        mip_level: 0,
+        origin: Origin3d { x: 0, y: 0, z: layer },
+        aspect: TextureAspect::All,
+      },
+      &bytes,
+      ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(texel_size * width),
+        rows_per_image: Some(height),
+      },
+      Extent3d { width, height, depth_or_array_layers: 1 }
+    );
+  }
+
+  // mip chains are filled by rendering, so the format must be renderable and filterable
+  fn supports_mipmaps(&self, format: TextureFormat) -> bool {
+    let features = format.guaranteed_format_features(self.device.features());
+    features.allowed_usages.contains(TextureUsages::RENDER_ATTACHMENT)
+      && features.flags.contains(TextureFormatFeatureFlags::FILTERABLE)
+  }
+
+  fn create_mip_blit(&self) -> RMipBlit {
+    let shader_mod = self.device.create_shader_module(ShaderModuleDescriptor {
+      label: Some("mipmap-shader-module"),
+      source: ShaderSource::Wgsl(include_str!("../embed_assets/mipmap.wgsl").into()),
+    });
+    let layout = self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("mipmap-layout"),
+      entries: &[
+        BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Sampler(SamplerBindingType::Filtering),
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 1,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+      ],
+    });
+    let pipeline_layout = self.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+      label: Some("mipmap-pipeline-layout"),
+      bind_group_layouts: &[&layout],
+      push_constant_ranges: &[]
+    });
+    let sampler = self.device.create_sampler(&SamplerDescriptor {
+      label: Some("mipmap-sampler"),
+      mag_filter: FilterMode::Linear,
+      min_filter: FilterMode::Linear,
+      ..Default::default()
+    });
+    RMipBlit { shader_mod, layout, pipeline_layout, sampler, pipelines: Vec::new() }
+  }
+
+  // downsample mip 0 through the rest of the chain, for every array layer
+  fn generate_mipmaps(&mut self, texture_id: RTextureId) {
+    let texture = &self.textures[texture_id.0];
+    let format = texture.format();
+    if texture.mip_level_count() < 2 || texture.dimension() != TextureDimension::D2 { return; }
+    if self.mip_blit.is_none() {
+      self.mip_blit = Some(self.create_mip_blit());
+    }
+    let blit = self.mip_blit.as_mut().unwrap();
+    if !blit.pipelines.iter().any(|(f, _)| *f == format) {
+      let pipeline = self.device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("mipmap-pipeline"),
+        layout: Some(&blit.pipeline_layout),
+        vertex: VertexState {
+          module: &blit.shader_mod,
+          entry_point: "vertexMain",
+          buffers: &[],
+          compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(FragmentState {
+          module: &blit.shader_mod,
+          entry_point: "fragmentMain",
+          targets: &[Some(ColorTargetState {
+            format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+          })],
+          compilation_options: PipelineCompilationOptions::default(),
+        }),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        primitive: PrimitiveState::default(),
+        multiview: None,
+      });
+      blit.pipelines.push((format, pipeline));
+    }
+    let blit = self.mip_blit.as_ref().unwrap();
+    let (_, pipeline) = blit.pipelines.iter().find(|(f, _)| *f == format).unwrap();
+    let texture = &self.textures[texture_id.0];
+    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("mipmap-encoder") });
+    for layer in 0..texture.depth_or_array_layers() {
+      for mip in 1..texture.mip_level_count() {
+        let level_view = |level: u32| texture.create_view(&TextureViewDescriptor {
+          dimension: Some(TextureViewDimension::D2),
+          base_array_layer: layer,
+          array_layer_count: Some(1),
+          base_mip_level: level,
+          mip_level_count: Some(1),
+          ..Default::default()
+        });
+        let src_view = level_view(mip - 1);
+        let dst_view = level_view(mip);
+        let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+          label: Some("mipmap-bind-group"),
+          layout: &blit.layout,
+          entries: &[
+            BindGroupEntry { binding: 0, resource: BindingResource::Sampler(&blit.sampler) },
+            BindGroupEntry { binding: 1, resource: BindingResource::TextureView(&src_view) },
+          ],
+        });
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+          label: Some("mipmap-pass"),
+          color_attachments: &[Some(RenderPassColorAttachment {
+            view: &dst_view,
+            resolve_target: None,
+            ops: Operations { load: LoadOp::Clear(Color::TRANSPARENT), store: StoreOp::Store },
+          })],
+          depth_stencil_attachment: None,
+          occlusion_query_set: None,
+          timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+      }
+    }
+    self.queue.submit(std::iter::once(encoder.finish()));
+  }
+
+  pub fn update_texture_size(&mut self, texture_id: RTextureId, pipeline_id: Option<RPipelineId>, width: u32, height: u32) {
+    let old_texture = &mut self.textures[texture_id.0];
+
+    // make new texture
+    let texture_size = Extent3d { width, height, depth_or_array_layers: 1 };
+    let mip_level_count = if old_texture.mip_level_count() > 1 { mip_level_count(width, height) } else { 1 };
+    let new_texture = self.device.create_texture(&TextureDescriptor {
+      label: Some("input-texture"),
+      size: texture_size,
+      sample_count: 1,
+      mip_level_count,
+      dimension: TextureDimension::D2,
+      format: old_texture.format(),
+      usage: Renderer::texture_usage(old_texture.format()),
+      view_formats: &[]
+    });
+    old_texture.destroy();
+    self.textures[texture_id.0] = new_texture;
+
+    // update bind group
+    if let Some(p_id) = pipeline_id {
+      self.rebuild_bind_group0(p_id);
+    }
+    for m in 0..self.materials.len() {
+      if self.materials[m].textures.contains(&texture_id) {
+        self.rebuild_material(RMaterialId(m));
+      }
+    }
+    for p in 0..self.pipelines.len() {
+      for g in 0..self.pipelines[p].resource_groups.len() {
+        if self.pipelines[p].resource_groups[g].bindings.iter().any(|b| b.texture_id == Some(texture_id)) {
+          self.rebuild_resource_group(RPipelineId(p), g);
+        }
+      }
+    }
+    for c in 0..self.compute_pipelines.len() {
+      for g in 0..self.compute_pipelines[c].resource_groups.len() {
+        if self.compute_pipelines[c].resource_groups[g].bindings.iter().any(|b| b.texture_id == Some(texture_id)) {
+          self.rebuild_compute_group(RComputeId(c), g);
+        }
+      }
+    }
+  }
+
+  // slot 0 is the shared sampler, slot 1 the texture2 sampler if the pipeline was created with one
+  pub fn set_pipeline_sampler(&mut self, pipeline_id: RPipelineId, slot: usize, setup: RSamplerSetup) {
+    let samplers = &mut self.pipelines[pipeline_id.0].samplers;
+    let current = match (slot, &mut samplers.1) {
+      (0, _) => &mut samplers.0,
+      (1, Some(texture2_sampler)) => texture2_sampler,
+      _ => {
+        eprintln!("Err: Pipeline has no sampler in slot {}", slot);
+        return;
+      }
+    };
+    // comparison samplers bind to a different layout
+    if current.compare.is_some() != setup.compare.is_some() {
+      eprintln!("Err: Cannot switch between comparison and filtering samplers after pipeline creation");
+      return;
+    }
+    *current = setup;
+    let pipe = &self.pipelines[pipeline_id.0];
+    let mut samplers = pipe.samplers;
+    self.group0_sample_types([pipe.texture1_id, pipe.texture2_id], &mut samplers);
+    self.pipelines[pipeline_id.0].samplers = samplers;
+    self.rebuild_bind_group0(pipeline_id);
+  }
+
+  // sample types of the texture1 + texture2 bindings, samplers reading an unfilterable texture
+  // (ie 32 bit float formats) are switched to nearest filtering
+  fn group0_sample_types(&self, textures: [Option<RTextureId>; 2], samplers: &mut (RSamplerSetup, Option<RSamplerSetup>)) -> [TextureSampleType; 2] {
+    let features = self.device.features();
+    let format = |texture: Option<RTextureId>| texture.map(|tx_id| self.textures[tx_id.0].format());
+    let sample_types = [
+      texture_sample_type(&samplers.0, format(textures[0]), features),
+      texture_sample_type(samplers.1.as_ref().unwrap_or(&samplers.0), format(textures[1]), features),
+    ];
+    let unfilterable = |t: &TextureSampleType| *t == TextureSampleType::Float { filterable: false };
+    let shared = if samplers.1.is_some() { &sample_types[..1] } else { &sample_types[..] };
+    if shared.iter().any(unfilterable) {
+      samplers.0 = nearest_sampler(&samplers.0);
+    }
+    if let (Some(sampler), true) = (&mut samplers.1, unfilterable(&sample_types[1])) {
+      *sampler = nearest_sampler(sampler);
+    }
+    sample_types
+  }
+
+  // returns the replaced bind group, its buffers stay alive until dropped
+  fn rebuild_bind_group0(&mut self, pipeline_id: RPipelineId) -> RBindGroup {
+    let new_bind_group = {
+      let pipeline = &self.pipelines[pipeline_id.0];
+      self.add_bind_group0(
+        &pipeline.bind_group_layouts[0],
+        pipeline.max_obj_count * pipeline.view_slots,
+        [(pipeline.texture1_id, pipeline.texture_dims[0]), (pipeline.texture2_id, pipeline.texture_dims[1])],
+        &pipeline.samplers,
+        pipeline.vertex_type,
+        pipeline.max_joints_count
+      )
+    };
+    std::mem::replace(&mut self.pipelines[pipeline_id.0].bind_group0, new_bind_group)
+  }
+
+  // room for more views in the mvp buffer, copying over the window view + joint transforms
+  fn grow_view_slots(&mut self, pipeline_id: RPipelineId, view_slots: usize, encoder: &mut CommandEncoder) {
+    self.pipelines[pipeline_id.0].view_slots = view_slots;
+    let old = self.rebuild_bind_group0(pipeline_id);
+    let pipe = &self.pipelines[pipeline_id.0];
+    let window_size = self.limits.min_uniform_buffer_offset_alignment as u64 * pipe.max_obj_count as u64;
+    for (i, (old_buffer, new_buffer)) in old.entries.iter().zip(&pipe.bind_group0.entries).enumerate() {
+      let size = if i == 0 { window_size } else { old_buffer.size() };
+      encoder.copy_buffer_to_buffer(old_buffer, 0, new_buffer, 0, size);
+    }
+  }
+
+  pub fn add_pipeline(&mut self, setup: RPipelineSetup) -> RPipelineId {
+    self.add_named_pipeline(setup, "shader")
+  }
+
+  // name is used for error locations of the shader source
+  fn add_named_pipeline(&mut self, mut setup: RPipelineSetup, name: &str) -> RPipelineId {
+    let id: usize = self.pipelines.len();
+
+    // built-in shading models
+    if setup.shading == RPipelineSetup::SHADING_PBR {
+      setup.shader = include_str!("../embed_assets/pbr.wgsl");
+      setup.vertex_fn = "vertexMain";
+      setup.fragment_fn = "fragmentMain";
+      setup.vertex_type = RPipelineSetup::VERTEX_TYPE_STATIC;
+      setup.use_materials = true;
+    }
+
+    // expand includes + feature flags
+    let defines = pipeline_defines(&setup, self.reversed_z);
+    let include_dir = setup.include_dir.map(|d| d.to_path_buf());
+    let source = match preprocess_shader(setup.shader, name, &defines, setup.include_dir) {
+      Ok(source) => source,
+      Err(e) => {
+        eprintln!("Err: Could not preprocess shader\n{}", e);
+        RShaderSource { code: setup.shader.to_owned(), ..Default::default() }
+      }
+    };
+
+    // reflect shader to fill in or check custom bindings before wgpu validates them
+    match reflect_shader(&source.code) {
+      Ok(reflection) => {
+        reflect_pipeline_setup(&mut setup, &reflection, self.limits.min_uniform_buffer_offset_alignment);
+      }
+      Err(e) => eprintln!("Err: Could not parse shader\n{}", source.map_error(&e)),
+    }
+
+    // translate cullmode
+    let cull_mode: Option<Face> = match setup.cull_mode {
+      1 => Some(Face::Back),
+      2 => Some(Face::Front),
+      _ => None
+    };
+
     // translate polygon mode
     let (polygon_mode, topology): (PolygonMode, PrimitiveTopology) = match setup.poly_mode {
       1 => (PolygonMode::Line, PrimitiveTopology::LineList),
@@ -420,8 +1304,13 @@ impl<'a> Renderer<'a> {
     // build render pipeline
     let shader_mod = self.device.create_shader_module(ShaderModuleDescriptor {
       label: Some("shader-module"),
-      source: ShaderSource::Wgsl(setup.shader.into()),
+      source: ShaderSource::Wgsl(source.code.into()),
     });
+    // texture sample types follow the bound textures, switching samplers of unfilterable ones to nearest
+    let mut samplers = (setup.sampler, setup.texture2_sampler);
+    let sample_types = self.group0_sample_types([setup.texture1_id, setup.texture2_id], &mut samplers);
+    (setup.sampler, setup.texture2_sampler) = samplers;
+    let sampler_types = if setup.texture2_sampler.is_some() { &sample_types[..1] } else { &sample_types[..] };
     // switch between static/dynamic vertex bind group entries
     let mut bind_group0_entries: Vec<BindGroupLayoutEntry> = vec![
       // mvp matrix
@@ -439,7 +1328,7 @@ impl<'a> Renderer<'a> {
       BindGroupLayoutEntry {
         binding: 1,
         visibility: ShaderStages::FRAGMENT,
-        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+        ty: BindingType::Sampler(sampler_binding_type(&setup.sampler, sampler_types)),
         count: None,
       },
       // texture 1
@@ -447,8 +1336,8 @@ impl<'a> Renderer<'a> {
         binding: 2,
         visibility: ShaderStages::FRAGMENT,
         ty: BindingType::Texture {
-          sample_type: TextureSampleType::Float { filterable: true },
-          view_dimension: TextureViewDimension::D2,
+          sample_type: sample_types[0],
+          view_dimension: view_dimension(setup.texture1_dimension),
           multisampled: false,
         },
         count: None,
@@ -458,13 +1347,22 @@ impl<'a> Renderer<'a> {
         binding: 3,
         visibility: ShaderStages::FRAGMENT,
         ty: BindingType::Texture {
-          sample_type: TextureSampleType::Float { filterable: true },
-          view_dimension: TextureViewDimension::D2,
+          sample_type: sample_types[1],
+          view_dimension: view_dimension(setup.texture2_dimension),
           multisampled: false,
         },
         count: None,
       },
     ];
+    // texture 2 sampler
+    if let Some(texture2_sampler) = &setup.texture2_sampler {
+      bind_group0_entries.push(BindGroupLayoutEntry {
+        binding: 5,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Sampler(sampler_binding_type(texture2_sampler, &sample_types[1..])),
+        count: None,
+      });
+    }
     if setup.vertex_type == RPipelineSetup::VERTEX_TYPE_ANIM {
       bind_group0_entries.push(BindGroupLayoutEntry {
         binding: 4,
@@ -477,14 +1375,14 @@ impl<'a> Renderer<'a> {
         count: None,
       });
     }
-    let bind_group0_layout = self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-      label: Some("bind-group0-layout"),
-      entries: &bind_group0_entries
-    });
-    let mut bind_group_container: Vec<&BindGroupLayout> = vec![&bind_group0_layout];
+    let mut bind_group_layouts: Vec<BindGroupLayout> = vec![
+      self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("bind-group0-layout"),
+        entries: &bind_group0_entries
+      })
+    ];
     // build custom bind group layout
-    let bind_group1_layout: BindGroupLayout;
-    if setup.uniforms.len() > 0 {
+    if !setup.uniforms.is_empty() {
       let mut entries: Vec<BindGroupLayoutEntry> = Vec::new();
       // add bind group entries to layout
       for u in &setup.uniforms {
@@ -504,21 +1402,413 @@ impl<'a> Renderer<'a> {
           count: None
         });
       }
-      bind_group1_layout = self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-        label: Some("bind-group0-layout"),
-        entries: &entries.as_slice()
-      });
-      bind_group_container.push(&bind_group1_layout);
+      bind_group_layouts.push(self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("bind-group1-layout"),
+        entries: &entries
+      }));
+    }
+    // materials always bind at group 2, pbr lighting at group 3
+    if setup.use_materials {
+      self.prepare_materials();
+    }
+    let reserved_groups = [
+      !setup.uniforms.is_empty(),
+      setup.use_materials,
+      setup.shading == RPipelineSetup::SHADING_PBR,
+    ];
+    // custom resources fill the remaining groups, unused groups in between are left empty
+    let resource_groups = self.add_resource_groups(&setup.bindings, [true, reserved_groups[0], reserved_groups[1], reserved_groups[2]], false);
+    let mut layout_refs: Vec<&BindGroupLayout> = vec![&bind_group_layouts[0]];
+    for group in 1..=3 {
+      let layout = match group {
+        1 if reserved_groups[0] => &bind_group_layouts[1],
+        2 if reserved_groups[1] => self.material_layout.as_ref().unwrap(),
+        3 if reserved_groups[2] => &self.ibl.as_ref().unwrap().layout,
+        _ => match resource_groups.iter().find(|g| g.index == group) {
+          Some(g) => &g.layout,
+          None => break,
+        }
+      };
+      layout_refs.push(layout);
     }
     let pipeline_layout = self.device.create_pipeline_layout(&PipelineLayoutDescriptor {
       label: Some("pipeline-layout"),
-      bind_group_layouts: bind_group_container.as_slice(),
+      bind_group_layouts: &layout_refs,
+      push_constant_ranges: &[]
+    });
+
+    // build bind groups
+    let bind_group0: RBindGroup = self.add_bind_group0(
+      &bind_group_layouts[0],
+      setup.max_obj_count,
+      [(setup.texture1_id, setup.texture1_dimension), (setup.texture2_id, setup.texture2_dimension)],
+      &(setup.sampler, setup.texture2_sampler),
+      setup.vertex_type,
+      setup.max_joints_count
+    );
+    let mut bind_group1: Option<RBindGroup> = None;
+    let uniform_sizes: Vec<u32> = setup.uniforms.iter().map(|u| u.size_in_bytes).collect();
+    if !setup.uniforms.is_empty() {
+      bind_group1 = Some(self.add_bind_group1(&bind_group_layouts[1], setup.max_obj_count, setup.uniforms));
+    }
+    // per instance attributes follow the vertex attributes, tightly packed
+    let first_location: u32 = if setup.vertex_type == RPipelineSetup::VERTEX_TYPE_ANIM { 5 } else { 3 };
+    let mut instance_stride: u64 = 0;
+    let mut instance_attributes: Vec<VertexAttribute> = Vec::new();
+    for (i, format) in setup.instance_attributes.iter().enumerate() {
+      instance_attributes.push(VertexAttribute { format: *format, offset: instance_stride, shader_location: first_location + i as u32 });
+      instance_stride += format.size();
+    }
+    // depth + stencil state, format is filled in per variant
+    let depth_stencil = DepthStencilState {
+      format: self.depth_format,
+      depth_write_enabled: setup.depth_write,
+      depth_compare: if setup.depth_test { setup.depth_compare } else { CompareFunction::Always },
+      stencil: setup.stencil.clone(),
+      bias: setup.depth_bias,
+    };
+    // add to cache
+    let pipe = RPipeline {
+      shader_mod,
+      layout: pipeline_layout,
+      bind_group_layouts,
+      variants: Vec::new(),
+      vertex_fn: setup.vertex_fn.to_owned(),
+      fragment_fn: setup.fragment_fn.to_owned(),
+      cull_mode,
+      polygon_mode,
+      topology,
+      alpha_to_coverage: setup.alpha_to_coverage,
+      blend: blend_state(setup.blend_mode, setup.custom_blend),
+      depth_stencil,
+      stencil_reference: setup.stencil_reference,
+      transparent: setup.transparent,
+      oit_fragment_fn: setup.oit_fragment_fn.map(|f| f.to_owned()),
+      oit_variants: Vec::new(),
+      use_materials: setup.use_materials,
+      shading: setup.shading,
+      frustum_cull: setup.frustum_cull,
+      objects: Vec::new(),
+      max_obj_count: setup.max_obj_count,
+      view_slots: 1,
+      vertex_type: setup.vertex_type,
+      max_joints_count: setup.max_joints_count,
+      instance_attributes,
+      instance_stride,
+      texture1_id: setup.texture1_id,
+      texture2_id: setup.texture2_id,
+      texture_dims: [setup.texture1_dimension, setup.texture2_dimension],
+      samplers: (setup.sampler, setup.texture2_sampler),
+      bind_group0,
+      bind_group1,
+      uniform_sizes,
+      resource_groups,
+      shader_name: name.to_owned(),
+      shader_source: setup.shader.to_owned(),
+      defines,
+      include_dir,
+    };
+    self.pipelines.push(pipe);
+    // surface variant is built up front, others when first rendered to
+    self.prepare_pipeline_variant(RPipelineId(id), self.surface_format, self.sample_count);
+    RPipelineId(id)
+  }
+
+  // pipeline whose shader is read from file and reloaded by poll_shader_changes,
+  // falls back to the setup shader if the file cannot be read
+  // note: includes resolve relative to the shader file unless include_dir is set
+  pub fn add_pipeline_from_file(&mut self, setup: RPipelineSetup, path: &Path) -> RPipelineId {
+    let source = fs::read_to_string(path);
+    let include_dir = setup.include_dir.or(path.parent());
+    let setup = match &source {
+      Ok(str) => RPipelineSetup { shader: str, include_dir, ..setup },
+      Err(e) => {
+        eprintln!("Err: Could not read shader {:?} - {}", path, e);
+        setup
+      }
+    };
+    let pipeline_id = self.add_named_pipeline(setup, &path.to_string_lossy());
+    self.watch_shader(pipeline_id, path);
+    pipeline_id
+  }
+
+  // watches the shader file and every file it includes
+  pub fn watch_shader(&mut self, pipeline_id: RPipelineId, path: &Path) {
+    let files = self.shader_files(pipeline_id, path);
+    self.shader_watches.push(RShaderWatch { pipeline_id, path: path.to_path_buf(), files });
+  }
+
+  // check watched shader files for changes, returns the pipelines that were reloaded
+  pub fn poll_shader_changes(&mut self) -> Vec<RPipelineId> {
+    let mut reloaded: Vec<RPipelineId> = Vec::new();
+    for i in 0..self.shader_watches.len() {
+      let watch = &self.shader_watches[i];
+      let changed = watch.files.iter().any(|(file, modified)| {
+        let m = fs::metadata(file).and_then(|m| m.modified()).ok();
+        m.is_some() && m != *modified
+      });
+      if !changed { continue; }
+      let (pipeline_id, path) = (watch.pipeline_id, watch.path.clone());
+      // refresh the file list, includes may have been added or removed
+      self.shader_watches[i].files = self.shader_files(pipeline_id, &path);
+      let result = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| self.reload_shader(pipeline_id, &source));
+      match result {
+        Ok(()) => reloaded.push(pipeline_id),
+        Err(e) => eprintln!("Err: Could not reload shader {:?}, keeping previous version\n{}", path, e),
+      }
+    }
+    reloaded
+  }
+
+  fn shader_files(&self, pipeline_id: RPipelineId, path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let pipe = &self.pipelines[pipeline_id.0];
+    let mut files = vec![path.to_path_buf()];
+    if let Ok(source) = fs::read_to_string(path) {
+      if let Ok(source) = preprocess_shader(&source, &pipe.shader_name, &pipe.defines, pipe.include_dir.as_deref()) {
+        files.extend(source.files);
+      }
+    }
+    files.into_iter().map(|f| {
+      let modified = fs::metadata(&f).and_then(|m| m.modified()).ok();
+      (f, modified)
+    }).collect()
+  }
+
+  // swap shader module + render pipelines in place, the bind group layouts stay the same
+  // the source is preprocessed with the defines + include dir the pipeline was created with
+  pub fn reload_shader(&mut self, pipeline_id: RPipelineId, source: &str) -> Result<(), String> {
+    let raw_source = source;
+    let pipe = &self.pipelines[pipeline_id.0];
+    let source = preprocess_shader(source, &pipe.shader_name, &pipe.defines, pipe.include_dir.as_deref())?;
+    let reflection = reflect_shader(&source.code).map_err(|e| source.map_error(&e))?;
+    let fragment_fns = std::iter::once(&pipe.fragment_fn).chain(pipe.oit_fragment_fn.as_ref());
+    if !reflection.vertex_entries.contains(&pipe.vertex_fn) {
+      return Err(format!("Vertex entry point {} not found", pipe.vertex_fn));
+    }
+    for f in fragment_fns {
+      if !reflection.fragment_entries.contains(f) {
+        return Err(format!("Fragment entry point {} not found", f));
+      }
+    }
+
+    // capture validation errors instead of panicking, so the old pipeline can be restored
+    self.device.push_error_scope(ErrorFilter::Validation);
+    let shader_mod = self.device.create_shader_module(ShaderModuleDescriptor {
+      label: Some("shader-module"),
+      source: ShaderSource::Wgsl(source.code.into()),
+    });
+    let pipe = &mut self.pipelines[pipeline_id.0];
+    let old_mod = std::mem::replace(&mut pipe.shader_mod, shader_mod);
+    let old_variants = std::mem::take(&mut pipe.variants);
+    let old_oit_variants = std::mem::take(&mut pipe.oit_variants);
+    for (format, samples, _) in &old_variants {
+      self.prepare_pipeline_variant(pipeline_id, *format, *samples);
+    }
+    for (samples, _) in &old_oit_variants {
+      self.prepare_oit_variant(pipeline_id, *samples);
+    }
+    if let Some(e) = pollster::block_on(self.device.pop_error_scope()) {
+      let pipe = &mut self.pipelines[pipeline_id.0];
+      pipe.shader_mod = old_mod;
+      pipe.variants = old_variants;
+      pipe.oit_variants = old_oit_variants;
+      return Err(e.to_string());
+    }
+    self.pipelines[pipeline_id.0].shader_source = raw_source.to_owned();
+    Ok(())
+  }
+
+  pub fn add_compute_pipeline(&mut self, setup: RComputeSetup) -> RComputeId {
+    let defines: Vec<String> = setup.defines.iter().map(|d| d.to_string()).collect();
+    let source = match preprocess_shader(setup.shader, "compute", &defines, setup.include_dir) {
+      Ok(source) => source,
+      Err(e) => {
+        eprintln!("Err: Could not preprocess shader\n{}", e);
+        RShaderSource { code: setup.shader.to_owned(), ..Default::default() }
+      }
+    };
+    match reflect_shader(&source.code) {
+      Ok(reflection) if !reflection.compute_entries.iter().any(|e| e == setup.entry_fn) => {
+        eprintln!("Err: Compute entry point {} not found", setup.entry_fn);
+      }
+      Ok(_) => (),
+      Err(e) => eprintln!("Err: Could not parse shader\n{}", source.map_error(&e)),
+    }
+    let shader_mod = self.device.create_shader_module(ShaderModuleDescriptor {
+      label: Some("compute-shader-module"),
+      source: ShaderSource::Wgsl(source.code.into()),
+    });
+    // unused groups in between are filled with empty groups
+    let resource_groups = self.add_resource_groups(&setup.bindings, [false; 4], true);
+    let layout_refs: Vec<&BindGroupLayout> = resource_groups.iter().map(|g| &g.layout).collect();
+    let pipeline_layout = self.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+      label: Some("compute-pipeline-layout"),
+      bind_group_layouts: &layout_refs,
       push_constant_ranges: &[]
     });
+    let pipeline = self.device.create_compute_pipeline(&ComputePipelineDescriptor {
+      label: Some("compute-pipeline"),
+      layout: Some(&pipeline_layout),
+      module: &shader_mod,
+      entry_point: setup.entry_fn,
+      compilation_options: PipelineCompilationOptions::default(),
+    });
+    self.compute_pipelines.push(RComputePipeline { shader_mod, pipeline, resource_groups });
+    RComputeId(self.compute_pipelines.len() - 1)
+  }
+
+  // runs the compute shader immediately, results are visible to everything submitted afterwards
+  pub fn dispatch(&mut self, compute_id: RComputeId, workgroups: [u32; 3]) {
+    let max = self.limits.max_compute_workgroups_per_dimension;
+    if workgroups.iter().any(|w| *w > max) {
+      eprintln!("Err: Workgroup count {:?} exceeds device limit of {}", workgroups, max);
+      return;
+    }
+    let compute = &self.compute_pipelines[compute_id.0];
+    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+      label: Some("compute-encoder"),
+    });
+    {
+      let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+        label: Some("compute-pass"),
+        timestamp_writes: None,
+      });
+      pass.set_pipeline(&compute.pipeline);
+      for group in &compute.resource_groups {
+        pass.set_bind_group(group.index, &group.bind_group, &[]);
+      }
+      pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
+    }
+    self.queue.submit(std::iter::once(encoder.finish()));
+  }
+
+  // write into uniform or storage binding of a compute pipeline, starting at byte offset
+  pub fn update_compute_buffer(&mut self, compute_id: RComputeId, bind_group: u32, bind_slot: u32, offset: u64, data: &[u8]) {
+    self.write_resource_buffer(&self.compute_pipelines[compute_id.0].resource_groups, bind_group, bind_slot, offset, data);
+  }
+
+  pub fn set_compute_texture(&mut self, compute_id: RComputeId, bind_group: u32, bind_slot: u32, texture_id: RTextureId) {
+    let Some((gi, bi)) = find_resource_binding(&self.compute_pipelines[compute_id.0].resource_groups, bind_group, bind_slot) else { return };
+    let binding = &self.compute_pipelines[compute_id.0].resource_groups[gi].bindings[bi];
+    if !self.binding_accepts_texture(binding, texture_id) { return; }
+    self.compute_pipelines[compute_id.0].resource_groups[gi].bindings[bi].texture_id = Some(texture_id);
+    self.rebuild_compute_group(compute_id, gi);
+  }
+
+  // handle to a storage binding, for use as object vertex_buffer or instance_buffer
+  pub fn compute_buffer(&self, compute_id: RComputeId, bind_group: u32, bind_slot: u32) -> Option<RComputeBuffer> {
+    let buffer = RComputeBuffer { compute_id, bind_group, bind_slot };
+    self.shared_buffer(&buffer).map(|_| buffer)
+  }
+
+  fn shared_buffer(&self, buffer: &RComputeBuffer) -> Option<&Buffer> {
+    let groups = &self.compute_pipelines.get(buffer.compute_id.0)?.resource_groups;
+    let (gi, bi) = find_resource_binding(groups, buffer.bind_group, buffer.bind_slot)?;
+    let binding = &groups[gi].bindings[bi];
+    if !matches!(binding.binding_type, RBindingSetup::BINDING_TYPE_STORAGE | RBindingSetup::BINDING_TYPE_STORAGE_RW) {
+      eprintln!("Err: Binding {} in bind group {} is not a storage buffer", buffer.bind_slot, buffer.bind_group);
+      return None;
+    }
+    groups[gi].buffers[bi].as_ref()
+  }
+
+  // copy a compute buffer back to the cpu, blocks until the gpu is done
+  pub fn read_compute_buffer(&self, buffer: RComputeBuffer) -> Vec<u8> {
+    let Some(source) = self.shared_buffer(&buffer) else { return Vec::new() };
+    let staging = self.device.create_buffer(&BufferDescriptor {
+      label: Some("compute-readback-buffer"),
+      size: source.size(),
+      usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+      label: Some("readback-encoder"),
+    });
+    encoder.copy_buffer_to_buffer(source, 0, &staging, 0, source.size());
+    self.queue.submit(std::iter::once(encoder.finish()));
+    let slice = staging.slice(..);
+    slice.map_async(MapMode::Read, |_| ());
+    self.device.poll(Maintain::Wait);
+    let data = slice.get_mapped_range().to_vec();
+    staging.destroy();
+    data
+  }
+
+  // pipelines are compiled per color target format + sample count, matching the texture rendered into
+  fn prepare_pipeline_variant(&mut self, pipeline_id: RPipelineId, color_format: TextureFormat, sample_count: u32) {
+    let pipe = &self.pipelines[pipeline_id.0];
+    if pipe.variants.iter().any(|(f, c, _)| *f == color_format && *c == sample_count) { return; }
+
+    let mut depth_stencil = self.variant_depth_stencil(&pipe.depth_stencil);
+    if !self.depth_format.has_stencil_aspect() && depth_stencil.stencil.is_enabled() {
+      eprintln!("Err: Depth format {:?} has no stencil aspect, ignoring stencil state", self.depth_format);
+      depth_stencil.stencil = StencilState::default();
+    }
+    let blend = variant_blend(pipe.blend, self.format_flags(color_format));
+    if blend != pipe.blend {
+      eprintln!("Err: Color format {:?} is not blendable, drawing without blending", color_format);
+    }
+    let targets = [Some(ColorTargetState {
+      format: color_format,
+      blend,
+      write_mask: ColorWrites::ALL
+    })];
+    // note: alpha to coverage requires multisampling
+    let alpha_to_coverage = pipe.alpha_to_coverage && sample_count > 1;
+    let pipeline = self.create_render_pipeline(pipe, &pipe.fragment_fn, &targets, depth_stencil, sample_count, alpha_to_coverage);
+    self.pipelines[pipeline_id.0].variants.push((color_format, sample_count, pipeline));
+  }
+
+  // accumulation variant for weighted blended transparency
+  fn prepare_oit_variant(&mut self, pipeline_id: RPipelineId, sample_count: u32) {
+    let pipe = &self.pipelines[pipeline_id.0];
+    if pipe.oit_variants.iter().any(|(c, _)| *c == sample_count) { return; }
+    let fragment_fn = match &pipe.oit_fragment_fn {
+      Some(f) => f,
+      None => return,
+    };
+
+    // depth is tested against opaque geometry, but never written
+    let mut depth_stencil = self.variant_depth_stencil(&pipe.depth_stencil);
+    depth_stencil.depth_write_enabled = false;
+    depth_stencil.stencil = StencilState::default();
+    let targets = [
+      Some(ColorTargetState {
+        format: OIT_ACCUM_FORMAT,
+        blend: Some(BlendState {
+          color: BlendComponent { operation: BlendOperation::Add, src_factor: BlendFactor::One, dst_factor: BlendFactor::One },
+          alpha: BlendComponent { operation: BlendOperation::Add, src_factor: BlendFactor::One, dst_factor: BlendFactor::One },
+        }),
+        write_mask: ColorWrites::ALL
+      }),
+      Some(ColorTargetState {
+        format: OIT_REVEAL_FORMAT,
+        blend: Some(BlendState {
+          color: BlendComponent { operation: BlendOperation::Add, src_factor: BlendFactor::Zero, dst_factor: BlendFactor::OneMinusSrc },
+          alpha: BlendComponent { operation: BlendOperation::Add, src_factor: BlendFactor::Zero, dst_factor: BlendFactor::OneMinusSrc },
+        }),
+        write_mask: ColorWrites::ALL
+      }),
+    ];
+    let pipeline = self.create_render_pipeline(pipe, fragment_fn, &targets, depth_stencil, sample_count, false);
+    self.pipelines[pipeline_id.0].oit_variants.push((sample_count, pipeline));
+  }
+
+  fn create_render_pipeline(
+    &self,
+    pipe: &RPipeline,
+    fragment_fn: &str,
+    targets: &[Option<ColorTargetState>],
+    mut depth_stencil: DepthStencilState,
+    sample_count: u32,
+    alpha_to_coverage: bool,
+  ) -> RenderPipeline {
     // switch between static/dynamic vertex layouts
     let vertex_attr_static = vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32x3];
     let vertex_attr_anim = vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32x3, 3 => Uint32x4, 4 => Float32x4];
-    let vertex_layout = match setup.vertex_type {
+    let vertex_layout = match pipe.vertex_type {
       RPipelineSetup::VERTEX_TYPE_ANIM => VertexBufferLayout {
         array_stride: std::mem::size_of::<RVertexAnim>() as BufferAddress,
         step_mode: VertexStepMode::Vertex,
@@ -530,82 +1820,153 @@ impl<'a> Renderer<'a> {
         attributes: &vertex_attr_static,
       }
     };
-    let pipeline = self.device.create_render_pipeline(&RenderPipelineDescriptor {
+    let mut buffers = vec![vertex_layout];
+    if !pipe.instance_attributes.is_empty() {
+      buffers.push(VertexBufferLayout {
+        array_stride: pipe.instance_stride,
+        step_mode: VertexStepMode::Instance,
+        attributes: &pipe.instance_attributes,
+      });
+    }
+    // note: depth bias is only valid for triangle topologies
+    if !matches!(pipe.topology, PrimitiveTopology::TriangleList | PrimitiveTopology::TriangleStrip) {
+      depth_stencil.bias = DepthBiasState::default();
+    }
+    self.device.create_render_pipeline(&RenderPipelineDescriptor {
       label: Some("render-pipeline"),
-      layout: Some(&pipeline_layout),
+      layout: Some(&pipe.layout),
       vertex: VertexState {
-        module: &shader_mod,
-        entry_point: setup.vertex_fn,
-        buffers: &[vertex_layout],
+        module: &pipe.shader_mod,
+        entry_point: &pipe.vertex_fn,
+        buffers: &buffers,
         compilation_options: PipelineCompilationOptions::default(),
       },
       fragment: Some(FragmentState{
-        module: &shader_mod,
-        entry_point: setup.fragment_fn,
-        targets: &[Some(ColorTargetState{
-          format: self.surface_format,
-          blend: Some(BlendState { 
-            color: BlendComponent {
-              operation: BlendOperation::Add,
-              src_factor: BlendFactor::SrcAlpha,
-              dst_factor: BlendFactor::OneMinusSrcAlpha
-            },
-            alpha: BlendComponent {
-              operation: BlendOperation::Add,
-              src_factor: BlendFactor::SrcAlpha,
-              dst_factor: BlendFactor::OneMinusSrcAlpha
-            }
-          }),
-          write_mask: ColorWrites::ALL
-        })],
+        module: &pipe.shader_mod,
+        entry_point: fragment_fn,
+        targets,
         compilation_options: PipelineCompilationOptions::default(),
       }),
       multisample: MultisampleState {
-        count: 4,
+        count: sample_count,
         mask: !0,
-        alpha_to_coverage_enabled: true,
+        alpha_to_coverage_enabled: alpha_to_coverage,
       },
-      depth_stencil: Some(DepthStencilState {
-        format: TextureFormat::Depth24Plus,
-        depth_write_enabled: true,
-        depth_compare: CompareFunction::LessEqual,
-        stencil: StencilState::default(),
-        bias: DepthBiasState::default(),
-      }),
+      depth_stencil: Some(depth_stencil),
       primitive: PrimitiveState {
-        cull_mode,
-        polygon_mode,
-        topology,
+        cull_mode: pipe.cull_mode,
+        polygon_mode: pipe.polygon_mode,
+        topology: pipe.topology,
         ..PrimitiveState::default()
       },
       multiview: None,
-    });
+    })
+  }
 
-    // build bind groups
-    let bind_group0: RBindGroup = self.add_bind_group0(&pipeline, setup.max_obj_count, setup.texture1_id, setup.texture2_id, setup.vertex_type, setup.max_joints_count);
-    let mut bind_group1: Option<RBindGroup> = None;
-    if setup.uniforms.len() > 0 {
-      bind_group1 = Some(self.add_bind_group1(&pipeline, setup.max_obj_count, setup.uniforms));
+  fn rebuild_pipeline_variants(&mut self) {
+    for pipe in &mut self.pipelines {
+      pipe.variants.clear();
+      pipe.oit_variants.clear();
+    }
+    for p in 0..self.pipelines.len() {
+      self.prepare_pipeline_variant(RPipelineId(p), self.surface_format, self.sample_count);
     }
-    // add to cache
-    let pipe = RPipeline {
-      pipe: pipeline,
-      objects: Vec::new(),
-      max_obj_count: setup.max_obj_count,
-      vertex_type: setup.vertex_type,
-      max_joints_count: setup.max_joints_count,
-      bind_group0,
-      bind_group1,
-    };
-    self.pipelines.push(pipe);
-    RPipelineId(id)
   }
 
-  fn add_bind_group0(
-    &self, pipeline: &RenderPipeline,
+  // depth state of a pipeline for the current depth format, flipped with reversed z
+  fn variant_depth_stencil(&self, depth_stencil: &DepthStencilState) -> DepthStencilState {
+    let mut depth_stencil = depth_stencil.clone();
+    depth_stencil.format = self.depth_format;
+    if self.reversed_z {
+      depth_stencil.depth_compare = reversed_compare(depth_stencil.depth_compare);
+      depth_stencil.bias.constant = -depth_stencil.bias.constant;
+      depth_stencil.bias.slope_scale = -depth_stencil.bias.slope_scale;
+    }
+    depth_stencil
+  }
+
+  // reversed z maps the far plane to depth 0 and the near plane to 1, which with a float depth
+  // buffer keeps precision roughly even across large depth ranges and avoids z-fighting.
+  // projections, depth tests + clears are flipped for every pipeline, and shaders are recompiled
+  // with REVERSED_Z defined (ie for the depth weight of transparent objects)
+  // note: Depth24Plus formats are swapped for their float versions when supported by the device
+  pub fn set_reversed_z(&mut self, enabled: bool) {
+    if self.reversed_z == enabled { return; }
+    self.reversed_z = enabled;
+    if let Some(picker) = &mut self.picker {
+      picker.variants.clear();
+    }
+    for p in 0..self.pipelines.len() {
+      let pipe = &mut self.pipelines[p];
+      pipe.defines.retain(|d| d != "REVERSED_Z");
+      if enabled {
+        pipe.defines.insert(0, "REVERSED_Z".to_owned());
+      }
+      let source = pipe.shader_source.clone();
+      if let Err(e) = self.reload_shader(RPipelineId(p), &source) {
+        eprintln!("Err: Could not recompile shader {} for reversed z\n{}", self.pipelines[p].shader_name, e);
+      }
+    }
+    let float_format = match self.depth_format {
+      TextureFormat::Depth24Plus => TextureFormat::Depth32Float,
+      TextureFormat::Depth24PlusStencil8 => TextureFormat::Depth32FloatStencil8,
+      format => format,
+    };
+    if enabled && float_format != self.depth_format && self.device.features().contains(float_format.required_features()) {
+      self.set_depth_format(float_format);
+    } else {
+      self.rebuild_pipeline_variants();
+    }
+  }
+
+  pub fn reversed_z(&self) -> bool {
+    self.reversed_z
+  }
+
+  fn depth_clear(&self) -> f32 {
+    if self.reversed_z { 0.0 } else { 1.0 }
+  }
+
+  // note: depth attachments and pipelines are rebuilt to match the new format
+  pub fn set_depth_format(&mut self, format: TextureFormat) {
+    if !format.has_depth_aspect() {
+      eprintln!("Err: {:?} is not a depth format", format);
+      return;
+    }
+    if !self.device.features().contains(format.required_features()) {
+      eprintln!("Err: Depth format {:?} is not supported by device", format);
+      return;
+    }
+    self.depth_format = format;
+    // depth format may not support the requested sample count, it is kept for later formats
+    self.sample_count = self.supported_sample_count(self.surface_format);
+    self.clear_attachments();
+    self.rebuild_pipeline_variants();
+  }
+
+  fn fallback_texture(&self, dim: u8, depth: bool) -> Texture {
+    let (layers, dimension) = match dim {
+      RPipelineSetup::TEXTURE_DIM_CUBE => (6, TextureDimension::D2),
+      RPipelineSetup::TEXTURE_DIM_3D => (1, TextureDimension::D3),
+      _ => (1, TextureDimension::D2),
+    };
+    self.device.create_texture(&TextureDescriptor {
+      label: Some("input-texture"),
+      size: Extent3d { width: 10, height: 10, depth_or_array_layers: layers },
+      sample_count: 1,
+      mip_level_count: 1,
+      dimension,
+      format: if depth { TextureFormat::Depth32Float } else { TextureFormat::Rgba8Unorm },
+      usage: TextureUsages::TEXTURE_BINDING,
+      view_formats: &[]
+    })
+  }
+
+  fn add_bind_group0(
+    &self, layout: &BindGroupLayout,
     max_obj_count: usize,
-    texture1: Option<RTextureId>,
-    texture2: Option<RTextureId>,
+    textures: [(Option<RTextureId>, u8); 2],
+    samplers: &(RSamplerSetup, Option<RSamplerSetup>),
     vertex_type: u8,
     max_joints: u32,
   ) -> RBindGroup {
@@ -614,48 +1975,31 @@ impl<'a> Renderer<'a> {
     let mvp_buffer = self.device.create_buffer(&BufferDescriptor {
       label: Some("mvp-uniform-buffer"),
       size: min_stride as u64 * max_obj_count as u64,
-      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
       mapped_at_creation: false,
     });
-    // create texture
-    let texture1_view: TextureView;
-    let texture2_view: TextureView;
-    let texture_size = Extent3d {
-      width: 10,
-      height: 10,
-      depth_or_array_layers: 1,
+    // create texture views, falling back to a blank texture of matching dimension
+    let (sampler_setup, texture2_sampler_setup) = samplers;
+    let texture_view = |(texture, dim): (Option<RTextureId>, u8), sampler: &RSamplerSetup| {
+      let depth = sampler.compare.is_some();
+      let desc = TextureViewDescriptor {
+        dimension: Some(view_dimension(dim)),
+        array_layer_count: if dim == RPipelineSetup::TEXTURE_DIM_2D { Some(1) } else { None },
+        aspect: if depth { TextureAspect::DepthOnly } else { TextureAspect::All },
+        ..Default::default()
+      };
+      match texture {
+        Some(tx_id) => self.textures[tx_id.0].create_view(&desc),
+        None => self.fallback_texture(dim, depth).create_view(&desc),
+      }
     };
-    let ftexture = self.device.create_texture(&TextureDescriptor {
-      label: Some("input-texture"),
-      size: texture_size,
-      sample_count: 1,
-      mip_level_count: 1,
-      dimension: TextureDimension::D2,
-      format: TextureFormat::Rgba8Unorm,
-      usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
-      view_formats: &[]
-    });
-    if let Some(tx_id) = texture1 {
-      texture1_view = self.textures[tx_id.0].create_view(&TextureViewDescriptor::default());
-    } else {
-      texture1_view = ftexture.create_view(&TextureViewDescriptor::default());
-    }
-    if let Some(tx_id) = texture2 {
-      texture2_view = self.textures[tx_id.0].create_view(&TextureViewDescriptor::default());
-    } else {
-      texture2_view = ftexture.create_view(&TextureViewDescriptor::default());
-    }
-    // create sampler
-    let sampler = self.device.create_sampler(&SamplerDescriptor {
-      label: Some("texture-sampler"),
-      address_mode_u: AddressMode::ClampToEdge,
-      address_mode_v: AddressMode::ClampToEdge,
-      address_mode_w: AddressMode::ClampToEdge,
-      mag_filter: FilterMode::Linear,
-      min_filter: FilterMode::Nearest,
-      mipmap_filter: FilterMode::Nearest,
-      ..Default::default()
-    });
+    let texture1_view = texture_view(textures[0], sampler_setup);
+    let texture2_view = texture_view(textures[1], texture2_sampler_setup.as_ref().unwrap_or(sampler_setup));
+    // create samplers
+    let features = self.device.features();
+    let sampler = self.device.create_sampler(&sampler_descriptor("texture-sampler", sampler_setup, features));
+    let texture2_sampler = texture2_sampler_setup.as_ref()
+      .map(|t| self.device.create_sampler(&sampler_descriptor("texture2-sampler", t, features)));
     // create bind entries
     let mvp_size = NonZeroU64::new(192); // 4 bytes * 4 rows * 4 columns * 3 matrices
     let mut bind_entries: Vec<BindGroupEntry> = vec![
@@ -678,11 +2022,17 @@ impl<'a> Renderer<'a> {
         resource: BindingResource::TextureView(&texture2_view)
       },
     ];
+    if let Some(texture2_sampler) = &texture2_sampler {
+      bind_entries.push(BindGroupEntry {
+        binding: 5,
+        resource: BindingResource::Sampler(texture2_sampler)
+      });
+    }
     // create joints matrix buffer
     let joints_buffer = self.device.create_buffer(&BufferDescriptor {
       label: Some("joint-transforms-buffer"),
       size: (max_joints * 4 * 4 * 4).into(), // 4x4 matrix of f32 values
-      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
       mapped_at_creation: false
     });
     if vertex_type == RPipelineSetup::VERTEX_TYPE_ANIM {
@@ -697,7 +2047,7 @@ impl<'a> Renderer<'a> {
     // create bind group
     let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
       label: Some("bind-group-0"),
-      layout: &pipeline.get_bind_group_layout(0),
+      layout,
       entries: &bind_entries
     });
 
@@ -712,9 +2062,230 @@ impl<'a> Renderer<'a> {
     }
   }
 
+  // reserved_groups are indexed by bind group 0-3, compute bindings are visible to the compute stage only
+  fn add_resource_groups(&self, bindings: &[RBindingSetup], reserved_groups: [bool; 4], compute: bool) -> Vec<RResourceGroup> {
+    let mut valid: Vec<RBindingSetup> = Vec::new();
+    for b in bindings {
+      if b.bind_group > 3 || reserved_groups[b.bind_group as usize] {
+        eprintln!("Err: Bind group {} is not available for custom bindings", b.bind_group);
+        continue;
+      }
+      if valid.iter().any(|v| v.bind_group == b.bind_group && v.bind_slot == b.bind_slot) {
+        eprintln!("Err: Duplicate binding {} in bind group {}", b.bind_slot, b.bind_group);
+        continue;
+      }
+      if b.binding_type == RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE && b.texture_id.is_none() {
+        eprintln!("Err: Storage texture binding {} in bind group {} needs a texture", b.bind_slot, b.bind_group);
+        continue;
+      }
+      valid.push(*b);
+    }
+    let Some(last_group) = (0..=3u32)
+      .filter(|g| reserved_groups[*g as usize] || valid.iter().any(|b| b.bind_group == *g))
+      .max() else { return Vec::new() };
+    let mut groups: Vec<RResourceGroup> = Vec::new();
+    for index in 0..=last_group {
+      if reserved_groups[index as usize] { continue; }
+      let bindings: Vec<RBindingSetup> = valid.iter().filter(|b| b.bind_group == index).copied().collect();
+      let entries: Vec<BindGroupLayoutEntry> = bindings.iter().map(|b| BindGroupLayoutEntry {
+        binding: b.bind_slot,
+        visibility: if compute { ShaderStages::COMPUTE } else { binding_visibility(b) },
+        ty: self.binding_type(b),
+        count: None,
+      }).collect();
+      let layout = self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("resource-group-layout"),
+        entries: &entries
+      });
+      let buffers: Vec<Option<Buffer>> = bindings.iter().map(|b| self.create_binding_buffer(b)).collect();
+      let bind_group = self.create_resource_bind_group(&layout, &bindings, &buffers);
+      groups.push(RResourceGroup { index, bindings, buffers, layout, bind_group });
+    }
+    groups
+  }
+
+  fn binding_type(&self, binding: &RBindingSetup) -> BindingType {
+    match binding.binding_type {
+      RBindingSetup::BINDING_TYPE_SAMPLER => BindingType::Sampler(resource_sampler_type(&binding.sampler)),
+      RBindingSetup::BINDING_TYPE_UNIFORM => BindingType::Buffer {
+        ty: BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: None,
+      },
+      RBindingSetup::BINDING_TYPE_STORAGE | RBindingSetup::BINDING_TYPE_STORAGE_RW => BindingType::Buffer {
+        ty: BufferBindingType::Storage { read_only: binding.binding_type == RBindingSetup::BINDING_TYPE_STORAGE },
+        has_dynamic_offset: false,
+        min_binding_size: None,
+      },
+      RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE => BindingType::StorageTexture {
+        access: StorageTextureAccess::WriteOnly,
+        format: binding.texture_id.map(|t| self.textures[t.0].format()).unwrap_or(TextureFormat::Rgba8Unorm),
+        view_dimension: view_dimension(binding.texture_dimension),
+      },
+      _ => BindingType::Texture {
+        sample_type: self.binding_sample_type(binding.texture_id),
+        view_dimension: view_dimension(binding.texture_dimension),
+        multisampled: false,
+      },
+    }
+  }
+
+  // sample type follows the bound texture, so depth and unfilterable float textures can be read
+  fn binding_sample_type(&self, texture_id: Option<RTextureId>) -> TextureSampleType {
+    let default = TextureSampleType::Float { filterable: true };
+    match texture_id {
+      Some(tx_id) => {
+        let format = self.textures[tx_id.0].format();
+        let aspect = if format.is_depth_stencil_format() { Some(TextureAspect::DepthOnly) } else { None };
+        format.sample_type(aspect, Some(self.device.features())).unwrap_or(default)
+      }
+      None => default,
+    }
+  }
+
+  fn create_binding_buffer(&self, binding: &RBindingSetup) -> Option<Buffer> {
+    let usage = match binding.binding_type {
+      RBindingSetup::BINDING_TYPE_UNIFORM => {
+        if binding.size_in_bytes > self.limits.max_uniform_buffer_binding_size as u64 {
+          eprintln!("Err: Uniform binding of {} bytes exceeds device limit of {}, use a storage binding instead",
+            binding.size_in_bytes, self.limits.max_uniform_buffer_binding_size);
+        }
+        BufferUsages::UNIFORM | BufferUsages::COPY_DST
+      }
+      // storage buffers can be shared as vertex or instance buffers
+      RBindingSetup::BINDING_TYPE_STORAGE | RBindingSetup::BINDING_TYPE_STORAGE_RW => {
+        BufferUsages::STORAGE | BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::COPY_SRC
+      }
+      _ => return None,
+    };
+    // buffers are sized in multiples of 16 bytes
+    let size = u64::max(16, binding.size_in_bytes.div_ceil(16) * 16);
+    Some(self.device.create_buffer(&BufferDescriptor {
+      label: Some("resource-buffer"),
+      size: u64::min(size, self.limits.max_buffer_size),
+      usage,
+      mapped_at_creation: false,
+    }))
+  }
+
+  fn create_resource_bind_group(&self, layout: &BindGroupLayout, bindings: &[RBindingSetup], buffers: &[Option<Buffer>]) -> BindGroup {
+    let features = self.device.features();
+    let views: Vec<Option<TextureView>> = bindings.iter().map(|b| {
+      let storage = b.binding_type == RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE;
+      if b.binding_type != RBindingSetup::BINDING_TYPE_TEXTURE && !storage { return None; }
+      // storage textures write into the first mip level only
+      let desc = TextureViewDescriptor {
+        dimension: Some(view_dimension(b.texture_dimension)),
+        array_layer_count: if b.texture_dimension == RPipelineSetup::TEXTURE_DIM_2D { Some(1) } else { None },
+        mip_level_count: if storage { Some(1) } else { None },
+        ..Default::default()
+      };
+      Some(match b.texture_id {
+        Some(tx_id) => {
+          let texture = &self.textures[tx_id.0];
+          let aspect = if texture.format().is_depth_stencil_format() { TextureAspect::DepthOnly } else { TextureAspect::All };
+          texture.create_view(&TextureViewDescriptor { aspect, ..desc })
+        }
+        None => self.fallback_texture(b.texture_dimension, false).create_view(&desc),
+      })
+    }).collect();
+    let samplers: Vec<Option<Sampler>> = bindings.iter().map(|b| {
+      if b.binding_type != RBindingSetup::BINDING_TYPE_SAMPLER { return None; }
+      Some(self.device.create_sampler(&sampler_descriptor("resource-sampler", &b.sampler, features)))
+    }).collect();
+    let entries: Vec<BindGroupEntry> = bindings.iter().enumerate().map(|(i, b)| BindGroupEntry {
+      binding: b.bind_slot,
+      resource: match (&views[i], &samplers[i], &buffers[i]) {
+        (Some(view), _, _) => BindingResource::TextureView(view),
+        (_, Some(sampler), _) => BindingResource::Sampler(sampler),
+        (_, _, Some(buffer)) => buffer.as_entire_binding(),
+        _ => unreachable!(),
+      },
+    }).collect();
+    self.device.create_bind_group(&BindGroupDescriptor {
+      label: Some("resource-bind-group"),
+      layout,
+      entries: &entries
+    })
+  }
+
+  fn rebuild_resource_group(&mut self, pipeline_id: RPipelineId, group: usize) {
+    let g = &self.pipelines[pipeline_id.0].resource_groups[group];
+    let bind_group = self.create_resource_bind_group(&g.layout, &g.bindings, &g.buffers);
+    self.pipelines[pipeline_id.0].resource_groups[group].bind_group = bind_group;
+  }
+
+  fn rebuild_compute_group(&mut self, compute_id: RComputeId, group: usize) {
+    let g = &self.compute_pipelines[compute_id.0].resource_groups[group];
+    let bind_group = self.create_resource_bind_group(&g.layout, &g.bindings, &g.buffers);
+    self.compute_pipelines[compute_id.0].resource_groups[group].bind_group = bind_group;
+  }
+
+  fn find_binding(&self, pipeline_id: RPipelineId, bind_group: u32, bind_slot: u32) -> Option<(usize, usize)> {
+    find_resource_binding(&self.pipelines[pipeline_id.0].resource_groups, bind_group, bind_slot)
+  }
+
+  // write into uniform or storage binding, starting at byte offset
+  pub fn update_binding_buffer(&mut self, pipeline_id: RPipelineId, bind_group: u32, bind_slot: u32, offset: u64, data: &[u8]) {
+    self.write_resource_buffer(&self.pipelines[pipeline_id.0].resource_groups, bind_group, bind_slot, offset, data);
+  }
+
+  fn write_resource_buffer(&self, groups: &[RResourceGroup], bind_group: u32, bind_slot: u32, offset: u64, data: &[u8]) {
+    let Some((gi, bi)) = find_resource_binding(groups, bind_group, bind_slot) else { return };
+    match &groups[gi].buffers[bi] {
+      Some(buffer) if offset + data.len() as u64 <= buffer.size() => {
+        self.queue.write_buffer(buffer, offset, data);
+      }
+      Some(buffer) => eprintln!("Err: Buffer write of {} bytes at {} exceeds binding size {}", data.len(), offset, buffer.size()),
+      None => eprintln!("Err: Binding {} in bind group {} is not a buffer", bind_slot, bind_group),
+    }
+  }
+
+  pub fn set_binding_texture(&mut self, pipeline_id: RPipelineId, bind_group: u32, bind_slot: u32, texture_id: RTextureId) {
+    let Some((gi, bi)) = self.find_binding(pipeline_id, bind_group, bind_slot) else { return };
+    let binding = &self.pipelines[pipeline_id.0].resource_groups[gi].bindings[bi];
+    if !self.binding_accepts_texture(binding, texture_id) { return; }
+    self.pipelines[pipeline_id.0].resource_groups[gi].bindings[bi].texture_id = Some(texture_id);
+    self.rebuild_resource_group(pipeline_id, gi);
+  }
+
+  // layout was built for the sample type (or storage format) of the original texture
+  fn binding_accepts_texture(&self, binding: &RBindingSetup, texture_id: RTextureId) -> bool {
+    let matches = match binding.binding_type {
+      RBindingSetup::BINDING_TYPE_TEXTURE => {
+        self.binding_sample_type(binding.texture_id) == self.binding_sample_type(Some(texture_id))
+      }
+      RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE => {
+        let texture = &self.textures[texture_id.0];
+        binding.texture_id.map(|t| self.textures[t.0].format()) == Some(texture.format())
+          && texture.usage().contains(TextureUsages::STORAGE_BINDING)
+      }
+      _ => {
+        eprintln!("Err: Binding {} in bind group {} is not a texture", binding.bind_slot, binding.bind_group);
+        return false;
+      }
+    };
+    if !matches {
+      eprintln!("Err: Texture type does not match binding {} in bind group {}", binding.bind_slot, binding.bind_group);
+    }
+    matches
+  }
+
+  pub fn set_binding_sampler(&mut self, pipeline_id: RPipelineId, bind_group: u32, bind_slot: u32, setup: RSamplerSetup) {
+    let Some((gi, bi)) = self.find_binding(pipeline_id, bind_group, bind_slot) else { return };
+    let binding = &self.pipelines[pipeline_id.0].resource_groups[gi].bindings[bi];
+    if binding.binding_type != RBindingSetup::BINDING_TYPE_SAMPLER
+      || resource_sampler_type(&binding.sampler) != resource_sampler_type(&setup) {
+      eprintln!("Err: Sampler does not match binding {} in bind group {}", bind_slot, bind_group);
+      return;
+    }
+    self.pipelines[pipeline_id.0].resource_groups[gi].bindings[bi].sampler = setup;
+    self.rebuild_resource_group(pipeline_id, gi);
+  }
+
   fn add_bind_group1(
     &self,
-    pipeline: &RenderPipeline,
+    layout: &BindGroupLayout,
     max_obj_count: usize,
     uniforms: Vec<RUniformSetup>,
   ) -> RBindGroup {
@@ -734,7 +2305,7 @@ impl<'a> Renderer<'a> {
     }
     for (i, u) in uniforms.iter().enumerate() {
       let desc = BindGroupEntry {
-        binding: i as u32,
+        binding: u.bind_slot,
         resource: BindingResource::Buffer(BufferBinding {
           buffer: &bind_entries[i], offset: 0, size: NonZeroU64::new(u.size_in_bytes as u64)
         })
@@ -743,7 +2314,7 @@ impl<'a> Renderer<'a> {
     }
     let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
       label: Some("bind-group-1"),
-      layout: &pipeline.get_bind_group_layout(1),
+      layout,
       entries: &bind_desc
     });
 
@@ -753,290 +2324,1890 @@ impl<'a> Renderer<'a> {
     }
   }
 
-  pub fn add_overlay_pipeline(&mut self) -> (RTextureId, RPipelineId) {
-    // build full screen texture
-    let texture_id = self.add_texture(self.config.width, self.config.height, None, true);
-    // build render pipeline
-    let pipeline_id = self.add_pipeline(RPipelineSetup {
-      shader: include_str!("../embed_assets/text.wgsl"),
-      texture1_id: Some(texture_id),
+  // shared material layout + default material, created with the first material pipeline
+  fn prepare_materials(&mut self) {
+    if self.material_layout.is_some() { return; }
+    let mut entries = vec![
+      // params
+      BindGroupLayoutEntry {
+        binding: 0,
+        visibility: ShaderStages::VERTEX_FRAGMENT,
+        ty: BindingType::Buffer {
+          ty: BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      },
+      // sampler
+      BindGroupLayoutEntry {
+        binding: 1,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+        count: None,
+      },
+    ];
+    for i in 0..RMaterialSetup::TEXTURE_SLOTS {
+      entries.push(BindGroupLayoutEntry {
+        binding: 2 + i as u32,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Texture {
+          sample_type: TextureSampleType::Float { filterable: true },
+          view_dimension: TextureViewDimension::D2,
+          multisampled: false,
+        },
+        count: None,
+      });
+    }
+    self.material_layout = Some(self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("material-layout"),
+      entries: &entries
+    }));
+    // white texture for empty slots
+    let white = self.add_texture_with_format(1, 1, None, TextureFormat::Rgba8Unorm);
+    self.queue.write_texture(
+      ImageCopyTexture {
+        texture: &self.textures[white.0],
+        mip_level: 0,
+        origin: Origin3d::ZERO,
+        aspect: TextureAspect::All,
+      },
+      &[255, 255, 255, 255],
+      ImageDataLayout { offset: 0, bytes_per_row: Some(4), rows_per_image: None },
+      Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+    );
+    // pbr params start with the base color, so they also serve as the plain white tint
+    let params = RPbrParams::default();
+    self.default_material = Some(self.add_material(RMaterialSetup {
+      textures: vec![white; RMaterialSetup::TEXTURE_SLOTS],
+      params: params.as_bytes(),
       ..Default::default()
-    });
-    // build object
-    let (rect_data, rect_i) = Primitives::rect_indexed(2.0, 2.0, 0.0);
-    let _rect = Shape::new(self, pipeline_id, rect_data, Some(rect_i));
-    // output fields
-    (texture_id, pipeline_id)
+    }));
   }
 
-  pub fn add_object(&mut self, obj_data: RObjectSetup) -> RObjectId {
-    let pipe = &mut self.pipelines[obj_data.pipeline_id.0];
-    let id = pipe.objects.len();
-
-    // create vertex buffer
-    let vlen: usize;
-    let v_buffer: Buffer;
-    match obj_data.vertex_type {
-      RObjectSetup::VERTEX_TYPE_ANIM => {
-        vlen = obj_data.anim_vertex_data.len();
-        v_buffer = self.device.create_buffer(&BufferDescriptor {
-          label: Some("anim-vertex-buffer"),
-          size: (std::mem::size_of::<RVertexAnim>() * vlen) as u64,
-          usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-          mapped_at_creation: false
-        });
-        self.queue.write_buffer(&v_buffer, 0, bytemuck::cast_slice(&obj_data.anim_vertex_data));
-      }
-      _ => {
-        vlen = obj_data.vertex_data.len();
-        v_buffer = self.device.create_buffer(&BufferDescriptor {
-          label: Some("vertex-buffer"),
-          size: (std::mem::size_of::<RVertex>() * vlen) as u64,
-          usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-          mapped_at_creation: false
-        });
-        self.queue.write_buffer(&v_buffer, 0, bytemuck::cast_slice(&obj_data.vertex_data));
+  pub fn add_material(&mut self, setup: RMaterialSetup) -> RMaterialId {
+    self.prepare_materials();
+    if setup.textures.len() > RMaterialSetup::TEXTURE_SLOTS {
+      eprintln!("Err: Material has more than {} textures, ignoring the rest", RMaterialSetup::TEXTURE_SLOTS);
+    }
+    // fill empty slots with default textures
+    let mut textures: Vec<RTextureId> = setup.textures.iter().take(RMaterialSetup::TEXTURE_SLOTS).copied().collect();
+    // slots are bound as filterable 2d textures, anything else is swapped for the default texture
+    if let Some(default) = self.default_material {
+      let white = self.materials[default.0].textures[0];
+      for texture_id in textures.iter_mut() {
+        if !self.is_material_texture(*texture_id) {
+          eprintln!("Err: Material textures must be filterable 2d textures, using default texture");
+          *texture_id = white;
+        }
       }
+      textures.resize(RMaterialSetup::TEXTURE_SLOTS, white);
     }
+    // uniform buffers are sized in multiples of 16 bytes
+    let size = u64::max(16, (setup.params.len() as u64).div_ceil(16) * 16);
+    let params = self.device.create_buffer(&BufferDescriptor {
+      label: Some("material-params-buffer"),
+      size,
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    self.queue.write_buffer(&params, 0, setup.params);
+    let sampler = self.create_material_sampler(&setup.sampler);
+    let bind_group = self.create_material_bind_group(&textures, &sampler, &params);
+    self.materials.push(RMaterial { textures, sampler, params, bind_group });
+    RMaterialId(self.materials.len() - 1)
+  }
 
-    // create index buffer
-    let mut index_buffer: Option<Buffer> = None;
-    let ilen: usize = obj_data.indices.len();
-    if ilen > 0 {
-      let i_buffer = self.device.create_buffer(&BufferDescriptor {
-        label: Some("index-buffer"),
-        size: (std::mem::size_of::<u32>() * ilen) as u64,
-        usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
-        mapped_at_creation: false
+  fn is_material_texture(&self, texture_id: RTextureId) -> bool {
+    let texture = &self.textures[texture_id.0];
+    texture.dimension() == TextureDimension::D2
+      && texture.depth_or_array_layers() == 1
+      && texture.format().sample_type(None, Some(self.device.features())) == Some(TextureSampleType::Float { filterable: true })
+  }
+
+  fn create_material_bind_group(&self, textures: &[RTextureId], sampler: &Sampler, params: &Buffer) -> BindGroup {
+    let views: Vec<TextureView> = textures.iter()
+      .map(|t| self.textures[t.0].create_view(&TextureViewDescriptor::default()))
+      .collect();
+    let mut entries = vec![
+      BindGroupEntry {
+        binding: 0,
+        resource: params.as_entire_binding(),
+      },
+      BindGroupEntry {
+        binding: 1,
+        resource: BindingResource::Sampler(sampler),
+      },
+    ];
+    for (i, view) in views.iter().enumerate() {
+      entries.push(BindGroupEntry {
+        binding: 2 + i as u32,
+        resource: BindingResource::TextureView(view),
       });
-      self.queue.write_buffer(&i_buffer, 0, bytemuck::cast_slice(&obj_data.indices));
-      index_buffer = Some(i_buffer);
     }
-
-    // save to cache
-    let obj = RObject {
-      visible: true,
-      v_buffer,
-      v_count: vlen,
-      pipe_index: id,
-      index_buffer,
-      index_count: ilen as u32,
-      instances: 1,
-    };
-    pipe.objects.push(obj);
-    let object_id = RObjectId(obj_data.pipeline_id.0, id);
-    self.update_object(RObjectUpdate{ object_id, ..Default::default()});
-    object_id
+    self.device.create_bind_group(&BindGroupDescriptor {
+      label: Some("material-bind-group"),
+      layout: self.material_layout.as_ref().unwrap(),
+      entries: &entries,
+    })
   }
 
-  pub fn update_object(&mut self, update: RObjectUpdate) {
-    let pipe = &mut self.pipelines[update.object_id.0];
-    let obj = &mut pipe.objects[update.object_id.1];
-    let cam = match update.camera {
-      Some(c) => c,
-      None => &self.default_cam
-    };
-
-    obj.visible = update.visible;
-    // model matrix
-    let model_t = Mat4::translate(update.translate[0], update.translate[1], update.translate[2]);
-    let model_r = Mat4::rotate(&update.rotate_axis, update.rotate_deg);
-    let model_s = Mat4::scale(update.scale[0], update.scale[1], update.scale[2]);
-    let model = Mat4::multiply(&model_t, &Mat4::multiply(&model_s, &model_r));
-    // view matrix
-    let view_t = Mat4::translate(-cam.position[0], -cam.position[1], -cam.position[2]);
-    let view_r = Mat4::view_rot(&cam.position, &cam.look_at, &cam.up);
-    let view = Mat4::multiply(&view_r, &view_t);
-    // projection matrix
-    let w2 = (self.config.width / 2) as f32;
-    let h2 = (self.config.height / 2) as f32;
-    let proj = match cam.cam_type {
-      1 => Mat4::ortho(-w2, w2, h2, -h2, cam.near, cam.far),
-      2 => Mat4::perspective(cam.fov_y, w2/h2, cam.near, cam.far),
-      _ => Mat4::identity()
-    };
-    // merge together
-    let mut mvp: [f32; 48] = [0.0; 48]; // 16 * 3 = 48
-    for i in 0..48 {
-      if i < 16 { mvp[i] = model[i]; }
-      else if i < 32 { mvp[i] = view[i - 16]; }
-      else { mvp[i] = proj[i - 32]; }
+  // material layout uses a filtering sampler, comparison is not available
+  fn create_material_sampler(&self, setup: &RSamplerSetup) -> Sampler {
+    let mut setup = *setup;
+    if setup.compare.take().is_some() {
+      eprintln!("Err: Materials cannot use comparison samplers, ignoring compare function");
     }
-    let stride = self.limits.min_uniform_buffer_offset_alignment;
-    self.queue.write_buffer(
-      &pipe.bind_group0.entries[0], 
-      (stride * obj.pipe_index as u32) as u64, 
-      bytemuck::cast_slice(&mvp)
-    );
-    // merge animation matrices into single buffer
-    if pipe.max_joints_count > 0 && update.anim_transforms.len() > 0 {
-      let mut anim_buffer: Vec<f32> = Vec::new();
-      for i in 0..pipe.max_joints_count {
-        if i >= update.anim_transforms.len() as u32 {
-          break;
-        }
-        // merge [f32; 16] arrays into single anim_buffer
-        let a = update.anim_transforms[i as usize];
-        anim_buffer.extend_from_slice(&a);
-      }
-      self.queue.write_buffer(&pipe.bind_group0.entries[1], 0, bytemuck::cast_slice(&anim_buffer));
+    self.device.create_sampler(&sampler_descriptor("material-sampler", &setup, self.device.features()))
+  }
+  pub fn set_material_sampler(&mut self, material_id: RMaterialId, setup: RSamplerSetup) {
+    self.materials[material_id.0].sampler = self.create_material_sampler(&setup);
+    self.rebuild_material(material_id);
+  }
+  fn rebuild_material(&mut self, material_id: RMaterialId) {
+    let material = &self.materials[material_id.0];
+    let bind_group = self.create_material_bind_group(&material.textures, &material.sampler, &material.params);
+    self.materials[material_id.0].bind_group = bind_group;
+  }
+
+  pub fn update_material(&mut self, material_id: RMaterialId, params: &[u8]) {
+    let buffer = &self.materials[material_id.0].params;
+    if params.len() as u64 > buffer.size() {
+      eprintln!("Err: Material params exceed {} bytes", buffer.size());
+      return;
     }
-    // update custom uniforms
-    if update.uniforms.len() > 0 {
-      if let Some(bind_group1) = &pipe.bind_group1 {
-        for (i, uniform) in update.uniforms.iter().enumerate() {
-          self.queue.write_buffer(
-            &bind_group1.entries[i],
-            (stride * obj.pipe_index as u32) as u64,
-            *uniform
-          );
-        }
-      }
+    self.queue.write_buffer(buffer, 0, params);
+  }
+
+  pub fn set_material_texture(&mut self, material_id: RMaterialId, slot: usize, texture_id: RTextureId) {
+    if slot >= RMaterialSetup::TEXTURE_SLOTS {
+      eprintln!("Err: Material texture slot {} out of range", slot);
+      return;
     }
+    self.materials[material_id.0].textures[slot] = texture_id;
+    self.rebuild_material(material_id);
   }
 
-  pub fn render_texture(&mut self, pipeline_ids: &[RPipelineId], target_id: RTextureId, clear_color: Option<[f64;4]>) {
-    let mut clear_clr = self.clear_color;
-    if let Some(c) = clear_color {
-      clear_clr = Color { r:c[0], g:c[1], b:c[2], a:c[3] };
-    }
-    let view = self.msaa.create_view(&TextureViewDescriptor::default());
-    let tx = &self.textures[target_id.0];
-    let target = tx.create_view(&TextureViewDescriptor::default());
-    let zbuffer_view = self.zbuffer.create_view(&TextureViewDescriptor::default());
-    let mut encoder = self.device.create_command_encoder(
-      &wgpu::CommandEncoderDescriptor { label: Some("render-texture-encoder") }
-    );
-    {
-      // new context so ownership of encoder is released after pass finishes
-      let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
-        label: Some("render-pass"),
-        color_attachments: &[Some(RenderPassColorAttachment {
-          view: &view,
-          resolve_target: Some(&target),
-          ops: Operations {
-            load: LoadOp::Clear(clear_clr),
-            store: StoreOp::Store,
+  pub fn set_object_material(&mut self, object_id: RObjectId, material_id: Option<RMaterialId>) {
+    self.pipelines[object_id.0].objects[object_id.1].material = material_id;
+  }
+
+  // brdf lut + default environment for pbr pipelines
+  fn prepare_ibl(&mut self) {
+    let layout = self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("ibl-layout"),
+      entries: &[
+        BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Sampler(SamplerBindingType::Filtering),
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 1,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::Cube,
+            multisampled: false,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 2,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::Cube,
+            multisampled: false,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 3,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 4,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+          },
+          count: None,
+        },
+      ],
+    });
+    let sampler = self.device.create_sampler(&SamplerDescriptor {
+      label: Some("ibl-sampler"),
+      address_mode_u: AddressMode::ClampToEdge,
+      address_mode_v: AddressMode::ClampToEdge,
+      address_mode_w: AddressMode::ClampToEdge,
+      mag_filter: FilterMode::Linear,
+      min_filter: FilterMode::Linear,
+      mipmap_filter: FilterMode::Linear,
+      ..Default::default()
+    });
+    let lighting = self.device.create_buffer(&BufferDescriptor {
+      label: Some("ibl-lighting-buffer"),
+      size: 48,
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let brdf_lut = self.create_ibl_texture("brdf-lut", IBL_BRDF_LUT_SIZE, 1, 1, TextureFormat::Rg16Float);
+    let irradiance = self.create_ibl_texture("irradiance-map", IBL_IRRADIANCE_SIZE, 6, 1, HDR_FORMAT);
+    let prefiltered = self.create_ibl_texture("prefiltered-map", IBL_PREFILTERED_SIZE, 6, IBL_PREFILTERED_MIPS, HDR_FORMAT);
+    let bind_group = Renderer::create_ibl_bind_group(&self.device, &layout, &sampler, &irradiance, &prefiltered, &brdf_lut, &lighting);
+    self.ibl = Some(RIbl { layout, bind_group, sampler, irradiance, prefiltered, brdf_lut, lighting });
+    self.set_pbr_lighting(&RPbrLighting::default());
+
+    // render brdf lut + procedural sky, then filter it as default environment
+    let sky = self.create_ibl_texture("default-environment", 64, 6, 1, HDR_FORMAT);
+    let mut passes = self.create_ibl_passes();
+    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("ibl-encoder") });
+    let ibl = self.ibl.as_ref().unwrap();
+    self.run_ibl_pass(&mut encoder, &mut passes, "fragmentBrdfLut", &ibl.brdf_lut, 0, 0, &sky, [0.0; 4]);
+    for face in 0..6 {
+      self.run_ibl_pass(&mut encoder, &mut passes, "fragmentSky", &sky, face, 0, &ibl.irradiance, [face as f32, 0.0, 0.0, 0.0]);
+    }
+    self.queue.submit(std::iter::once(encoder.finish()));
+    self.filter_environment(&mut passes, &sky);
+    sky.destroy();
+  }
+
+  fn create_ibl_passes(&self) -> RIblPasses {
+    let shader_mod = self.device.create_shader_module(ShaderModuleDescriptor {
+      label: Some("ibl-shader-module"),
+      source: ShaderSource::Wgsl(include_str!("../embed_assets/ibl.wgsl").into()),
+    });
+    let layout = self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("ibl-pass-layout"),
+      entries: &[
+        BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 1,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Sampler(SamplerBindingType::Filtering),
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 2,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::Cube,
+            multisampled: false,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 3,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+      ],
+    });
+    let pipeline_layout = self.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+      label: Some("ibl-pipeline-layout"),
+      bind_group_layouts: &[&layout],
+      push_constant_ranges: &[]
+    });
+    let source = self.create_ibl_texture("ibl-source", 1, 1, 1, HDR_FORMAT);
+    RIblPasses { shader_mod, layout, pipeline_layout, pipelines: Vec::new(), source }
+  }
+
+  fn create_ibl_texture(&self, label: &str, size: u32, layers: u32, mips: u32, format: TextureFormat) -> Texture {
+    self.device.create_texture(&TextureDescriptor {
+      label: Some(label),
+      size: Extent3d { width: size, height: size, depth_or_array_layers: layers },
+      sample_count: 1,
+      mip_level_count: mips,
+      dimension: TextureDimension::D2,
+      format,
+      usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+      view_formats: &[]
+    })
+  }
+
+  fn create_ibl_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    sampler: &Sampler,
+    irradiance: &Texture,
+    prefiltered: &Texture,
+    brdf_lut: &Texture,
+    lighting: &Buffer,
+  ) -> BindGroup {
+    let cube_view = TextureViewDescriptor {
+      dimension: Some(TextureViewDimension::Cube),
+      ..Default::default()
+    };
+    device.create_bind_group(&BindGroupDescriptor {
+      label: Some("ibl-bind-group"),
+      layout,
+      entries: &[
+        BindGroupEntry { binding: 0, resource: BindingResource::Sampler(sampler) },
+        BindGroupEntry { binding: 1, resource: BindingResource::TextureView(&irradiance.create_view(&cube_view)) },
+        BindGroupEntry { binding: 2, resource: BindingResource::TextureView(&prefiltered.create_view(&cube_view)) },
+        BindGroupEntry { binding: 3, resource: BindingResource::TextureView(&brdf_lut.create_view(&TextureViewDescriptor::default())) },
+        BindGroupEntry { binding: 4, resource: lighting.as_entire_binding() },
+      ],
+    })
+  }
+
+  // render a single face + mip level of an ibl texture with a fragment fn from embed_assets/ibl.wgsl
+  #[allow(clippy::too_many_arguments)]
+  fn run_ibl_pass(
+    &self,
+    encoder: &mut CommandEncoder,
+    passes: &mut RIblPasses,
+    fragment_fn: &str,
+    target: &Texture,
+    layer: u32,
+    mip: u32,
+    env: &Texture,
+    params: [f32; 4],
+  ) {
+    let format = target.format();
+    if !passes.pipelines.iter().any(|(f, fmt, _)| f == fragment_fn && *fmt == format) {
+      let pipeline = self.device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("ibl-pipeline"),
+        layout: Some(&passes.pipeline_layout),
+        vertex: VertexState {
+          module: &passes.shader_mod,
+          entry_point: "vertexMain",
+          buffers: &[],
+          compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(FragmentState {
+          module: &passes.shader_mod,
+          entry_point: fragment_fn,
+          targets: &[Some(ColorTargetState {
+            format,
+            blend: None,
+            write_mask: ColorWrites::ALL
+          })],
+          compilation_options: PipelineCompilationOptions::default(),
+        }),
+        multisample: MultisampleState::default(),
+        depth_stencil: None,
+        primitive: PrimitiveState::default(),
+        multiview: None,
+      });
+      passes.pipelines.push((fragment_fn.to_owned(), format, pipeline));
+    }
+    let (_, _, pipeline) = passes.pipelines.iter().find(|(f, fmt, _)| f == fragment_fn && *fmt == format).unwrap();
+    let params_buffer = self.device.create_buffer(&BufferDescriptor {
+      label: Some("ibl-params-buffer"),
+      size: 16,
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    self.queue.write_buffer(&params_buffer, 0, bytemuck::cast_slice(&params));
+    let env_view = env.create_view(&TextureViewDescriptor {
+      dimension: Some(TextureViewDimension::Cube),
+      ..Default::default()
+    });
+    let source_view = passes.source.create_view(&TextureViewDescriptor::default());
+    let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+      label: Some("ibl-pass-bind-group"),
+      layout: &passes.layout,
+      entries: &[
+        BindGroupEntry { binding: 0, resource: params_buffer.as_entire_binding() },
+        BindGroupEntry { binding: 1, resource: BindingResource::Sampler(&self.ibl.as_ref().unwrap().sampler) },
+        BindGroupEntry { binding: 2, resource: BindingResource::TextureView(&env_view) },
+        BindGroupEntry { binding: 3, resource: BindingResource::TextureView(&source_view) },
+      ],
+    });
+    let target_view = target.create_view(&TextureViewDescriptor {
+      dimension: Some(TextureViewDimension::D2),
+      base_array_layer: layer,
+      array_layer_count: Some(1),
+      base_mip_level: mip,
+      mip_level_count: Some(1),
+      ..Default::default()
+    });
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("ibl-pass"),
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view: &target_view,
+        resolve_target: None,
+        ops: Operations { load: LoadOp::Clear(Color::BLACK), store: StoreOp::Store },
+      })],
+      depth_stencil_attachment: None,
+      occlusion_query_set: None,
+      timestamp_writes: None,
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, &bind_group, &[]);
+    pass.draw(0..3, 0..1);
+  }
+
+  // convolve environment cubemap into irradiance + prefiltered specular maps
+  fn filter_environment(&self, passes: &mut RIblPasses, env: &Texture) {
+    let ibl = self.ibl.as_ref().unwrap();
+    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("ibl-encoder") });
+    for face in 0..6 {
+      self.run_ibl_pass(&mut encoder, passes, "fragmentIrradiance", &ibl.irradiance, face, 0, env, [face as f32, 0.0, 0.0, 0.0]);
+      for mip in 0..IBL_PREFILTERED_MIPS {
+        let roughness = mip as f32 / (IBL_PREFILTERED_MIPS - 1) as f32;
+        self.run_ibl_pass(&mut encoder, passes, "fragmentPrefilter", &ibl.prefiltered, face, mip, env, [face as f32, roughness, 0.0, 0.0]);
+      }
+    }
+    self.queue.submit(std::iter::once(encoder.finish()));
+  }
+
+  fn create_cubemap(&mut self, size: u32, tex_format: TextureFormat) -> RTextureId {
+    let texture = self.device.create_texture(&TextureDescriptor {
+      label: Some("cubemap-texture"),
+      size: Extent3d { width: size, height: size, depth_or_array_layers: 6 },
+      sample_count: 1,
+      mip_level_count: if self.supports_mipmaps(tex_format) { mip_level_count(size, size) } else { 1 },
+      dimension: TextureDimension::D2,
+      format: tex_format,
+      usage: Renderer::texture_usage(tex_format),
+      view_formats: &[]
+    });
+    self.textures.push(texture);
+    RTextureId(self.textures.len() - 1)
+  }
+
+  // faces in order +x, -x, +y, -y, +z, -z
+  pub fn add_cubemap(&mut self, face_paths: [&Path; 6], tex_format: TextureFormat) -> RTextureId {
+    let mut faces: Vec<DynamicImage> = Vec::with_capacity(6);
+    for path in face_paths {
+      match ImageReader::open(path).map(|r| r.decode()) {
+        Ok(Ok(img)) => faces.push(img),
+        _ => eprintln!("Err: Could not load cubemap face {:?}", path),
+      }
+    }
+    let size = faces.first().map(|f| f.width()).unwrap_or(1);
+    let texture_id = self.create_cubemap(size, tex_format);
+    for (layer, face) in faces.iter().enumerate() {
+      if face.width() != size || face.height() != size {
+        eprintln!("Err: Cubemap faces must be square and of equal size");
+        continue;
+      }
+      self.write_image_layer(&self.textures[texture_id.0], face, layer as u32);
+    }
+    self.generate_mipmaps(texture_id);
+    texture_id
+  }
+
+  // project equirectangular panorama (ie .hdr) onto a cubemap
+  pub fn add_cubemap_from_equirect(&mut self, path: &Path, face_size: u32) -> RTextureId {
+    let texture_id = self.create_cubemap(face_size, HDR_FORMAT);
+    let img = match ImageReader::open(path).map(|r| r.decode()) {
+      Ok(Ok(img)) => img,
+      _ => {
+        eprintln!("Err: Could not load panorama {:?}", path);
+        return texture_id;
+      }
+    };
+    let mut passes = self.create_ibl_passes();
+    passes.source.destroy();
+    passes.source = self.device.create_texture(&TextureDescriptor {
+      label: Some("equirect-source"),
+      size: Extent3d { width: img.width(), height: img.height(), depth_or_array_layers: 1 },
+      sample_count: 1,
+      mip_level_count: 1,
+      dimension: TextureDimension::D2,
+      format: HDR_FORMAT,
+      usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+      view_formats: &[]
+    });
+    self.write_image(&passes.source, &img);
+    let ibl = self.ibl.as_ref().unwrap();
+    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("equirect-encoder") });
+    for face in 0..6 {
+      self.run_ibl_pass(&mut encoder, &mut passes, "fragmentEquirect", &self.textures[texture_id.0], face, 0, &ibl.irradiance, [face as f32, 0.0, 0.0, 0.0]);
+    }
+    self.queue.submit(std::iter::once(encoder.finish()));
+    passes.source.destroy();
+    self.generate_mipmaps(texture_id);
+    texture_id
+  }
+
+  // note: returned object should be updated with the scene camera, only its rotation is used.
+  // passing None hides the skybox
+  pub fn set_skybox(&mut self, cubemap: Option<RTextureId>) -> Option<RObjectId> {
+    self.skybox_enabled = cubemap.is_some();
+    if let Some(skybox) = self.skybox {
+      let pipeline_id = RPipelineId(skybox.0);
+      let texture_id = cubemap?;
+      self.pipelines[pipeline_id.0].texture1_id = Some(texture_id);
+      self.rebuild_bind_group0(pipeline_id);
+      return Some(skybox);
+    }
+    let texture_id = cubemap?;
+    let pipeline_id = self.add_pipeline(RPipelineSetup {
+      shader: include_str!("../embed_assets/skybox.wgsl"),
+      max_obj_count: 1,
+      texture1_id: Some(texture_id),
+      texture1_dimension: RPipelineSetup::TEXTURE_DIM_CUBE,
+      blend_mode: RPipelineSetup::BLEND_MODE_OPAQUE,
+      alpha_to_coverage: false,
+      depth_test: false,
+      depth_write: false,
+      frustum_cull: false,
+      ..Default::default()
+    });
+    let (rect_data, rect_i) = Primitives::rect_indexed(2.0, 2.0, 0.0);
+    let object_id = self.add_object(RObjectSetup {
+      pipeline_id,
+      vertex_data: rect_data,
+      indices: rect_i,
+      ..Default::default()
+    });
+    self.skybox = Some(object_id);
+    Some(object_id)
+  }
+
+  // note: texture must be a cubemap with 6 array layers
+  pub fn set_environment(&mut self, texture_id: RTextureId) {
+    let env = &self.textures[texture_id.0];
+    if env.depth_or_array_layers() != 6 || env.width() != env.height() {
+      eprintln!("Err: Environment texture must be a cubemap");
+      return;
+    }
+    let mut passes = self.create_ibl_passes();
+    self.filter_environment(&mut passes, env);
+  }
+
+  pub fn set_pbr_lighting(&mut self, lighting: &RPbrLighting) {
+    let ibl = self.ibl.as_ref().unwrap();
+    let d = lighting.light_dir;
+    let c = lighting.light_color;
+    let i = lighting.light_intensity;
+    let data: [f32; 12] = [
+      d[0], d[1], d[2], 0.0,
+      c[0] * i, c[1] * i, c[2] * i, 1.0,
+      (IBL_PREFILTERED_MIPS - 1) as f32, lighting.ibl_intensity, 0.0, 0.0,
+    ];
+    self.queue.write_buffer(&ibl.lighting, 0, bytemuck::cast_slice(&data));
+  }
+
+  pub fn add_overlay_pipeline(&mut self) -> (RTextureId, RPipelineId) {
+    // build full screen texture
+    let texture_id = self.add_transient_texture(1.0, true);
+    // build render pipeline
+    let pipeline_id = self.add_pipeline(RPipelineSetup {
+      shader: include_str!("../embed_assets/text.wgsl"),
+      texture1_id: Some(texture_id),
+      ..Default::default()
+    });
+    // build object
+    let (rect_data, rect_i) = Primitives::rect_indexed(2.0, 2.0, 0.0);
+    let _rect = Shape::new(self, pipeline_id, rect_data, Some(rect_i));
+    // output fields
+    (texture_id, pipeline_id)
+  }
+
+  pub fn enable_post_processing(&mut self) {
+    if self.post_stack.is_some() { return; }
+    let scene_texture = self.add_transient_texture_with_format(1.0, HDR_FORMAT);
+    let present_pipeline = self.add_post_pipeline(
+      include_str!("../embed_assets/post.wgsl"), "fragmentCopy", scene_texture, None, RPipelineSetup::BLEND_MODE_OPAQUE
+    );
+    self.post_stack = Some(RPostStack {
+      scene_texture,
+      present_pipeline,
+      effects: Vec::new(),
+    });
+  }
+
+  // effects are applied in the order they are added
+  pub fn add_post_effect(&mut self, setup: RPostEffectSetup) -> RPostEffectId {
+    self.enable_post_processing();
+    let (id, input) = {
+      let stack = self.post_stack.as_ref().unwrap();
+      let input = match stack.effects.last() {
+        Some(e) => e.output,
+        None => stack.scene_texture,
+      };
+      (stack.effects.len(), input)
+    };
+    let shader = include_str!("../embed_assets/post.wgsl");
+    let output = self.add_transient_texture_with_format(1.0, HDR_FORMAT);
+    let passes: Vec<(RPipelineId, RTextureId)> = match setup.effect {
+      RPostEffectSetup::EFFECT_BLOOM => {
+        let bright = self.add_transient_texture_with_format(0.5, HDR_FORMAT);
+        let blur_h = self.add_transient_texture_with_format(0.5, HDR_FORMAT);
+        let blur_v = self.add_transient_texture_with_format(0.5, HDR_FORMAT);
+        vec![
+          (self.add_post_pipeline(shader, "fragmentBright", input, None, RPipelineSetup::BLEND_MODE_OPAQUE), bright),
+          (self.add_post_pipeline(shader, "fragmentBlurH", bright, None, RPipelineSetup::BLEND_MODE_OPAQUE), blur_h),
+          (self.add_post_pipeline(shader, "fragmentBlurV", blur_h, None, RPipelineSetup::BLEND_MODE_OPAQUE), blur_v),
+          (self.add_post_pipeline(shader, "fragmentBloom", input, Some(blur_v), RPipelineSetup::BLEND_MODE_OPAQUE), output),
+        ]
+      }
+      RPostEffectSetup::EFFECT_CUSTOM => {
+        let custom_shader = match setup.shader {
+          Some(str) => str,
+          None => {
+            eprintln!("Err: Custom post effect is missing shader, using copy pass");
+            shader
+          }
+        };
+        let fragment_fn = if setup.shader.is_some() { setup.fragment_fn } else { "fragmentCopy" };
+        vec![(self.add_post_pipeline(custom_shader, fragment_fn, input, setup.lut_texture_id, RPipelineSetup::BLEND_MODE_OPAQUE), output)]
+      }
+      _ => {
+        let fragment_fn = match setup.effect {
+          RPostEffectSetup::EFFECT_TONEMAP_ACES => "fragmentAces",
+          RPostEffectSetup::EFFECT_TONEMAP_REINHARD => "fragmentReinhard",
+          RPostEffectSetup::EFFECT_FXAA => "fragmentFxaa",
+          RPostEffectSetup::EFFECT_COLOR_GRADE => "fragmentLut",
+          RPostEffectSetup::EFFECT_VIGNETTE => "fragmentVignette",
+          RPostEffectSetup::EFFECT_GAMMA => "fragmentGamma",
+          _ => "fragmentCopy",
+        };
+        vec![(self.add_post_pipeline(shader, fragment_fn, input, setup.lut_texture_id, RPipelineSetup::BLEND_MODE_OPAQUE), output)]
+      }
+    };
+    let stack = self.post_stack.as_mut().unwrap();
+    stack.effects.push(RPostEffect { effect: setup.effect, passes, output });
+    // present latest output
+    let present_pipeline = stack.present_pipeline;
+    self.pipelines[present_pipeline.0].texture1_id = Some(output);
+    self.rebuild_bind_group0(present_pipeline);
+    self.update_post_effect(RPostEffectId(id), setup.params);
+    RPostEffectId(id)
+  }
+
+  pub fn update_post_effect(&mut self, effect_id: RPostEffectId, params: [f32; 4]) {
+    let pipeline_ids: Vec<RPipelineId> = match &self.post_stack {
+      Some(stack) => stack.effects[effect_id.0].passes.iter().map(|(p, _)| *p).collect(),
+      None => return,
+    };
+    for p_id in pipeline_ids {
+      self.update_object(RObjectUpdate {
+        object_id: RObjectId(p_id.0, 0),
+        uniforms: vec![bytemuck::cast_slice(&params)],
+        ..Default::default()
+      });
+    }
+  }
+
+  // full screen pass reading from texture1 (and optionally texture2)
+  fn add_post_pipeline(
+    &mut self,
+    shader: &str,
+    fragment_fn: &str,
+    texture1: RTextureId,
+    texture2: Option<RTextureId>,
+    blend_mode: u8,
+  ) -> RPipelineId {
+    let pipeline_id = self.add_pipeline(RPipelineSetup {
+      shader,
+      max_obj_count: 1,
+      texture1_id: Some(texture1),
+      texture2_id: texture2,
+      fragment_fn,
+      uniforms: vec![
+        RUniformSetup {
+          bind_slot: 0,
+          visibility: RUniformSetup::VISIBILITY_FRAGMENT,
+          size_in_bytes: 16,
+        }
+      ],
+      // fullscreen passes ignore depth
+      blend_mode,
+      alpha_to_coverage: false,
+      depth_test: false,
+      depth_write: false,
+      frustum_cull: false,
+      ..Default::default()
+    });
+    let (rect_data, rect_i) = Primitives::rect_indexed(2.0, 2.0, 0.0);
+    self.add_object(RObjectSetup {
+      pipeline_id,
+      vertex_data: rect_data,
+      indices: rect_i,
+      ..Default::default()
+    });
+    pipeline_id
+  }
+
+  // geometry shared by any number of objects through RObjectSetup::mesh
+  pub fn add_mesh(&mut self, vertex_data: &[RVertex], indices: &[u32]) -> RMeshId {
+    let positions: Vec<[f32; 3]> = vertex_data.iter().map(|v| v.position).collect();
+    let buffer = self.add_mesh_buffer(bytemuck::cast_slice(vertex_data), indices);
+    self.meshes.push(RMesh {
+      buffer,
+      vertex_type: RObjectSetup::VERTEX_TYPE_STATIC,
+      base_vertex: 0,
+      v_count: vertex_data.len() as u32,
+      first_index: 0,
+      index_count: indices.len() as u32,
+      bounds: RBounds::from_positions(&positions),
+      positions,
+      indices: indices.to_vec(),
+    });
+    RMeshId(self.meshes.len() - 1)
+  }
+
+  pub fn add_anim_mesh(&mut self, vertex_data: &[RVertexAnim], indices: &[u32]) -> RMeshId {
+    let positions: Vec<[f32; 3]> = vertex_data.iter().map(|v| v.position).collect();
+    let buffer = self.add_mesh_buffer(bytemuck::cast_slice(vertex_data), indices);
+    self.meshes.push(RMesh {
+      buffer,
+      vertex_type: RObjectSetup::VERTEX_TYPE_ANIM,
+      base_vertex: 0,
+      v_count: vertex_data.len() as u32,
+      first_index: 0,
+      index_count: indices.len() as u32,
+      bounds: RBounds::from_positions(&positions),
+      positions,
+      indices: indices.to_vec(),
+    });
+    RMeshId(self.meshes.len() - 1)
+  }
+
+  // merge static geometry into one vertex + index buffer, so objects drawn one after
+  // another do not rebind buffers. meshes without indices are indexed in order
+  pub fn add_static_meshes(&mut self, meshes: &[(Vec<RVertex>, Vec<u32>)]) -> Vec<RMeshId> {
+    let mut vertices: Vec<RVertex> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    let mut ranges: Vec<(u32, u32, u32, u32)> = Vec::with_capacity(meshes.len());
+    for (v, i) in meshes {
+      let first_index = indices.len() as u32;
+      match i.is_empty() {
+        true => indices.extend(0..v.len() as u32),
+        false => indices.extend_from_slice(i),
+      }
+      ranges.push((vertices.len() as u32, v.len() as u32, first_index, indices.len() as u32 - first_index));
+      vertices.extend_from_slice(v);
+    }
+    let buffer = self.add_mesh_buffer(bytemuck::cast_slice(&vertices), &indices);
+    let mut ids = Vec::with_capacity(meshes.len());
+    for ((v, i), (base_vertex, v_count, first_index, index_count)) in meshes.iter().zip(ranges) {
+      let positions: Vec<[f32; 3]> = v.iter().map(|v| v.position).collect();
+      self.meshes.push(RMesh {
+        buffer,
+        vertex_type: RObjectSetup::VERTEX_TYPE_STATIC,
+        base_vertex,
+        v_count,
+        first_index,
+        index_count,
+        bounds: RBounds::from_positions(&positions),
+        positions,
+        indices: i.clone(),
+      });
+      ids.push(RMeshId(self.meshes.len() - 1));
+    }
+    ids
+  }
+
+  pub fn mesh_bounds(&self, mesh: RMeshId) -> Option<RBounds> {
+    self.meshes[mesh.0].bounds
+  }
+
+  fn add_mesh_buffer(&mut self, vertex_bytes: &[u8], indices: &[u32]) -> usize {
+    let v_buffer = self.device.create_buffer(&BufferDescriptor {
+      label: Some("vertex-buffer"),
+      size: vertex_bytes.len() as u64,
+      usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    self.queue.write_buffer(&v_buffer, 0, vertex_bytes);
+    let mut index_buffer: Option<Buffer> = None;
+    if !indices.is_empty() {
+      let i_buffer = self.device.create_buffer(&BufferDescriptor {
+        label: Some("index-buffer"),
+        size: std::mem::size_of_val(indices) as u64,
+        usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false
+      });
+      self.queue.write_buffer(&i_buffer, 0, bytemuck::cast_slice(indices));
+      index_buffer = Some(i_buffer);
+    }
+    self.mesh_buffers.push(RMeshBuffer { v_buffer, index_buffer });
+    self.mesh_buffers.len() - 1
+  }
+
+  pub fn add_object(&mut self, obj_data: RObjectSetup) -> RObjectId {
+    // vertices shared from a compute buffer, counted from the buffer size
+    let vertex_size = match obj_data.vertex_type {
+      RObjectSetup::VERTEX_TYPE_ANIM => std::mem::size_of::<RVertexAnim>(),
+      _ => std::mem::size_of::<RVertex>(),
+    } as u64;
+    let shared_count = obj_data.vertex_buffer.and_then(|b| self.shared_buffer(&b)).map(|b| (b.size() / vertex_size) as usize);
+    let instance_source = obj_data.instance_buffer.filter(|b| self.shared_buffer(b).is_some());
+    if !self.pipelines[obj_data.pipeline_id.0].instance_attributes.is_empty() && instance_source.is_none() {
+      eprintln!("Err: Pipeline has instance attributes, object is not drawn without an instance buffer");
+    }
+
+    // reuse mesh, or upload vertex data as a new one
+    let pipeline_vertex_type = self.pipelines[obj_data.pipeline_id.0].vertex_type;
+    let mesh = match obj_data.mesh {
+      Some(mesh) => {
+        if self.meshes[mesh.0].vertex_type != pipeline_vertex_type {
+          eprintln!("Err: Mesh vertex type does not match the pipeline");
+        }
+        mesh
+      }
+      None => match obj_data.vertex_type {
+        RObjectSetup::VERTEX_TYPE_ANIM => self.add_anim_mesh(&obj_data.anim_vertex_data, &obj_data.indices),
+        _ => self.add_mesh(&obj_data.vertex_data, &obj_data.indices),
+      }
+    };
+    // object space bounds, vertices from a shared buffer are unknown
+    let bounds = self.meshes[mesh.0].bounds.filter(|_| shared_count.is_none());
+
+    // lower detail meshes
+    let mut lods: Vec<(RMeshId, f32)> = Vec::with_capacity(obj_data.lods.len());
+    if self.meshes[mesh.0].vertex_type == RObjectSetup::VERTEX_TYPE_ANIM && !obj_data.lods.is_empty() {
+      eprintln!("Err: Level of detail meshes are only supported for static vertices");
+    } else {
+      for lod in &obj_data.lods {
+        let lod_mesh = match lod.mesh {
+          Some(m) => m,
+          None => self.add_mesh(&lod.vertex_data, &lod.indices),
+        };
+        lods.push((lod_mesh, lod.threshold));
+      }
+    }
+
+    let pipe = &mut self.pipelines[obj_data.pipeline_id.0];
+    let id = pipe.objects.len();
+    // save to cache
+    let obj = RObject {
+      visible: true,
+      mesh,
+      v_count: shared_count.unwrap_or(0),
+      pipe_index: id,
+      instances: obj_data.instances,
+      cam_distance: 0.0,
+      material: obj_data.material,
+      vertex_source: obj_data.vertex_buffer.filter(|_| shared_count.is_some()),
+      instance_source,
+      bounds,
+      world_bounds: None,
+      model: Mat4::identity(),
+      camera: self.default_cam,
+      in_frustum: vec![true],
+      lods,
+      lod_mode: obj_data.lod_mode,
+      lod_hysteresis: obj_data.lod_hysteresis,
+      lod_level: 0,
+    };
+    pipe.objects.push(obj);
+    let object_id = RObjectId(obj_data.pipeline_id.0, id);
+    self.update_object(RObjectUpdate{ object_id, ..Default::default()});
+    object_id
+  }
+
+  // object space bounds computed from the vertex data
+  pub fn object_bounds(&self, object_id: RObjectId) -> Option<RBounds> {
+    self.pipelines[object_id.0].objects[object_id.1].bounds
+  }
+
+  // 0 for the object mesh, followed by its lods
+  pub fn object_lod(&self, object_id: RObjectId) -> usize {
+    self.pipelines[object_id.0].objects[object_id.1].lod_level
+  }
+
+  // ie number of live particles written by a compute pass
+  pub fn set_object_instances(&mut self, object_id: RObjectId, instances: u32) {
+    self.pipelines[object_id.0].objects[object_id.1].instances = instances;
+  }
+
+  pub fn update_object(&mut self, update: RObjectUpdate) {
+    let cam = *update.camera.unwrap_or(&self.default_cam);
+    let height = self.config.height;
+    let pipe = &mut self.pipelines[update.object_id.0];
+    let obj = &mut pipe.objects[update.object_id.1];
+
+    obj.visible = update.visible;
+    // distance used for draw order sorting
+    obj.cam_distance = (0..3).map(|i| (update.translate[i] - cam.position[i]).powi(2)).sum::<f32>().sqrt();
+    // model matrix
+    let model_t = Mat4::translate(update.translate[0], update.translate[1], update.translate[2]);
+    let model_r = Mat4::rotate(&update.rotate_axis, update.rotate_deg);
+    let model_s = Mat4::scale(update.scale[0], update.scale[1], update.scale[2]);
+    let model = Mat4::multiply(&model_t, &Mat4::multiply(&model_s, &model_r));
+    let world_bounds = obj.bounds.map(|b| b.transform(&model));
+    obj.world_bounds = world_bounds;
+    obj.model = model;
+    obj.camera = cam;
+    // level of detail, switching on a metric growing with distance
+    if let (false, Some(bounds)) = (obj.lods.is_empty(), world_bounds) {
+      let (metric, thresholds): (f32, Vec<f32>) = match obj.lod_mode {
+        RObjectSetup::LOD_MODE_DISTANCE => (obj.cam_distance, obj.lods.iter().map(|l| l.1).collect()),
+        _ => (
+          1.0 / cam.screen_size(&bounds.center, bounds.radius, height),
+          obj.lods.iter().map(|l| 1.0 / l.1).collect(),
+        ),
+      };
+      obj.lod_level = select_lod(metric, &thresholds, obj.lod_level, obj.lod_hysteresis);
+    }
+    let pipe = &self.pipelines[update.object_id.0];
+    let obj = &pipe.objects[update.object_id.1];
+    let (mvp, in_frustum) = self.object_view(pipe, obj, &cam, self.config.width, self.config.height);
+    let stride = self.limits.min_uniform_buffer_offset_alignment;
+    self.queue.write_buffer(
+      &pipe.bind_group0.entries[0], 
+      (stride * obj.pipe_index as u32) as u64, 
+      bytemuck::cast_slice(&mvp)
+    );
+    self.pipelines[update.object_id.0].objects[update.object_id.1].in_frustum[0] = in_frustum;
+    let pipe = &self.pipelines[update.object_id.0];
+    let obj = &pipe.objects[update.object_id.1];
+    // merge animation matrices into single buffer
+    if pipe.max_joints_count > 0 && update.anim_transforms.len() > 0 {
+      let mut anim_buffer: Vec<f32> = Vec::new();
+      for i in 0..pipe.max_joints_count {
+        if i >= update.anim_transforms.len() as u32 {
+          break;
+        }
+        // merge [f32; 16] arrays into single anim_buffer
+        let a = update.anim_transforms[i as usize];
+        anim_buffer.extend_from_slice(&a);
+      }
+      self.queue.write_buffer(&pipe.bind_group0.entries[1], 0, bytemuck::cast_slice(&anim_buffer));
+    }
+    // update custom uniforms
+    if update.uniforms.len() > 0 {
+      if let Some(bind_group1) = &pipe.bind_group1 {
+        for (i, uniform) in update.uniforms.iter().enumerate() {
+          // writing past the uniform size would spill into the next object
+          let size = pipe.uniform_sizes[i] as usize;
+          if uniform.len() != size {
+            eprintln!("Err: Uniform {} expects {} bytes, got {}", i, size, uniform.len());
+          }
+          self.queue.write_buffer(
+            &bind_group1.entries[i],
+            (stride * obj.pipe_index as u32) as u64,
+            &uniform[..usize::min(size, uniform.len())]
+          );
+        }
+      }
+    }
+  }
+
+  // model + view + projection for a target of the given size, and whether the object is in its frustum
+  fn object_view(&self, pipe: &RPipeline, obj: &RObject, cam: &RCamera, width: u32, height: u32) -> ([f32; 48], bool) {
+    let view = cam.view_matrix();
+    let proj = match self.reversed_z {
+      true => Mat4::multiply(&Mat4::reverse_z(), &cam.proj_matrix(width, height)),
+      false => cam.proj_matrix(width, height),
+    };
+    // instanced or skinned vertices can move outside of the object bounds
+    let cullable = self.frustum_culling && pipe.frustum_cull && pipe.max_joints_count == 0 && obj.instances == 1;
+    let in_frustum = match obj.world_bounds.filter(|_| cullable && obj.instance_source.is_none()) {
+      Some(bounds) => RFrustum::from_matrix(&Mat4::multiply(&proj, &view)).intersects_bounds(&bounds),
+      None => true,
+    };
+    // merge together
+    let mut mvp: [f32; 48] = [0.0; 48]; // 16 * 3 = 48
+    for i in 0..48 {
+      if i < 16 { mvp[i] = obj.model[i]; }
+      else if i < 32 { mvp[i] = view[i - 16]; }
+      else { mvp[i] = proj[i - 32]; }
+    }
+    (mvp, in_frustum)
+  }
+
+  // writes every object of a pipeline into a view slot of its mvp buffer with a single upload,
+  // objects without a view camera keep the camera from update_object
+  fn write_pipeline_view(&mut self, pipeline_id: RPipelineId, slot: usize, view: &RPassView) {
+    let (cam, width, height) = view;
+    let stride = self.limits.min_uniform_buffer_offset_alignment as usize;
+    let pipe = &self.pipelines[pipeline_id.0];
+    let mut data: Vec<u8> = vec![0; stride * pipe.objects.len()];
+    let mut in_frustum: Vec<bool> = Vec::with_capacity(pipe.objects.len());
+    for obj in &pipe.objects {
+      let (mvp, visible) = self.object_view(pipe, obj, cam.as_ref().unwrap_or(&obj.camera), *width, *height);
+      let offset = stride * obj.pipe_index;
+      data[offset..offset + 192].copy_from_slice(bytemuck::cast_slice(&mvp));
+      in_frustum.push(visible);
+    }
+    let offset = (stride * slot * pipe.max_obj_count) as u64;
+    self.queue.write_buffer(&pipe.bind_group0.entries[0], offset, &data);
+    for (obj, visible) in self.pipelines[pipeline_id.0].objects.iter_mut().zip(in_frustum) {
+      if obj.in_frustum.len() <= slot {
+        obj.in_frustum.resize(slot + 1, true);
+      }
+      obj.in_frustum[slot] = visible;
+    }
+  }
+
+  pub fn render_texture(&mut self, pipeline_ids: &[RPipelineId], target_id: RTextureId, clear_color: Option<[f64;4]>) {
+    let graph = RRenderGraph::new().with_pass(RGraphPass {
+      label: "render-texture-pass",
+      target: RPassTarget::Texture(target_id),
+      pipeline_ids: pipeline_ids.to_vec(),
+      clear_color,
+      ..Default::default()
+    });
+    if let Err(e) = self.render_graph(&graph) {
+      eprintln!("Err: Could not render to texture - {:?}", e);
+    }
+  }
+
+  pub fn render_str_on_texture(&mut self, texture_id: RTextureId, input: &str, size:f32, color: [u8; 3], base_point: [u32; 2], char_gap: u32) {
+    let texture = &mut self.textures[texture_id.0];
+    // fetch font data
+    if self.font_cache.is_none() { 
+      let font = include_bytes!("../embed_assets/roboto.ttf");
+      self.font_cache = Some(font.to_vec());
+    }
+    let font_data = self.font_cache.as_ref().unwrap();
+    // draw string onto existing texture
+    match draw_str(RStringInputs {
+      queue: &self.queue,
+      texture,
+      font_data,
+      string: input,
+      size,
+      color,
+      base_point,
+      char_gap,
+    }) {
+      Ok(()) => (),
+      Err(e) => {
+        println!("Could not draw str: \"{}\" - {:?}", input, e);
+      }
+    };
+  }
+
+  pub fn render(&mut self, pipeline_ids: &[RPipelineId]) -> Result<(), wgpu::SurfaceError> {
+    let graph = self.scene_graph(pipeline_ids, &[(None, None)]);
+    self.render_graph(&graph)
+  }
+
+  // same scene drawn from several cameras, each into a viewport (x, y, width, height) in pixels
+  // ie split-screen, picture-in-picture or editor quad views
+  // note: the first view clears the whole frame, later views draw over it
+  pub fn render_views(&mut self, pipeline_ids: &[RPipelineId], views: &[(RCamera, [f32; 4])]) -> Result<(), wgpu::SurfaceError> {
+    let views: Vec<(Option<RCamera>, Option<[f32; 4]>)> = views.iter().map(|(c, v)| (Some(*c), Some(*v))).collect();
+    let graph = self.scene_graph(pipeline_ids, &views);
+    self.render_graph(&graph)
+  }
+
+  fn scene_graph(&self, pipeline_ids: &[RPipelineId], views: &[(Option<RCamera>, Option<[f32; 4]>)]) -> RRenderGraph<'static> {
+    // skybox is drawn first, behind the scene
+    let mut scene_ids: Vec<RPipelineId> = Vec::with_capacity(pipeline_ids.len() + 1);
+    if let (Some(skybox), true) = (self.skybox, self.skybox_enabled) {
+      scene_ids.push(RPipelineId(skybox.0));
+    }
+    scene_ids.extend_from_slice(pipeline_ids);
+    let (label, target) = match &self.post_stack {
+      // render scene in hdr, then apply effects before presenting
+      Some(stack) => ("scene-pass", RPassTarget::Texture(stack.scene_texture)),
+      None => ("render-pass", RPassTarget::Surface),
+    };
+    let mut graph = RRenderGraph::new();
+    for (i, (camera, viewport)) in views.iter().enumerate() {
+      let load = if i == 0 { RGraphPass::LOAD_OP_CLEAR } else { RGraphPass::LOAD_OP_LOAD };
+      graph.add_pass(RGraphPass {
+        label,
+        target,
+        pipeline_ids: scene_ids.clone(),
+        color_load: load,
+        depth_load: load,
+        viewport: *viewport,
+        camera: *camera,
+        ..Default::default()
+      });
+    }
+    if let Some(stack) = &self.post_stack {
+      for effect in &stack.effects {
+        for (p_id, output) in &effect.passes {
+          let pipeline = &self.pipelines[p_id.0];
+          graph.add_pass(RGraphPass {
+            label: "post-effect-pass",
+            target: RPassTarget::Texture(*output),
+            inputs: pipeline.texture1_id.into_iter().chain(pipeline.texture2_id).collect(),
+            pipeline_ids: vec![*p_id],
+            ..Default::default()
+          });
+        }
+      }
+      let present_input = stack.effects.last()
+        .and_then(|e| e.passes.last())
+        .map_or(stack.scene_texture, |(_, output)| *output);
+      graph.add_pass(RGraphPass {
+        label: "present-pass",
+        inputs: vec![present_input],
+        pipeline_ids: vec![stack.present_pipeline],
+        ..Default::default()
+      });
+    }
+    graph
+  }
+
+  pub fn render_graph(&mut self, graph: &RRenderGraph) -> Result<(), wgpu::SurfaceError> {
+    let order = match graph.order() {
+      Ok(o) => o,
+      Err(e) => {
+        eprintln!("Err: Could not build render graph - {:?}", e);
+        return Ok(());
+      }
+    };
+    // timings of an earlier frame
+    if let Some(profiler) = &mut self.profiler {
+      self.device.poll(Maintain::Poll);
+      profiler.collect(self.queue.get_timestamp_period());
+    }
+    let mut encoder = self.device.create_command_encoder(
+      &wgpu::CommandEncoderDescriptor { label: Some("render-graph-encoder") }
+    );
+    // views with a pass specific camera or size (projections follow the viewport aspect, not the
+    // window) are drawn from their own mvp slot, slot 0 being the window view from update_object
+    let mut views: Vec<RPassView> = vec![(None, self.config.width, self.config.height)];
+    let mut pass_slots: Vec<usize> = Vec::with_capacity(graph.passes.len());
+    let mut slot_pipelines: Vec<(RPipelineId, usize)> = Vec::new();
+    // allocate msaa + depth attachments for each target
+    let mut target_formats: Vec<(TextureFormat, u32)> = Vec::with_capacity(graph.passes.len());
+    for graph_pass in &graph.passes {
+      let (width, height, color_format) = match graph_pass.target {
+        RPassTarget::Surface => (self.config.width, self.config.height, self.surface_format),
+        RPassTarget::Texture(tx_id) => {
+          let tx = &self.textures[tx_id.0];
+          (tx.width(), tx.height(), tx.format())
+        }
+      };
+      let slot = match graph_pass.clipped_rects(width, height) {
+        Some((vp, _)) => view_slot(&mut views, (graph_pass.camera, vp[2] as u32, vp[3] as u32)),
+        None => 0,
+      };
+      pass_slots.push(slot);
+      for p_id in &graph_pass.pipeline_ids {
+        if slot > 0 && !slot_pipelines.contains(&(*p_id, slot)) {
+          slot_pipelines.push((*p_id, slot));
+        }
+      }
+      let sample_count = self.prepare_attachment(graph_pass.target, width, height, color_format);
+      // compile pipelines for target format
+      for p_id in &graph_pass.pipeline_ids {
+        self.prepare_pipeline_variant(*p_id, color_format, sample_count);
+      }
+      // accumulation targets for transparent pipelines
+      if self.uses_oit(&graph_pass.pipeline_ids) {
+        let composite = self.prepare_oit_target(graph_pass.target, width, height, sample_count);
+        self.prepare_pipeline_variant(composite, color_format, sample_count);
+        for p_id in &graph_pass.pipeline_ids {
+          self.prepare_oit_variant(*p_id, sample_count);
+        }
+      }
+      target_formats.push((color_format, sample_count));
+    }
+    for (p_id, slot) in slot_pipelines {
+      if self.pipelines[p_id.0].objects.is_empty() { continue; }
+      if self.pipelines[p_id.0].view_slots <= slot {
+        self.grow_view_slots(p_id, slot + 1, &mut encoder);
+      }
+      self.write_pipeline_view(p_id, slot, &views[slot]);
+    }
+    let output = if graph.passes.iter().any(|p| p.target == RPassTarget::Surface) {
+      Some(self.surface.get_current_texture()?)
+    } else {
+      None
+    };
+    let mut stats = RenderStats::default();
+    for i in order {
+      let graph_pass = &graph.passes[i];
+      let (width, height) = match graph_pass.target {
+        RPassTarget::Surface => (self.config.width, self.config.height),
+        RPassTarget::Texture(tx_id) => (self.textures[tx_id.0].width(), self.textures[tx_id.0].height()),
+      };
+      let rects = graph_pass.clipped_rects(width, height);
+      let att = self.attachments.iter().find(|a| a.target == graph_pass.target).unwrap();
+      let tx = match graph_pass.target {
+        RPassTarget::Surface => &output.as_ref().unwrap().texture,
+        RPassTarget::Texture(tx_id) => &self.textures[tx_id.0],
+      };
+      // mipmapped textures are rendered into at their base level
+      let target = tx.create_view(&TextureViewDescriptor { mip_level_count: Some(1), ..Default::default() });
+      let zbuffer_view = att.zbuffer.create_view(&TextureViewDescriptor::default());
+      // without msaa, render directly into target
+      let msaa_view = att.msaa.as_ref().map(|m| m.create_view(&TextureViewDescriptor::default()));
+      let (view, resolve_target) = match &msaa_view {
+        Some(v) => (v, Some(&target)),
+        None => (&target, None),
+      };
+      let color_load = match graph_pass.color_load {
+        RGraphPass::LOAD_OP_LOAD => LoadOp::Load,
+        _ => match graph_pass.clear_color {
+          Some(c) => LoadOp::Clear(Color { r:c[0], g:c[1], b:c[2], a:c[3] }),
+          None => LoadOp::Clear(self.clear_color),
+        }
+      };
+      let depth_load = match graph_pass.depth_load {
+        RGraphPass::LOAD_OP_LOAD => LoadOp::Load,
+        _ => LoadOp::Clear(self.depth_clear()),
+      };
+      let stencil_ops = if self.depth_format.has_stencil_aspect() {
+        Some(Operations {
+          load: match graph_pass.depth_load {
+            RGraphPass::LOAD_OP_LOAD => LoadOp::Load,
+            _ => LoadOp::Clear(0),
+          },
+          store: StoreOp::Store,
+        })
+      } else {
+        None
+      };
+      let (color_format, sample_count) = target_formats[i];
+      // new context so ownership of encoder is released after pass finishes
+      let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some(graph_pass.label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+          view,
+          resolve_target,
+          ops: Operations {
+            load: color_load,
+            store: StoreOp::Store,
           },
         })],
         depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
           view: &zbuffer_view,
           depth_ops: Some(Operations {
-            load: LoadOp::Clear(1.0),
+            load: depth_load,
             store: StoreOp::Store
           }),
-          stencil_ops: None,
+          stencil_ops,
         }),
         occlusion_query_set: None,
-        timestamp_writes: None,
+        timestamp_writes: self.profiler.as_ref().and_then(|p| p.begin_pass(graph_pass.label)),
+      });
+      // passes with nothing left to draw still clear
+      let rects = match rects {
+        Some(r) => r,
+        None => continue,
+      };
+      set_pass_rects(&mut pass, &rects);
+      let mut state = RDrawState { view_slot: pass_slots[i], ..Default::default() };
+      self.draw_pipelines(&mut pass, &graph_pass.pipeline_ids, color_format, sample_count, &mut state);
+      drop(pass);
+      stats.merge(&state.stats);
+
+      // accumulate order independent transparency, then blend over target
+      if !self.uses_oit(&graph_pass.pipeline_ids) { continue; }
+      let oit = self.oit_targets.iter().find(|o| o.target == graph_pass.target).unwrap();
+      let accum = self.textures[oit.accum.0].create_view(&TextureViewDescriptor::default());
+      let reveal = self.textures[oit.reveal.0].create_view(&TextureViewDescriptor::default());
+      let accum_msaa = oit.accum_msaa.as_ref().map(|m| m.create_view(&TextureViewDescriptor::default()));
+      let reveal_msaa = oit.reveal_msaa.as_ref().map(|m| m.create_view(&TextureViewDescriptor::default()));
+      let (accum_view, accum_resolve) = match &accum_msaa {
+        Some(v) => (v, Some(&accum)),
+        None => (&accum, None),
+      };
+      let (reveal_view, reveal_resolve) = match &reveal_msaa {
+        Some(v) => (v, Some(&reveal)),
+        None => (&reveal, None),
+      };
+      let depth_stencil_load = RenderPassDepthStencilAttachment {
+        view: &zbuffer_view,
+        depth_ops: Some(Operations { load: LoadOp::Load, store: StoreOp::Store }),
+        stencil_ops: stencil_ops.map(|_| Operations { load: LoadOp::Load, store: StoreOp::Store }),
+      };
+      let mut oit_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("oit-accum-pass"),
+        color_attachments: &[
+          Some(RenderPassColorAttachment {
+            view: accum_view,
+            resolve_target: accum_resolve,
+            ops: Operations { load: LoadOp::Clear(Color::TRANSPARENT), store: StoreOp::Store },
+          }),
+          Some(RenderPassColorAttachment {
+            view: reveal_view,
+            resolve_target: reveal_resolve,
+            ops: Operations { load: LoadOp::Clear(Color::WHITE), store: StoreOp::Store },
+          }),
+        ],
+        depth_stencil_attachment: Some(depth_stencil_load.clone()),
+        occlusion_query_set: None,
+        timestamp_writes: self.profiler.as_ref().and_then(|p| p.begin_pass("oit-accum-pass")),
+      });
+      set_pass_rects(&mut oit_pass, &rects);
+      let mut state = RDrawState { view_slot: pass_slots[i], ..Default::default() };
+      self.draw_oit(&mut oit_pass, &graph_pass.pipeline_ids, sample_count, &mut state);
+      drop(oit_pass);
+      stats.merge(&state.stats);
+      let mut composite_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("oit-composite-pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+          view,
+          resolve_target,
+          ops: Operations { load: LoadOp::Load, store: StoreOp::Store },
+        })],
+        depth_stencil_attachment: Some(depth_stencil_load),
+        occlusion_query_set: None,
+        timestamp_writes: self.profiler.as_ref().and_then(|p| p.begin_pass("oit-composite-pass")),
+      });
+      set_pass_rects(&mut composite_pass, &rects);
+      let mut state = RDrawState::default();
+      self.draw_pipelines(&mut composite_pass, &[oit.composite], color_format, sample_count, &mut state);
+      drop(composite_pass);
+      stats.merge(&state.stats);
+    }
+
+    // passes timed across the frame are read back once presenting
+    let resolving = match &mut self.profiler {
+      Some(profiler) if output.is_some() => profiler.resolve(&mut encoder),
+      _ => false,
+    };
+    self.queue.submit(std::iter::once(encoder.finish()));
+    if resolving {
+      self.profiler.as_ref().unwrap().request_readback();
+    }
+    // offscreen passes count towards the next presented frame
+    self.frame_stats.merge(&stats);
+    if let Some(o) = output {
+      o.present();
+      self.finish_frame_stats();
+    }
+
+    Ok(())
+  }
+
+  // make sure target has attachments matching its current size, returns sample count used
+  fn prepare_attachment(&mut self, target: RPassTarget, width: u32, height: u32, format: TextureFormat) -> u32 {
+    let sample_count = self.supported_sample_count(format);
+    if let Some(i) = self.attachments.iter().position(|a| a.target == target) {
+      let att = &self.attachments[i];
+      let z = &att.zbuffer;
+      let format_match = match &att.msaa {
+        Some(m) => m.format() == format,
+        None => true,
+      };
+      if z.width() == width && z.height() == height && z.sample_count() == sample_count && format_match {
+        return sample_count;
+      }
+      let old = self.attachments.remove(i);
+      if let Some(msaa) = &old.msaa {
+        msaa.destroy();
+      }
+      old.zbuffer.destroy();
+    }
+    let (msaa, zbuffer) = Renderer::create_attachments(&self.device, width, height, format, self.depth_format, sample_count);
+    self.attachments.push(RAttachment { target, msaa, zbuffer });
+    sample_count
+  }
+
+  // note: takes effect on the next update_object
+  pub fn set_frustum_culling(&mut self, enabled: bool) {
+    self.frustum_culling = enabled;
+  }
+
+  pub fn render_stats(&self) -> RenderStats {
+    self.stats
+  }
+
+  // frame wide counters, then start counting the next frame
+  fn finish_frame_stats(&mut self) {
+    let now = Instant::now();
+    let mut stats = std::mem::take(&mut self.frame_stats);
+    stats.frame_time_ms = self.last_present.map_or(0.0, |t| (now - t).as_secs_f32() * 1000.0);
+    stats.upload_bytes = self.queue.take_uploaded();
+    let attachments = self.attachments.iter().flat_map(|a| a.msaa.iter().chain(std::iter::once(&a.zbuffer)));
+    let oit = self.oit_targets.iter().flat_map(|o| o.accum_msaa.iter().chain(o.reveal_msaa.iter()));
+    for tx in self.textures.iter().chain(attachments).chain(oit) {
+      stats.textures += 1;
+      stats.texture_memory += texture_bytes(tx.format(), tx.size(), tx.mip_level_count(), tx.sample_count());
+    }
+    stats.gpu_time_ms = self.gpu_pass_timings().iter().map(|(_, ms)| ms).sum();
+    self.last_present = Some(now);
+    self.stats = stats;
+  }
+
+  // time stamp each render pass, returns false when the adapter has no timestamp query support
+  // note: off by default, timings are read back without stalling and lag a few frames behind
+  pub fn set_gpu_profiling(&mut self, enabled: bool) -> bool {
+    if !enabled {
+      if let Some(profiler) = self.profiler.take() {
+        profiler.destroy();
+      }
+      return true;
+    }
+    if !self.device.features().contains(Features::TIMESTAMP_QUERY) {
+      return false;
+    }
+    if self.profiler.is_none() {
+      self.profiler = Some(RProfiler::new(&self.device));
+    }
+    true
+  }
+
+  // closest visible object hit by a ray, tested against its triangles
+  // note: vertices come from the mesh data at creation, skinning + instancing are not applied
+  // and objects drawn from shared vertex buffers are skipped
+  pub fn pick(&self, ray: &RRay, pipeline_ids: &[RPipelineId]) -> Option<RPickHit> {
+    let mut best: Option<RPickHit> = None;
+    for p_id in pipeline_ids {
+      let pipe = &self.pipelines[p_id.0];
+      for (i, obj) in pipe.objects.iter().enumerate() {
+        let bounds = match obj.world_bounds {
+          Some(b) if obj.visible => b,
+          _ => continue,
+        };
+        let box_distance = match ray.intersect_aabb(&bounds.min, &bounds.max) {
+          Some(t) => t,
+          None => continue,
+        };
+        if best.is_some_and(|b| b.distance <= box_distance) { continue; }
+        // lines + points are picked by their bounds
+        let distance = match pipe.polygon_mode {
+          PolygonMode::Fill => {
+            let mesh = &self.meshes[self.object_mesh(obj).0];
+            let local = ray.transform(&Mat4::inverse(&obj.model));
+            match local.intersect_mesh(&mesh.positions, &mesh.indices) {
+              Some(t) => t,
+              None => continue,
+            }
+          }
+          _ => box_distance,
+        };
+        if !best.is_some_and(|b| b.distance <= distance) {
+          best = Some(RPickHit { object_id: RObjectId(p_id.0, i), distance, point: ray.at(distance) });
+        }
+      }
+    }
+    best
+  }
+
+  // object drawn at a pixel (from the top left) of the window, rendered into an id buffer
+  // note: waits for the gpu to finish, uses the transforms of the last update_object calls
+  // and skips pipelines with instance attributes
+  pub fn pick_pixel(&mut self, pipeline_ids: &[RPipelineId], x: u32, y: u32) -> Option<RObjectId> {
+    let (width, height) = (self.config.width, self.config.height);
+    if x >= width || y >= height { return None; }
+    // objects in draw order, id 0 is the background
+    let mut ids: Vec<RObjectId> = Vec::new();
+    for p_id in pipeline_ids {
+      let pipe = &self.pipelines[p_id.0];
+      if !pipe.instance_attributes.is_empty() { continue; }
+      for (i, obj) in pipe.objects.iter().enumerate() {
+        if obj.visible && obj.in_frustum[0] { ids.push(RObjectId(p_id.0, i)); }
+      }
+    }
+    self.prepare_picker(width, height, ids.len());
+    for p_id in pipeline_ids {
+      self.prepare_pick_variant(*p_id);
+    }
+    let picker = self.picker.as_ref().unwrap();
+    let align = self.limits.min_uniform_buffer_offset_alignment;
+    for (n, _) in ids.iter().enumerate() {
+      self.queue.write_buffer(&picker.id_buffer, n as u64 * align as u64, bytemuck::cast_slice(&[n as u32 + 1, 0, 0, 0]));
+    }
+
+    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("pick-encoder") });
+    let target = picker.target.create_view(&TextureViewDescriptor::default());
+    let zbuffer = picker.zbuffer.create_view(&TextureViewDescriptor::default());
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("pick-pass"),
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view: &target,
+        resolve_target: None,
+        ops: Operations { load: LoadOp::Clear(Color::TRANSPARENT), store: StoreOp::Store },
+      })],
+      depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+        view: &zbuffer,
+        depth_ops: Some(Operations { load: LoadOp::Clear(self.depth_clear()), store: StoreOp::Store }),
+        stencil_ops: None,
+      }),
+      occlusion_query_set: None,
+      timestamp_writes: None,
+    });
+    for (n, id) in ids.iter().enumerate() {
+      let pipe = &self.pipelines[id.0];
+      let obj = &pipe.objects[id.1];
+      let variant = match picker.variants.iter().find(|(p, _)| *p == id.0) {
+        Some((_, v)) => v,
+        None => continue,
+      };
+      pass.set_pipeline(variant);
+      let stride = align * obj.pipe_index as u32;
+      pass.set_bind_group(0, &pipe.bind_group0.base, &[stride]);
+      pass.set_bind_group(1, &picker.id_bind_group, &[align * n as u32]);
+      let mesh = &self.meshes[self.object_mesh(obj).0];
+      let mesh_buffer = &self.mesh_buffers[mesh.buffer];
+      let vertex_buffer = obj.vertex_source.and_then(|b| self.shared_buffer(&b)).unwrap_or(&mesh_buffer.v_buffer);
+      pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+      match &mesh_buffer.index_buffer {
+        Some(i_buffer) if mesh.index_count > 0 => {
+          pass.set_index_buffer(i_buffer.slice(..), IndexFormat::Uint32);
+          pass.draw_indexed(mesh.first_index..mesh.first_index + mesh.index_count, mesh.base_vertex as i32, 0..obj.instances);
+        }
+        _ => match obj.vertex_source {
+          Some(_) => pass.draw(0..(obj.v_count as u32), 0..obj.instances),
+          None => pass.draw(mesh.base_vertex..mesh.base_vertex + mesh.v_count, 0..obj.instances),
+        }
+      }
+    }
+    drop(pass);
+    encoder.copy_texture_to_buffer(
+      ImageCopyTexture {
+        texture: &picker.target,
+        mip_level: 0,
+        origin: Origin3d { x, y, z: 0 },
+        aspect: TextureAspect::All,
+      },
+      ImageCopyBuffer {
+        buffer: &picker.readback,
+        layout: ImageDataLayout { offset: 0, bytes_per_row: Some(COPY_BYTES_PER_ROW_ALIGNMENT), rows_per_image: None },
+      },
+      Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+    );
+    self.queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = picker.readback.slice(..4);
+    slice.map_async(MapMode::Read, |_| ());
+    self.device.poll(Maintain::Wait);
+    let id = bytemuck::cast_slice::<u8, u32>(&slice.get_mapped_range())[0];
+    picker.readback.unmap();
+    match id {
+      0 => None,
+      n => ids.get(n as usize - 1).copied(),
+    }
+  }
+
+  // id target sized to the window, id buffer with room for count objects
+  fn prepare_picker(&mut self, width: u32, height: u32, count: usize) {
+    let align = self.limits.min_uniform_buffer_offset_alignment as u64;
+    let id_size = u64::max(1, count as u64) * align;
+    if let Some(picker) = &self.picker {
+      let fits = picker.target.width() == width && picker.target.height() == height && picker.id_buffer.size() >= id_size;
+      if fits { return; }
+    }
+    let variants = match self.picker.take() {
+      Some(old) => {
+        old.target.destroy();
+        old.zbuffer.destroy();
+        old.id_buffer.destroy();
+        old.readback.destroy();
+        Some((old.shader_mod, old.id_layout, old.variants))
+      }
+      None => None,
+    };
+    let (shader_mod, id_layout, variants) = variants.unwrap_or_else(|| {
+      let source = preprocess_shader(include_str!("../embed_assets/pick.wgsl"), "pick.wgsl", &[], None).unwrap();
+      let shader_mod = self.device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("pick-shader-module"),
+        source: ShaderSource::Wgsl(source.code.into()),
       });
-      // add objects to render
-      for p_id in pipeline_ids {
-        let pipeline = &self.pipelines[p_id.0];
-        for obj in &pipeline.objects {
-          if !obj.visible { continue; }
-          let stride = self.limits.min_uniform_buffer_offset_alignment * obj.pipe_index as u32;
-          pass.set_pipeline(&pipeline.pipe);
-          pass.set_vertex_buffer(0, obj.v_buffer.slice(..));
-          pass.set_bind_group(0, &pipeline.bind_group0.base, &[stride]);
-          if let Some(bind_group1) = &pipeline.bind_group1 {
-            pass.set_bind_group(1, &bind_group1.base, &[stride]);
-          }
-          if let Some(i_buffer) = &obj.index_buffer {
-            pass.set_index_buffer(i_buffer.slice(..), IndexFormat::Uint32);
-            pass.draw_indexed(0..obj.index_count, 0, 0..obj.instances);
-          } else {
-            pass.draw(0..(obj.v_count as u32), 0..obj.instances);
-          }
-        }
+      let id_layout = self.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("pick-bind-group-layout"),
+        entries: &[BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: NonZeroU64::new(16),
+          },
+          count: None,
+        }],
+      });
+      (shader_mod, id_layout, Vec::new())
+    });
+    // grow in steps, so picking with a few more objects does not reallocate
+    let id_buffer = self.device.create_buffer(&BufferDescriptor {
+      label: Some("pick-id-buffer"),
+      size: u64::max(id_size, 64 * align).next_power_of_two(),
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let id_bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+      label: Some("pick-bind-group"),
+      layout: &id_layout,
+      entries: &[BindGroupEntry {
+        binding: 0,
+        resource: BindingResource::Buffer(BufferBinding { buffer: &id_buffer, offset: 0, size: NonZeroU64::new(16) }),
+      }],
+    });
+    let texture = |label: &'static str, format: TextureFormat, usage: TextureUsages| self.device.create_texture(&TextureDescriptor {
+      label: Some(label),
+      size: Extent3d { width, height, depth_or_array_layers: 1 },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: TextureDimension::D2,
+      format,
+      usage,
+      view_formats: &[],
+    });
+    let target = texture("pick-texture", PICK_FORMAT, TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC);
+    let zbuffer = texture("pick-zbuffer", PICK_DEPTH_FORMAT, TextureUsages::RENDER_ATTACHMENT);
+    let readback = self.device.create_buffer(&BufferDescriptor {
+      label: Some("pick-readback-buffer"),
+      size: COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+      usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    self.picker = Some(RPicker { shader_mod, id_layout, variants, id_buffer, id_bind_group, target, zbuffer, readback });
+  }
+
+  // position only variant of a pipeline, writing the id uniform instead of color
+  fn prepare_pick_variant(&mut self, pipeline_id: RPipelineId) {
+    let picker = self.picker.as_ref().unwrap();
+    if picker.variants.iter().any(|(p, _)| *p == pipeline_id.0) { return; }
+    let pipe = &self.pipelines[pipeline_id.0];
+    let layout = self.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+      label: Some("pick-pipeline-layout"),
+      bind_group_layouts: &[&pipe.bind_group_layouts[0], &picker.id_layout],
+      push_constant_ranges: &[],
+    });
+    let vertex_stride = match pipe.vertex_type {
+      RPipelineSetup::VERTEX_TYPE_ANIM => std::mem::size_of::<RVertexAnim>(),
+      _ => std::mem::size_of::<RVertex>(),
+    } as BufferAddress;
+    let pipeline = self.device.create_render_pipeline(&RenderPipelineDescriptor {
+      label: Some("pick-pipeline"),
+      layout: Some(&layout),
+      vertex: VertexState {
+        module: &picker.shader_mod,
+        entry_point: "vertexMain",
+        buffers: &[VertexBufferLayout {
+          array_stride: vertex_stride,
+          step_mode: VertexStepMode::Vertex,
+          attributes: &vertex_attr_array![0 => Float32x3],
+        }],
+        compilation_options: PipelineCompilationOptions::default(),
+      },
+      fragment: Some(FragmentState {
+        module: &picker.shader_mod,
+        entry_point: "fragmentMain",
+        targets: &[Some(ColorTargetState { format: PICK_FORMAT, blend: None, write_mask: ColorWrites::ALL })],
+        compilation_options: PipelineCompilationOptions::default(),
+      }),
+      primitive: PrimitiveState {
+        topology: pipe.topology,
+        cull_mode: pipe.cull_mode,
+        polygon_mode: pipe.polygon_mode,
+        ..PrimitiveState::default()
+      },
+      depth_stencil: Some(DepthStencilState {
+        format: PICK_DEPTH_FORMAT,
+        depth_write_enabled: true,
+        depth_compare: if self.reversed_z { CompareFunction::Greater } else { CompareFunction::Less },
+        stencil: StencilState::default(),
+        bias: DepthBiasState::default(),
+      }),
+      multisample: MultisampleState::default(),
+      multiview: None,
+    });
+    self.picker.as_mut().unwrap().variants.push((pipeline_id.0, pipeline));
+  }
+
+  // gpu time in milliseconds per render pass label, of the last frame read back
+  pub fn gpu_pass_timings(&self) -> &[(String, f32)] {
+    match &self.profiler {
+      Some(profiler) => &profiler.timings,
+      None => &[],
+    }
+  }
+
+  // note: transparent pipelines are skipped here when drawn through the oit passes
+  pub fn set_oit_enabled(&mut self, enabled: bool) {
+    self.oit_enabled = enabled;
+  }
+
+  fn uses_oit(&self, pipeline_ids: &[RPipelineId]) -> bool {
+    self.oit_enabled && pipeline_ids.iter().any(|p| self.is_oit_pipeline(&self.pipelines[p.0]))
+  }
+
+  fn is_oit_pipeline(&self, pipeline: &RPipeline) -> bool {
+    self.oit_enabled && pipeline.transparent && pipeline.oit_fragment_fn.is_some()
+  }
+
+  // make sure target has accumulation textures matching its current size, returns composite pipeline
+  fn prepare_oit_target(&mut self, target: RPassTarget, width: u32, height: u32, sample_count: u32) -> RPipelineId {
+    let i = match self.oit_targets.iter().position(|o| o.target == target) {
+      Some(i) => i,
+      None => {
+        let accum = self.add_texture_with_format(width, height, None, OIT_ACCUM_FORMAT);
+        let reveal = self.add_texture_with_format(width, height, None, OIT_REVEAL_FORMAT);
+        let composite = self.add_post_pipeline(
+          include_str!("../embed_assets/post.wgsl"),
+          "fragmentOitComposite",
+          accum,
+          Some(reveal),
+          // composited over the opaque scene
+          RPipelineSetup::BLEND_MODE_ALPHA,
+        );
+        self.oit_targets.push(ROitTarget { target, accum, reveal, accum_msaa: None, reveal_msaa: None, composite });
+        self.oit_targets.len() - 1
+      }
+    };
+    let (accum, reveal, composite) = {
+      let oit = &self.oit_targets[i];
+      (oit.accum, oit.reveal, oit.composite)
+    };
+    let tx = &self.textures[accum.0];
+    if tx.width() != width || tx.height() != height {
+      self.update_texture_size(accum, None, width, height);
+      self.update_texture_size(reveal, Some(composite), width, height);
+    }
+    let oit = &mut self.oit_targets[i];
+    let msaa_match = match &oit.accum_msaa {
+      Some(m) => m.width() == width && m.height() == height && m.sample_count() == sample_count,
+      None => sample_count == 1,
+    };
+    if !msaa_match {
+      for msaa in [&oit.accum_msaa, &oit.reveal_msaa].into_iter().flatten() {
+        msaa.destroy();
       }
+      oit.accum_msaa = Renderer::create_msaa_texture(&self.device, width, height, OIT_ACCUM_FORMAT, sample_count);
+      oit.reveal_msaa = Renderer::create_msaa_texture(&self.device, width, height, OIT_REVEAL_FORMAT, sample_count);
     }
-    self.queue.submit(std::iter::once(encoder.finish()));
+    composite
   }
 
-  pub fn render_str_on_texture(&mut self, texture_id: RTextureId, input: &str, size:f32, color: [u8; 3], base_point: [u32; 2], char_gap: u32) {
-    let texture = &mut self.textures[texture_id.0];
-    // fetch font data
-    if self.font_cache.is_none() { 
-      let font = include_bytes!("../embed_assets/roboto.ttf");
-      self.font_cache = Some(font.to_vec());
+  fn draw_pipelines<'p>(&'p self, pass: &mut RenderPass<'p>, pipeline_ids: &[RPipelineId], color_format: TextureFormat, sample_count: u32, state: &mut RDrawState) {
+    let mut transparent: Vec<(&RPipeline, &RObject)> = Vec::new();
+    for p_id in pipeline_ids {
+      let pipeline = &self.pipelines[p_id.0];
+      if self.is_oit_pipeline(pipeline) { continue; }
+      if pipeline.transparent {
+        transparent.extend(pipeline.objects.iter().map(|obj| (pipeline, obj)));
+        continue;
+      }
+      let variant = match pipeline.variants.iter().find(|(f, c, _)| *f == color_format && *c == sample_count) {
+        Some((_, _, v)) => v,
+        None => continue,
+      };
+      // opaque objects grouped by material + mesh, front to back within a group
+      let keys: Vec<[usize; 3]> = pipeline.objects.iter().map(|obj| {
+        let mesh = self.object_mesh(obj);
+        [obj.material.map_or(0, |m| m.0 + 1), self.meshes[mesh.0].buffer, mesh.0]
+      }).collect();
+      let distances: Vec<f32> = pipeline.objects.iter().map(|obj| obj.cam_distance).collect();
+      for o in batch_order(&keys, &distances) {
+        self.draw_object(pass, pipeline, &pipeline.objects[o], variant, state);
+      }
     }
-    let font_data = self.font_cache.as_ref().unwrap();
-    // draw string onto existing texture
-    match draw_str(RStringInputs {
-      queue: &self.queue,
-      texture,
-      font_data,
-      string: input,
-      size,
-      color,
-      base_point,
-      char_gap,
-    }) {
-      Ok(()) => (),
-      Err(e) => {
-        println!("Could not draw str: \"{}\" - {:?}", input, e);
+    // transparent objects back to front, across pipelines
+    let distances: Vec<f32> = transparent.iter().map(|(_, obj)| obj.cam_distance).collect();
+    for t in draw_order(&distances, true) {
+      let (pipeline, obj) = transparent[t];
+      if let Some((_, _, variant)) = pipeline.variants.iter().find(|(f, c, _)| *f == color_format && *c == sample_count) {
+        self.draw_object(pass, pipeline, obj, variant, state);
       }
-    };
+    }
   }
 
-  pub fn render(&mut self, pipeline_ids: &Vec<RPipelineId>) -> Result<(), wgpu::SurfaceError> {
-    let output = self.surface.get_current_texture()?;
-    let view = self.msaa.create_view(&TextureViewDescriptor::default());
-    let target = output.texture.create_view(&TextureViewDescriptor::default());
-    let zbuffer_view = self.zbuffer.create_view(&TextureViewDescriptor::default());
-    let mut encoder = self.device.create_command_encoder(
-      &wgpu::CommandEncoderDescriptor { label: Some("render-encoder") }
-    );
-    {
-      // new context so ownership of encoder is released after pass finishes
-      let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
-        label: Some("render-pass"),
-        color_attachments: &[Some(RenderPassColorAttachment {
-          view: &view,
-          resolve_target: Some(&target),
-          ops: Operations {
-            load: LoadOp::Clear(self.clear_color),
-            store: StoreOp::Store,
-          },
-        })],
-        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-          view: &zbuffer_view,
-          depth_ops: Some(Operations {
-            load: LoadOp::Clear(1.0),
-            store: StoreOp::Store
-          }),
-          stencil_ops: None,
-        }),
-        occlusion_query_set: None,
-        timestamp_writes: None,
-      });
-      // add objects to render
-      for p_id in pipeline_ids {
-        let pipeline = &self.pipelines[p_id.0];
-        for obj in &pipeline.objects {
-          if !obj.visible { continue; }
-          let stride = self.limits.min_uniform_buffer_offset_alignment * obj.pipe_index as u32;
-          pass.set_pipeline(&pipeline.pipe);
-          pass.set_vertex_buffer(0, obj.v_buffer.slice(..));
-          pass.set_bind_group(0, &pipeline.bind_group0.base, &[stride]);
-          if let Some(bind_group1) = &pipeline.bind_group1 {
-            pass.set_bind_group(1, &bind_group1.base, &[stride]);
-          }
-          if let Some(i_buffer) = &obj.index_buffer {
-            pass.set_index_buffer(i_buffer.slice(..), IndexFormat::Uint32);
-            pass.draw_indexed(0..obj.index_count, 0, 0..obj.instances);
-          } else {
-            pass.draw(0..(obj.v_count as u32), 0..obj.instances);
-          }
-        }
+  fn draw_oit<'p>(&'p self, pass: &mut RenderPass<'p>, pipeline_ids: &[RPipelineId], sample_count: u32, state: &mut RDrawState) {
+    for p_id in pipeline_ids {
+      let pipeline = &self.pipelines[p_id.0];
+      if !self.is_oit_pipeline(pipeline) { continue; }
+      let variant = match pipeline.oit_variants.iter().find(|(c, _)| *c == sample_count) {
+        Some((_, v)) => v,
+        None => continue,
+      };
+      for obj in &pipeline.objects {
+        self.draw_object(pass, pipeline, obj, variant, state);
       }
     }
+  }
 
-    self.queue.submit(std::iter::once(encoder.finish()));
-    output.present();
+  // mesh of the current level of detail
+  fn object_mesh(&self, obj: &RObject) -> RMeshId {
+    match obj.lod_level {
+      0 => obj.mesh,
+      l => obj.lods[l - 1].0,
+    }
+  }
 
-    Ok(())
+  fn draw_object<'p>(&'p self, pass: &mut RenderPass<'p>, pipeline: &'p RPipeline, obj: &'p RObject, variant: &'p RenderPipeline, state: &mut RDrawState) {
+    if !obj.visible { return; }
+    if !obj.in_frustum.get(state.view_slot).copied().unwrap_or(true) {
+      state.stats.objects_culled += 1;
+      return;
+    }
+    let instance_buffer = obj.instance_source.and_then(|b| self.shared_buffer(&b));
+    if !pipeline.instance_attributes.is_empty() && instance_buffer.is_none() { return; }
+    let align = self.limits.min_uniform_buffer_offset_alignment;
+    let stride = align * obj.pipe_index as u32;
+    if state.pipeline != Some(variant.global_id()) {
+      pass.set_pipeline(variant);
+      state.pipeline = Some(variant.global_id());
+      state.stats.pipeline_switches += 1;
+      // pipeline wide bind groups
+      for group in &pipeline.resource_groups {
+        pass.set_bind_group(group.index, &group.bind_group, &[]);
+      }
+      if pipeline.shading == RPipelineSetup::SHADING_PBR {
+        pass.set_bind_group(3, &self.ibl.as_ref().unwrap().bind_group, &[]);
+      }
+      state.material = None;
+    }
+    if state.stencil_reference != Some(pipeline.stencil_reference) {
+      pass.set_stencil_reference(pipeline.stencil_reference);
+      state.stencil_reference = Some(pipeline.stencil_reference);
+    }
+    let mesh = &self.meshes[self.object_mesh(obj).0];
+    let mesh_buffer = &self.mesh_buffers[mesh.buffer];
+    let vertex_buffer = obj.vertex_source.and_then(|b| self.shared_buffer(&b)).unwrap_or(&mesh_buffer.v_buffer);
+    if state.vertex_buffer != Some(vertex_buffer.global_id()) {
+      pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+      state.vertex_buffer = Some(vertex_buffer.global_id());
+      state.stats.buffer_switches += 1;
+    }
+    if let Some(buffer) = instance_buffer {
+      if state.instance_buffer != Some(buffer.global_id()) {
+        pass.set_vertex_buffer(1, buffer.slice(..));
+        state.instance_buffer = Some(buffer.global_id());
+        state.stats.buffer_switches += 1;
+      }
+    }
+    let view_offset = align * (state.view_slot * pipeline.max_obj_count + obj.pipe_index) as u32;
+    pass.set_bind_group(0, &pipeline.bind_group0.base, &[view_offset]);
+    if let Some(bind_group1) = &pipeline.bind_group1 {
+      pass.set_bind_group(1, &bind_group1.base, &[stride]);
+    }
+    if pipeline.use_materials {
+      let material = obj.material.or(self.default_material).unwrap();
+      if state.material != Some(material) {
+        pass.set_bind_group(2, &self.materials[material.0].bind_group, &[]);
+        state.material = Some(material);
+        state.stats.material_switches += 1;
+      }
+    }
+    match &mesh_buffer.index_buffer {
+      Some(i_buffer) if mesh.index_count > 0 => {
+        if state.index_buffer != Some(i_buffer.global_id()) {
+          pass.set_index_buffer(i_buffer.slice(..), IndexFormat::Uint32);
+          state.index_buffer = Some(i_buffer.global_id());
+          state.stats.buffer_switches += 1;
+        }
+        pass.draw_indexed(mesh.first_index..mesh.first_index + mesh.index_count, mesh.base_vertex as i32, 0..obj.instances);
+        state.stats.triangles += triangle_count(pipeline.topology, mesh.index_count, obj.instances);
+      }
+      _ => {
+        let (first, v_count) = match obj.vertex_source {
+          Some(_) => (0, obj.v_count as u32),
+          None => (mesh.base_vertex, mesh.v_count),
+        };
+        pass.draw(first..first + v_count, 0..obj.instances);
+        state.stats.triangles += triangle_count(pipeline.topology, v_count, obj.instances);
+      }
+    }
+    state.stats.draw_calls += 1;
+    state.stats.objects_drawn += 1;
   }
 
   pub fn destroy(&mut self, destroy_renderer: bool) {
@@ -1047,12 +4218,6 @@ impl<'a> Renderer<'a> {
     self.textures.clear();
     // destroy pipeline buffers
     for pipe in &mut self.pipelines {
-      for obj in &mut pipe.objects {
-        obj.v_buffer.destroy();
-        if let Some(ibf) = &mut obj.index_buffer {
-          ibf.destroy();
-        }
-      }
       for bf in &mut pipe.bind_group0.entries {
         bf.destroy();
       }
@@ -1061,13 +4226,563 @@ impl<'a> Renderer<'a> {
           bf.destroy();
         }
       }
+      for group in &mut pipe.resource_groups {
+        for bf in group.buffers.iter_mut().flatten() {
+          bf.destroy();
+        }
+      }
     }
     self.pipelines.clear();
+    for mb in &mut self.mesh_buffers {
+      mb.v_buffer.destroy();
+      if let Some(ibf) = &mut mb.index_buffer {
+        ibf.destroy();
+      }
+    }
+    self.mesh_buffers.clear();
+    self.meshes.clear();
+    for compute in &mut self.compute_pipelines {
+      for group in &mut compute.resource_groups {
+        for bf in group.buffers.iter_mut().flatten() {
+          bf.destroy();
+        }
+      }
+    }
+    self.compute_pipelines.clear();
+    self.shader_watches.clear();
+    if let Some(profiler) = self.profiler.take() {
+      profiler.destroy();
+    }
+    if let Some(picker) = self.picker.take() {
+      picker.target.destroy();
+      picker.zbuffer.destroy();
+      picker.id_buffer.destroy();
+      picker.readback.destroy();
+    }
+    self.clear_attachments();
+    for oit in &self.oit_targets {
+      for msaa in [&oit.accum_msaa, &oit.reveal_msaa].into_iter().flatten() {
+        msaa.destroy();
+      }
+    }
+    self.oit_targets.clear();
+    self.materials.clear();
+    self.material_layout = None;
+    self.default_material = None;
+    self.transient_textures.clear();
     // destroy device
     if destroy_renderer {
-      self.msaa.destroy();
-      self.zbuffer.destroy();
+      if let Some(ibl) = &self.ibl {
+        ibl.irradiance.destroy();
+        ibl.prefiltered.destroy();
+        ibl.brdf_lut.destroy();
+      }
       self.device.destroy();
     }
   }
+}
+
+fn view_dimension(dim: u8) -> TextureViewDimension {
+  match dim {
+    RPipelineSetup::TEXTURE_DIM_CUBE => TextureViewDimension::Cube,
+    RPipelineSetup::TEXTURE_DIM_2D_ARRAY => TextureViewDimension::D2Array,
+    RPipelineSetup::TEXTURE_DIM_3D => TextureViewDimension::D3,
+    _ => TextureViewDimension::D2,
+  }
+}
+
+// custom sampler bindings without linear filtering can pair with unfilterable textures
+fn resource_sampler_type(setup: &RSamplerSetup) -> SamplerBindingType {
+  let all_nearest = [setup.mag_filter, setup.min_filter, setup.mipmap_filter].iter()
+    .all(|f| *f == RSamplerSetup::FILTER_NEAREST);
+  match setup.compare {
+    Some(_) => SamplerBindingType::Comparison,
+    None if all_nearest => SamplerBindingType::NonFiltering,
+    None => SamplerBindingType::Filtering,
+  }
+}
+
+// position of a binding within resource groups
+fn find_resource_binding(groups: &[RResourceGroup], bind_group: u32, bind_slot: u32) -> Option<(usize, usize)> {
+  let found = groups.iter().enumerate()
+    .filter(|(_, g)| g.index == bind_group)
+    .find_map(|(gi, g)| g.bindings.iter().position(|b| b.bind_slot == bind_slot).map(|bi| (gi, bi)));
+  if found.is_none() {
+    eprintln!("Err: Pipeline has no custom binding {} in bind group {}", bind_slot, bind_group);
+  }
+  found
+}
+
+// textures are writable from compute shaders when the format allows it without extra features
+fn storage_usage(format: TextureFormat) -> TextureUsages {
+  if format.guaranteed_format_features(Features::empty()).allowed_usages.contains(TextureUsages::STORAGE_BINDING) {
+    TextureUsages::STORAGE_BINDING
+  } else {
+    TextureUsages::empty()
+  }
+}
+
+fn binding_visibility(binding: &RBindingSetup) -> ShaderStages {
+  // vertex stage cannot write to storage buffers or textures
+  if matches!(binding.binding_type, RBindingSetup::BINDING_TYPE_STORAGE_RW | RBindingSetup::BINDING_TYPE_STORAGE_TEXTURE) {
+    return ShaderStages::FRAGMENT;
+  }
+  match binding.visibility {
+    RBindingSetup::VISIBILITY_VERTEX => ShaderStages::VERTEX,
+    RBindingSetup::VISIBILITY_FRAGMENT => ShaderStages::FRAGMENT,
+    _ => ShaderStages::VERTEX_FRAGMENT,
+  }
+}
+
+// samplers reading an unfilterable texture bind as non-filtering
+fn sampler_binding_type(setup: &RSamplerSetup, sample_types: &[TextureSampleType]) -> SamplerBindingType {
+  if setup.compare.is_some() {
+    SamplerBindingType::Comparison
+  } else if sample_types.contains(&TextureSampleType::Float { filterable: false }) {
+    SamplerBindingType::NonFiltering
+  } else {
+    SamplerBindingType::Filtering
+  }
+}
+
+// sample type of a texture1/texture2 binding, read as float unless paired with a comparison sampler
+fn texture_sample_type(setup: &RSamplerSetup, format: Option<TextureFormat>, features: Features) -> TextureSampleType {
+  if setup.compare.is_some() {
+    return TextureSampleType::Depth;
+  }
+  let aspect = format.filter(|f| f.is_depth_stencil_format()).map(|_| TextureAspect::DepthOnly);
+  match format.and_then(|f| f.sample_type(aspect, Some(features))) {
+    // depth read without comparison is an unfilterable float
+    Some(TextureSampleType::Depth) => TextureSampleType::Float { filterable: false },
+    Some(sample_type) => sample_type,
+    None => TextureSampleType::Float { filterable: true },
+  }
+}
+
+fn nearest_sampler(setup: &RSamplerSetup) -> RSamplerSetup {
+  RSamplerSetup {
+    mag_filter: RSamplerSetup::FILTER_NEAREST,
+    min_filter: RSamplerSetup::FILTER_NEAREST,
+    mipmap_filter: RSamplerSetup::FILTER_NEAREST,
+    anisotropy: 1,
+    ..*setup
+  }
+}
+
+// translate sampler setup, dropping options the device cannot support
+fn sampler_descriptor<'a>(label: &'a str, setup: &RSamplerSetup, features: Features) -> SamplerDescriptor<'a> {
+  let has_border = features.contains(Features::ADDRESS_MODE_CLAMP_TO_BORDER);
+  let address_mode = |mode: u8| match mode {
+    RSamplerSetup::ADDRESS_MODE_REPEAT => AddressMode::Repeat,
+    RSamplerSetup::ADDRESS_MODE_MIRROR => AddressMode::MirrorRepeat,
+    RSamplerSetup::ADDRESS_MODE_BORDER if has_border => AddressMode::ClampToBorder,
+    RSamplerSetup::ADDRESS_MODE_BORDER => {
+      eprintln!("Err: Border address mode is not supported by device, using clamp");
+      AddressMode::ClampToEdge
+    }
+    _ => AddressMode::ClampToEdge,
+  };
+  let filter = |mode: u8| match mode {
+    RSamplerSetup::FILTER_NEAREST => FilterMode::Nearest,
+    _ => FilterMode::Linear,
+  };
+  let (mag_filter, min_filter, mipmap_filter) = (filter(setup.mag_filter), filter(setup.min_filter), filter(setup.mipmap_filter));
+  let all_linear = [mag_filter, min_filter, mipmap_filter].iter().all(|f| *f == FilterMode::Linear);
+  let anisotropy_clamp = if setup.anisotropy > 1 && !all_linear {
+    eprintln!("Err: Anisotropic filtering requires linear filters, ignoring anisotropy");
+    1
+  } else {
+    setup.anisotropy.clamp(1, 16)
+  };
+  let address_modes = [setup.address_mode_u, setup.address_mode_v, setup.address_mode_w].map(address_mode);
+  let border_color = if address_modes.contains(&AddressMode::ClampToBorder) {
+    Some(match setup.border_color {
+      RSamplerSetup::BORDER_COLOR_BLACK => SamplerBorderColor::OpaqueBlack,
+      RSamplerSetup::BORDER_COLOR_WHITE => SamplerBorderColor::OpaqueWhite,
+      _ => SamplerBorderColor::TransparentBlack,
+    })
+  } else { None };
+  SamplerDescriptor {
+    label: Some(label),
+    address_mode_u: address_modes[0],
+    address_mode_v: address_modes[1],
+    address_mode_w: address_modes[2],
+    mag_filter,
+    min_filter,
+    mipmap_filter,
+    anisotropy_clamp,
+    border_color,
+    compare: setup.compare,
+    ..Default::default()
+  }
+}
+
+// full mip chain down to 1x1
+fn mip_level_count(width: u32, height: u32) -> u32 {
+  32 - u32::max(1, u32::max(width, height)).leading_zeros()
+}
+
+// feature flags derived from the pipeline setup + renderer, followed by the user defines
+fn pipeline_defines(setup: &RPipelineSetup, reversed_z: bool) -> Vec<String> {
+  let mut defines: Vec<String> = Vec::new();
+  if reversed_z {
+    defines.push("REVERSED_Z".to_owned());
+  }
+  if setup.vertex_type == RPipelineSetup::VERTEX_TYPE_ANIM {
+    defines.push("HAS_SKINNING".to_owned());
+    defines.push(format!("MAX_JOINTS={}", setup.max_joints_count.max(1)));
+  }
+  if setup.texture2_id.is_some() {
+    defines.push("HAS_TEXTURE2".to_owned());
+  }
+  defines.extend(setup.defines.iter().map(|d| d.to_string()));
+  defines
+}
+
+fn clamp_sample_count(requested: u32, color_flags: TextureFormatFeatureFlags, depth_flags: TextureFormatFeatureFlags) -> u32 {
+  let mut count = requested;
+  while count > 1 && !(color_flags.sample_count_supported(count) && depth_flags.sample_count_supported(count)) {
+    count /= 2;
+  }
+  count
+}
+
+// blending is dropped for formats that cannot blend, ie 32 bit float formats
+fn variant_blend(blend: Option<BlendState>, flags: TextureFormatFeatureFlags) -> Option<BlendState> {
+  blend.filter(|_| flags.contains(TextureFormatFeatureFlags::BLENDABLE))
+}
+
+fn reversed_compare(compare: CompareFunction) -> CompareFunction {
+  match compare {
+    CompareFunction::Less => CompareFunction::Greater,
+    CompareFunction::LessEqual => CompareFunction::GreaterEqual,
+    CompareFunction::Greater => CompareFunction::Less,
+    CompareFunction::GreaterEqual => CompareFunction::LessEqual,
+    compare => compare,
+  }
+}
+
+// camera override and target size of a view slot in the mvp buffers
+type RPassView = (Option<RCamera>, u32, u32);
+
+// slot of a view, appending views not seen before in the frame
+fn view_slot(views: &mut Vec<RPassView>, view: RPassView) -> usize {
+  match views.iter().position(|v| *v == view) {
+    Some(slot) => slot,
+    None => {
+      views.push(view);
+      views.len() - 1
+    }
+  }
+}
+
+fn set_pass_rects(pass: &mut RenderPass, rects: &([f32; 4], [u32; 4])) {
+  let ([x, y, w, h], [sx, sy, sw, sh]) = *rects;
+  pass.set_viewport(x, y, w, h, 0.0, 1.0);
+  pass.set_scissor_rect(sx, sy, sw, sh);
+}
+
+// triangles drawn from a number of vertices or indices
+fn triangle_count(topology: PrimitiveTopology, count: u32, instances: u32) -> u64 {
+  let per_instance = match topology {
+    PrimitiveTopology::TriangleList => count / 3,
+    PrimitiveTopology::TriangleStrip => count.saturating_sub(2),
+    _ => 0,
+  };
+  per_instance as u64 * instances as u64
+}
+
+// memory held by a texture across mip levels and samples
+// note: combined depth-stencil formats have no copy size, their size is estimated
+fn texture_bytes(format: TextureFormat, size: Extent3d, mip_levels: u32, samples: u32) -> u64 {
+  let block_size = format.block_copy_size(None).unwrap_or(match format {
+    TextureFormat::Depth32FloatStencil8 => 8,
+    _ => 4,
+  }) as u64;
+  let (bw, bh) = format.block_dimensions();
+  (0..mip_levels).map(|level| {
+    let w = u32::max(1, size.width >> level).div_ceil(bw) as u64;
+    let h = u32::max(1, size.height >> level).div_ceil(bh) as u64;
+    w * h * block_size * size.depth_or_array_layers as u64
+  }).sum::<u64>() * samples as u64
+}
+
+// sort by state keys (ie material, buffer, mesh) to minimize switches, then front to back
+fn batch_order(keys: &[[usize; 3]], distances: &[f32]) -> Vec<usize> {
+  let mut order: Vec<usize> = (0..keys.len()).collect();
+  order.sort_by(|a, b| {
+    keys[*a].cmp(&keys[*b])
+      .then(distances[*a].partial_cmp(&distances[*b]).unwrap_or(std::cmp::Ordering::Equal))
+  });
+  order
+}
+
+// object indices sorted by camera distance, keeping insertion order for equal distances
+fn draw_order(distances: &[f32], back_to_front: bool) -> Vec<usize> {
+  let mut order: Vec<usize> = (0..distances.len()).collect();
+  order.sort_by(|a, b| {
+    let cmp = distances[*a].partial_cmp(&distances[*b]).unwrap_or(std::cmp::Ordering::Equal);
+    if back_to_front { cmp.reverse() } else { cmp }
+  });
+  order
+}
+
+// color blend state for blend mode preset
+fn blend_state(blend_mode: u8, custom: BlendState) -> Option<BlendState> {
+  match blend_mode {
+    RPipelineSetup::BLEND_MODE_OPAQUE => None,
+    RPipelineSetup::BLEND_MODE_PREMULTIPLIED => Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+    RPipelineSetup::BLEND_MODE_ADDITIVE => Some(BlendState {
+      color: BlendComponent {
+        operation: BlendOperation::Add,
+        src_factor: BlendFactor::SrcAlpha,
+        dst_factor: BlendFactor::One,
+      },
+      alpha: BlendComponent {
+        operation: BlendOperation::Add,
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::One,
+      },
+    }),
+    RPipelineSetup::BLEND_MODE_MULTIPLY => Some(BlendState {
+      color: BlendComponent {
+        operation: BlendOperation::Add,
+        src_factor: BlendFactor::Dst,
+        dst_factor: BlendFactor::Zero,
+      },
+      alpha: BlendComponent {
+        operation: BlendOperation::Add,
+        src_factor: BlendFactor::Zero,
+        dst_factor: BlendFactor::One,
+      },
+    }),
+    RPipelineSetup::BLEND_MODE_CUSTOM => Some(custom),
+    _ => Some(BlendState {
+      color: BlendComponent {
+        operation: BlendOperation::Add,
+        src_factor: BlendFactor::SrcAlpha,
+        dst_factor: BlendFactor::OneMinusSrcAlpha
+      },
+      alpha: BlendComponent {
+        operation: BlendOperation::Add,
+        src_factor: BlendFactor::SrcAlpha,
+        dst_factor: BlendFactor::OneMinusSrcAlpha
+      }
+    }),
+  }
+}
+
+// half precision float bits, used for uploading to 16-bit float textures
+fn f32_to_f16(value: f32) -> u16 {
+  let bits = value.to_bits();
+  let sign = ((bits >> 16) & 0x8000) as u16;
+  let exp = ((bits >> 23) & 0xff) as i32;
+  let mantissa = bits & 0x007f_ffff;
+  if exp == 0xff {
+    // inf or nan
+    let nan = if mantissa != 0 { 0x0200 } else { 0 };
+    return sign | 0x7c00 | nan;
+  }
+  let e = exp - 127 + 15;
+  if e >= 0x1f {
+    // overflow to inf
+    return sign | 0x7c00;
+  }
+  if e <= 0 {
+    // subnormal or zero
+    if e < -10 { return sign; }
+    let m = mantissa | 0x0080_0000;
+    let shift = (14 - e) as u32;
+    let half_m = m >> shift;
+    let round = (m >> (shift - 1)) & 1;
+    return sign | (half_m + round) as u16;
+  }
+  let half = sign as u32 | ((e as u32) << 10) | (mantissa >> 13);
+  // round to nearest
+  let round = (mantissa >> 12) & 1;
+  (half + round) as u16
+}
+
+#[cfg(test)]
+mod root_tests {
+  use super::*;
+
+  #[test]
+  fn half_float() {
+    assert_eq!(f32_to_f16(0.0), 0x0000);
+    assert_eq!(f32_to_f16(-0.0), 0x8000);
+    assert_eq!(f32_to_f16(1.0), 0x3c00);
+    assert_eq!(f32_to_f16(-2.0), 0xc000);
+    assert_eq!(f32_to_f16(0.5), 0x3800);
+    assert_eq!(f32_to_f16(65504.0), 0x7bff);
+    assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+    assert_eq!(f32_to_f16(f32::INFINITY), 0x7c00);
+    assert_eq!(f32_to_f16(0.000061035156), 0x0400); // smallest normal
+    assert_eq!(f32_to_f16(0.000000059604645), 0x0001); // smallest subnormal
+  }
+
+  #[test]
+  fn blend_presets() {
+    let custom = BlendState::REPLACE;
+    assert_eq!(blend_state(RPipelineSetup::BLEND_MODE_OPAQUE, custom), None);
+    let alpha = blend_state(RPipelineSetup::BLEND_MODE_ALPHA, custom).unwrap();
+    assert_eq!(alpha.color, BlendState::ALPHA_BLENDING.color);
+    assert_eq!(blend_state(RPipelineSetup::BLEND_MODE_PREMULTIPLIED, custom), Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING));
+    assert_eq!(blend_state(RPipelineSetup::BLEND_MODE_CUSTOM, custom), Some(custom));
+    let additive = blend_state(RPipelineSetup::BLEND_MODE_ADDITIVE, custom).unwrap();
+    assert_eq!(additive.color.dst_factor, BlendFactor::One);
+    let multiply = blend_state(RPipelineSetup::BLEND_MODE_MULTIPLY, custom).unwrap();
+    assert_eq!(multiply.color.src_factor, BlendFactor::Dst);
+  }
+
+  #[test]
+  fn oit_weight_favors_near_fragments() {
+    // mirror of oit_output in embed_assets/lib/oit.wgsl
+    let weight = |alpha: f32, frag_z: f32, reversed_z: bool| {
+      let z = if reversed_z { 1.0 - frag_z } else { frag_z };
+      f32::clamp(alpha * f32::max(0.01, 3000.0 * (1.0 - z).powi(3)), 0.01, 3000.0)
+    };
+    let proj = Mat4::perspective(60.0, 1.0, 1.0, 1000.0);
+    for reversed_z in [false, true] {
+      let proj = if reversed_z { Mat4::multiply(&Mat4::reverse_z(), &proj) } else { proj };
+      let depth = |z: f32| { let c = Mat4::multiply_vec4(&proj, &[0.0, 0.0, z, 1.0]); c[2] / c[3] };
+      assert!(weight(0.5, depth(-2.0), reversed_z) > weight(0.5, depth(-20.0), reversed_z));
+      assert!(weight(0.5, depth(-20.0), reversed_z) >= weight(0.5, depth(-900.0), reversed_z));
+      // the shader only flips depth with the define set
+      let setup = RPipelineSetup::default();
+      let source = preprocess_shader(include_str!("../embed_assets/lib/oit.wgsl"), "oit.wgsl", &pipeline_defines(&setup, reversed_z), None).unwrap();
+      assert_eq!(source.code.contains("1.0 - frag_z"), reversed_z);
+    }
+  }
+
+  #[test]
+  fn view_slots_shared_by_matching_views() {
+    let mut views: Vec<RPassView> = vec![(None, 800, 600)];
+    let left = RCamera::new_persp(60.0, 1.0, 100.0);
+    let right = RCamera { position: [5.0, 0.0, 1.0], ..left };
+    // window sized passes draw from the window view
+    assert_eq!(view_slot(&mut views, (None, 800, 600)), 0);
+    assert_eq!(view_slot(&mut views, (Some(left), 400, 600)), 1);
+    assert_eq!(view_slot(&mut views, (Some(right), 400, 600)), 2);
+    assert_eq!(view_slot(&mut views, (None, 400, 300)), 3);
+    assert_eq!(view_slot(&mut views, (Some(left), 400, 600)), 1);
+    assert_eq!(views.len(), 4);
+  }
+
+  #[test]
+  fn sample_count_clamps_from_request() {
+    let flags = |f: TextureFormat| f.guaranteed_format_features(Features::empty()).flags;
+    let depth = flags(TextureFormat::Depth24Plus);
+    // 32 bit float targets are not guaranteed to multisample, switching back restores the request
+    assert_eq!(clamp_sample_count(4, flags(TextureFormat::Bgra8Unorm), depth), 4);
+    assert_eq!(clamp_sample_count(4, flags(TextureFormat::Rgba32Float), depth), 1);
+    assert_eq!(clamp_sample_count(4, flags(TextureFormat::Bgra8Unorm), depth), 4);
+    assert_eq!(clamp_sample_count(1, flags(TextureFormat::Bgra8Unorm), depth), 1);
+  }
+
+  #[test]
+  fn blend_dropped_for_unblendable_formats() {
+    let blend = Some(BlendState::ALPHA_BLENDING);
+    let flags = |f: TextureFormat| f.guaranteed_format_features(Features::empty()).flags;
+    assert_eq!(variant_blend(blend, flags(TextureFormat::Rgba16Float)), blend);
+    assert_eq!(variant_blend(blend, flags(TextureFormat::Rgba32Float)), None);
+    assert_eq!(variant_blend(None, flags(TextureFormat::Rgba8Unorm)), None);
+  }
+
+  #[test]
+  fn draw_order_by_distance() {
+    let distances = [5.0, 1.0, 3.0, 1.0];
+    assert_eq!(draw_order(&distances, false), vec![1, 3, 2, 0]);
+    assert_eq!(draw_order(&distances, true), vec![0, 2, 1, 3]);
+  }
+
+  #[test]
+  fn batch_order_by_state() {
+    let keys = [[1, 0, 2], [0, 0, 1], [1, 0, 2], [0, 0, 0], [0, 0, 1]];
+    let distances = [1.0, 5.0, 0.5, 9.0, 2.0];
+    assert_eq!(batch_order(&keys, &distances), vec![3, 4, 1, 2, 0]);
+  }
+
+  #[test]
+  fn frame_stat_sizes() {
+    assert_eq!(triangle_count(PrimitiveTopology::TriangleList, 36, 2), 24);
+    assert_eq!(triangle_count(PrimitiveTopology::TriangleStrip, 4, 1), 2);
+    assert_eq!(triangle_count(PrimitiveTopology::LineList, 36, 1), 0);
+    let size = Extent3d { width: 256, height: 128, depth_or_array_layers: 1 };
+    assert_eq!(texture_bytes(TextureFormat::Rgba8Unorm, size, 1, 1), 256 * 128 * 4);
+    assert_eq!(texture_bytes(TextureFormat::Rgba16Float, size, 1, 4), 256 * 128 * 8 * 4);
+    assert_eq!(texture_bytes(TextureFormat::Depth24PlusStencil8, size, 1, 1), 256 * 128 * 4);
+    // full mip chain down to 1x1
+    let square = Extent3d { width: 4, height: 4, depth_or_array_layers: 6 };
+    assert_eq!(texture_bytes(TextureFormat::R8Unorm, square, 3, 1), (16 + 4 + 1) * 6);
+    // compressed formats count 4x4 blocks
+    assert_eq!(texture_bytes(TextureFormat::Bc1RgbaUnorm, Extent3d { width: 6, height: 6, depth_or_array_layers: 1 }, 1, 1), 4 * 8);
+  }
+
+  #[test]
+  fn mip_chain_length() {
+    assert_eq!(mip_level_count(1, 1), 1);
+    assert_eq!(mip_level_count(256, 256), 9);
+    assert_eq!(mip_level_count(300, 20), 9);
+    assert_eq!(mip_level_count(0, 0), 1);
+  }
+
+  #[test]
+  fn sampler_fallbacks() {
+    let pixel_art = RSamplerSetup {
+      address_mode_u: RSamplerSetup::ADDRESS_MODE_REPEAT,
+      address_mode_v: RSamplerSetup::ADDRESS_MODE_MIRROR,
+      mag_filter: RSamplerSetup::FILTER_NEAREST,
+      anisotropy: 8,
+      ..Default::default()
+    };
+    let desc = sampler_descriptor("test", &pixel_art, Features::empty());
+    assert_eq!(desc.address_mode_u, AddressMode::Repeat);
+    assert_eq!(desc.address_mode_v, AddressMode::MirrorRepeat);
+    assert_eq!(desc.mag_filter, FilterMode::Nearest);
+    assert_eq!(desc.anisotropy_clamp, 1);
+
+    let border = RSamplerSetup {
+      address_mode_u: RSamplerSetup::ADDRESS_MODE_BORDER,
+      border_color: RSamplerSetup::BORDER_COLOR_WHITE,
+      anisotropy: 32,
+      ..Default::default()
+    };
+    let desc = sampler_descriptor("test", &border, Features::ADDRESS_MODE_CLAMP_TO_BORDER);
+    assert_eq!(desc.address_mode_u, AddressMode::ClampToBorder);
+    assert_eq!(desc.border_color, Some(SamplerBorderColor::OpaqueWhite));
+    assert_eq!(desc.anisotropy_clamp, 16);
+    let desc = sampler_descriptor("test", &border, Features::empty());
+    assert_eq!(desc.address_mode_u, AddressMode::ClampToEdge);
+    assert_eq!(desc.border_color, None);
+  }
+
+  #[test]
+  fn custom_binding_types() {
+    let nearest = RSamplerSetup { mag_filter: 0, min_filter: 0, mipmap_filter: 0, ..Default::default() };
+    assert_eq!(resource_sampler_type(&nearest), SamplerBindingType::NonFiltering);
+    assert_eq!(resource_sampler_type(&RSamplerSetup::default()), SamplerBindingType::Filtering);
+    let compare = RSamplerSetup { compare: Some(CompareFunction::Less), ..nearest };
+    assert_eq!(resource_sampler_type(&compare), SamplerBindingType::Comparison);
+
+    // texture1/texture2 layouts follow the bound texture format
+    let linear = RSamplerSetup::default();
+    let sample_type = |setup: &RSamplerSetup, format: Option<TextureFormat>| texture_sample_type(setup, format, Features::empty());
+    let unfilterable = TextureSampleType::Float { filterable: false };
+    assert_eq!(sample_type(&linear, None), TextureSampleType::Float { filterable: true });
+    assert_eq!(sample_type(&linear, Some(TextureFormat::Rgba8UnormSrgb)), TextureSampleType::Float { filterable: true });
+    assert_eq!(sample_type(&linear, Some(TextureFormat::Rgba32Float)), unfilterable);
+    assert_eq!(sample_type(&linear, Some(TextureFormat::R32Float)), unfilterable);
+    assert_eq!(sample_type(&linear, Some(TextureFormat::Depth32Float)), unfilterable);
+    assert_eq!(sample_type(&compare, Some(TextureFormat::Depth32Float)), TextureSampleType::Depth);
+    assert_eq!(sampler_binding_type(&linear, &[TextureSampleType::Float { filterable: true }]), SamplerBindingType::Filtering);
+    assert_eq!(sampler_binding_type(&linear, &[TextureSampleType::Float { filterable: true }, unfilterable]), SamplerBindingType::NonFiltering);
+    assert_eq!(sampler_binding_type(&compare, &[TextureSampleType::Depth]), SamplerBindingType::Comparison);
+    assert_eq!(resource_sampler_type(&nearest_sampler(&RSamplerSetup { anisotropy: 16, ..linear })), SamplerBindingType::NonFiltering);
+
+    let storage_rw = RBindingSetup { binding_type: RBindingSetup::BINDING_TYPE_STORAGE_RW, ..Default::default() };
+    assert_eq!(binding_visibility(&storage_rw), ShaderStages::FRAGMENT);
+    let vertex = RBindingSetup { visibility: RBindingSetup::VISIBILITY_VERTEX, ..Default::default() };
+    assert_eq!(binding_visibility(&vertex), ShaderStages::VERTEX);
+  }
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+#![allow(dead_code)]
+use bytemuck::{Pod, Zeroable};
+
+use super::{
+  Renderer,
+  RPipelineId,
+  RObjectId,
+  RPipelineSetup,
+  RBindingSetup,
+  RObjectSetup,
+  RObjectUpdate,
+  Primitives,
+};
+
+const SAMPLE_COUNT: usize = 64;
+
+// uniform layout of stats_overlay.wgsl
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct RStatsGraph {
+  rect: [f32; 4],
+  screen: [f32; 4],
+  info: [u32; 4],
+  samples: [f32; SAMPLE_COUNT],
+}
+
+// graph of recent frame times drawn over the window, fed from Renderer::render_stats
+// note: drawn with its own pipeline, include pipeline_id last when rendering to keep it on top
+#[derive(Debug)]
+pub struct RStatsOverlay {
+  // top left corner + size in pixels
+  pub position: [f32; 2],
+  pub size: [f32; 2],
+  // frame time budget, bars above it turn yellow then red
+  pub target_ms: f32,
+  pub visible: bool,
+  pub pipeline_id: RPipelineId,
+  pub object_id: RObjectId,
+  samples: [f32; SAMPLE_COUNT],
+  head: usize,
+  count: usize,
+}
+impl RStatsOverlay {
+  pub fn new(renderer: &mut Renderer) -> Self {
+    let pipeline_id = renderer.add_pipeline(RPipelineSetup {
+      shader: include_str!("../embed_assets/stats_overlay.wgsl"),
+      max_obj_count: 1,
+      bindings: vec![RBindingSetup {
+        bind_group: 1,
+        bind_slot: 0,
+        visibility: RBindingSetup::VISIBILITY_BOTH,
+        binding_type: RBindingSetup::BINDING_TYPE_UNIFORM,
+        size_in_bytes: std::mem::size_of::<RStatsGraph>() as u64,
+        ..Default::default()
+      }],
+      alpha_to_coverage: false,
+      depth_test: false,
+      depth_write: false,
+      frustum_cull: false,
+      ..Default::default()
+    });
+    let (vertex_data, indices) = Primitives::rect_indexed(1.0, 1.0, 0.0);
+    let object_id = renderer.add_object(RObjectSetup {
+      pipeline_id,
+      vertex_data,
+      indices,
+      ..Default::default()
+    });
+    Self {
+      position: [10.0, 10.0],
+      size: [240.0, 80.0],
+      target_ms: 1000.0 / 60.0,
+      visible: true,
+      pipeline_id,
+      object_id,
+      samples: [0.0; SAMPLE_COUNT],
+      head: 0,
+      count: 0,
+    }
+  }
+
+  // record a frame time, dropping the oldest once full
+  pub fn push_sample(&mut self, frame_time_ms: f32) {
+    self.samples[(self.head + self.count) % SAMPLE_COUNT] = frame_time_ms;
+    if self.count < SAMPLE_COUNT {
+      self.count += 1;
+    } else {
+      self.head = (self.head + 1) % SAMPLE_COUNT;
+    }
+  }
+
+  // recorded frame times, oldest first
+  pub fn samples(&self) -> Vec<f32> {
+    (0..self.count).map(|i| self.samples[(self.head + i) % SAMPLE_COUNT]).collect()
+  }
+
+  // graph scale, keeps the target line in view
+  pub fn max_ms(&self) -> f32 {
+    self.samples().into_iter().fold(self.target_ms * 2.0, f32::max)
+  }
+
+  // call once per frame, after the previous frame was presented
+  pub fn update(&mut self, renderer: &mut Renderer) {
+    let frame_time = renderer.render_stats().frame_time_ms;
+    if frame_time > 0.0 {
+      self.push_sample(frame_time);
+    }
+    let graph = RStatsGraph {
+      rect: [self.position[0], self.position[1], self.size[0], self.size[1]],
+      screen: [renderer.config.width as f32, renderer.config.height as f32, self.target_ms, self.max_ms()],
+      info: [self.count as u32, self.head as u32, 0, 0],
+      samples: self.samples,
+    };
+    renderer.update_binding_buffer(self.pipeline_id, 1, 0, 0, bytemuck::bytes_of(&graph));
+    renderer.update_object(RObjectUpdate {
+      object_id: self.object_id,
+      visible: self.visible,
+      ..Default::default()
+    });
+  }
+}
+
+#[cfg(test)]
+mod stats_overlay_tests {
+  use super::*;
+
+  #[test]
+  fn samples_wrap_oldest_first() {
+    let mut overlay = RStatsOverlay {
+      position: [0.0, 0.0],
+      size: [100.0, 50.0],
+      target_ms: 10.0,
+      visible: true,
+      pipeline_id: RPipelineId(0),
+      object_id: RObjectId(0, 0),
+      samples: [0.0; SAMPLE_COUNT],
+      head: 0,
+      count: 0,
+    };
+    overlay.push_sample(5.0);
+    overlay.push_sample(30.0);
+    assert_eq!(overlay.samples(), vec![5.0, 30.0]);
+    assert_eq!(overlay.max_ms(), 30.0);
+    for i in 0..SAMPLE_COUNT {
+      overlay.push_sample(i as f32);
+    }
+    let samples = overlay.samples();
+    assert_eq!(samples.len(), SAMPLE_COUNT);
+    assert_eq!(samples[0], 0.0);
+    assert_eq!(samples[SAMPLE_COUNT - 1], (SAMPLE_COUNT - 1) as f32);
+    assert_eq!(std::mem::size_of::<RStatsGraph>(), 304);
+  }
+}
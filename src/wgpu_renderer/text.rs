@@ -2,7 +2,9 @@
 
 use ab_glyph::{Font, FontRef, Glyph, Rect};
 use image::{RgbaImage, Rgba};
-use wgpu::{Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, Texture, TextureAspect, TextureFormat};
+use wgpu::{Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Texture, TextureAspect, TextureFormat};
+
+use super::RQueue;
 
 #[derive(Debug, PartialEq)]
 pub enum TextError {
@@ -13,7 +15,7 @@ pub enum TextError {
 }
 
 pub struct RStringInputs<'a> {
-  pub queue: &'a Queue,
+  pub queue: &'a RQueue,
   pub texture: &'a mut Texture,
   pub font_data: &'a Vec<u8>,
   pub string: &'a str,
@@ -88,7 +90,7 @@ pub fn load_cached_glyph(font_raw: &Vec<u8>, c: char, size: f32, color: [u8; 3])
 }
 
 // draw glyph on texture
-pub fn draw_glyph_on_texture(queue: &Queue, texture: &mut Texture, glyph: RgbaImage, position: [u32; 2]) -> Result<(), TextError> {
+pub fn draw_glyph_on_texture(queue: &RQueue, texture: &mut Texture, glyph: RgbaImage, position: [u32; 2]) -> Result<(), TextError> {
   // define glyph data
   let dimensions = glyph.dimensions();
   let glyph_size = Extent3d { 
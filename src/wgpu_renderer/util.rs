@@ -1,5 +1,6 @@
 #![allow(dead_code)]
-use super::{Renderer, RTextureId, RPipelineId, RObjectId, RVertex, RVertexAnim};
+use bytemuck::{Pod, Zeroable};
+use super::{Renderer, RTextureId, RPipelineId, RObjectId, RMaterialId, RMeshId, RVertex, RVertexAnim, RComputeBuffer, RBounds, RRay, Mat4, Vec3, PI};
 
 // helper for defining object transform data
 pub struct Shape {
@@ -11,6 +12,7 @@ pub struct Shape {
   pub visible: bool,
   pub v_index: Option<Vec<f32>>,
   pub anim_transforms: Vec<[f32; 16]>,
+  pub bounds: Option<RBounds>,
 }
 impl Shape {
   pub fn new(renderer: &mut Renderer, pipeline_id: RPipelineId, vertex_data: Vec<RVertex>, index_data: Option<Vec<u32>>) -> Self {
@@ -23,16 +25,7 @@ impl Shape {
       setup.indices = indices;
     }
     let id = renderer.add_object(setup);
-    Self {
-      id,
-      position: [0.0, 0.0, 0.0],
-      rotate_axis: [0.0, 0.0, 1.0],
-      rotate_deg: 0.0,
-      scale: [1.0, 1.0, 1.0],
-      visible: true,
-      v_index: None,
-      anim_transforms: Vec::new(),
-    }
+    Shape::from_object(renderer, id)
   }
   pub fn new_anim(renderer: &mut Renderer, pipeline_id: RPipelineId, vertex_data: Vec<RVertexAnim>, index_data: Option<Vec<u32>>) -> Self {
     let mut setup = RObjectSetup {
@@ -45,6 +38,18 @@ impl Shape {
       setup.indices = indices;
     }
     let id = renderer.add_object(setup);
+    Shape::from_object(renderer, id)
+  }
+  // shares the vertex + index buffers of an existing mesh
+  pub fn new_from_mesh(renderer: &mut Renderer, pipeline_id: RPipelineId, mesh: RMeshId) -> Self {
+    let id = renderer.add_object(RObjectSetup {
+      pipeline_id,
+      mesh: Some(mesh),
+      ..Default::default()
+    });
+    Shape::from_object(renderer, id)
+  }
+  fn from_object(renderer: &Renderer, id: RObjectId) -> Self {
     Self {
       id,
       position: [0.0, 0.0, 0.0],
@@ -54,12 +59,19 @@ impl Shape {
       visible: true,
       v_index: None,
       anim_transforms: Vec::new(),
+      bounds: renderer.object_bounds(id),
     }
   }
 }
 
 // helper for defining camera/view matrix
-#[derive(Debug)]
+// - extents: (left, right, bottom, top) of the view in world units for ortho cameras, or of the
+//   near plane for perspective cameras (off-center frustum, fov_y is ignored)
+// - zoom: scales the visible area around its center, 2.0 shows half as much
+// - clip_plane: world space plane (a, b, c, d) replacing the near plane, ie for portals or mirrors.
+//   the camera has to be on its negative side
+// note: a perspective far of f32::INFINITY leaves out the far plane
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RCamera {
   pub cam_type: u8,
   pub position: [f32; 3],
@@ -68,6 +80,9 @@ pub struct RCamera {
   pub fov_y: f32,
   pub near: f32,
   pub far: f32,
+  pub extents: Option<[f32; 4]>,
+  pub zoom: f32,
+  pub clip_plane: Option<[f32; 4]>,
 }
 impl RCamera {
   pub const ORTHOGRAPHIC: u8 = 1;
@@ -81,6 +96,9 @@ impl RCamera {
       fov_y: 0.0,
       near,
       far,
+      extents: None,
+      zoom: 1.0,
+      clip_plane: None,
     }
   }
   pub fn new_persp(fov_y: f32, near: f32, far: f32) -> Self {
@@ -92,6 +110,134 @@ impl RCamera {
       fov_y,
       near,
       far,
+      extents: None,
+      zoom: 1.0,
+      clip_plane: None,
+    }
+  }
+  // perspective camera with an off-center frustum, from the bounds of the near plane
+  pub fn new_frustum(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+    Self {
+      extents: Some([left, right, bottom, top]),
+      ..RCamera::new_persp(0.0, near, far)
+    }
+  }
+  pub fn view_matrix(&self) -> [f32; 16] {
+    let view_t = Mat4::translate(-self.position[0], -self.position[1], -self.position[2]);
+    let view_r = Mat4::view_rot(&self.position, &self.look_at, &self.up);
+    Mat4::multiply(&view_r, &view_t)
+  }
+  // ortho cameras without extents span the target size in pixels, centered on the view axis
+  pub fn proj_matrix(&self, width: u32, height: u32) -> [f32; 16] {
+    let w2 = (width / 2) as f32;
+    let h2 = (height / 2) as f32;
+    let mut proj = match (self.cam_type, self.extents) {
+      (RCamera::ORTHOGRAPHIC, Some([l, r, b, t])) => Mat4::ortho(l, r, t, b, self.near, self.far),
+      (RCamera::ORTHOGRAPHIC, None) => Mat4::ortho(-w2, w2, h2, -h2, self.near, self.far),
+      (RCamera::PERSPECTIVE, Some([l, r, b, t])) => Mat4::frustum(l, r, b, t, self.near, self.far),
+      (RCamera::PERSPECTIVE, None) => Mat4::perspective(self.fov_y, w2/h2, self.near, self.far),
+      _ => return Mat4::identity()
+    };
+    // zoom around the center of the view, scaling clip space x + y
+    for i in 0..4 {
+      proj[i * 4] *= self.zoom;
+      proj[i * 4 + 1] *= self.zoom;
+    }
+    match self.clip_plane {
+      Some(plane) => {
+        // planes transform by the inverse transpose, ie transpose of the inverse view
+        let inv_view_t = Mat4::transpose(&Mat4::inverse(&self.view_matrix()));
+        Mat4::oblique(&proj, &Mat4::multiply_vec4(&inv_view_t, &plane))
+      }
+      None => proj,
+    }
+  }
+  // fraction of the target height covered by a world space sphere
+  pub fn screen_size(&self, center: &[f32; 3], radius: f32, height: u32) -> f32 {
+    // vertical scale of the projection does not depend on the target width
+    let y_scale = f32::abs(self.proj_matrix(height, height)[5]);
+    match self.cam_type {
+      RCamera::ORTHOGRAPHIC => radius * y_scale,
+      RCamera::PERSPECTIVE => {
+        let distance = Vec3::magnitude(&Vec3::subtract(center, &self.position));
+        if distance <= radius { return f32::MAX; }
+        radius * y_scale / distance
+      }
+      _ => radius
+    }
+  }
+  // ray through a pixel (from the top left) of a width x height target, ie the cursor position
+  pub fn screen_to_ray(&self, x: f32, y: f32, width: u32, height: u32) -> RRay {
+    let inv = Mat4::inverse(&Mat4::multiply(&self.proj_matrix(width, height), &self.view_matrix()));
+    let ndc_x = 2.0 * x / width as f32 - 1.0;
+    let ndc_y = 1.0 - 2.0 * y / height as f32;
+    let unproject = |z: f32| {
+      let p = Mat4::multiply_vec4(&inv, &[ndc_x, ndc_y, z, 1.0]);
+      [p[0] / p[3], p[1] / p[3], p[2] / p[3]]
+    };
+    // depth 1 is at infinity without a far plane
+    let near = unproject(0.0);
+    RRay::new(near, Vec3::subtract(&unproject(0.5), &near))
+  }
+  // pixel position (from the top left) + depth of a world point, None behind the camera
+  pub fn world_to_screen(&self, point: &[f32; 3], width: u32, height: u32) -> Option<[f32; 3]> {
+    let view_proj = Mat4::multiply(&self.proj_matrix(width, height), &self.view_matrix());
+    let clip = Mat4::multiply_vec4(&view_proj, &[point[0], point[1], point[2], 1.0]);
+    if clip[3] <= 0.0 { return None; }
+    let ndc = [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]];
+    Some([(ndc[0] + 1.0) * 0.5 * width as f32, (1.0 - ndc[1]) * 0.5 * height as f32, ndc[2]])
+  }
+}
+
+// counters of the last presented frame, see Renderer::render_stats
+// note: switches count the calls made after skipping state already set by the previous draw
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+  pub objects_drawn: u32,
+  pub objects_culled: u32,
+  pub draw_calls: u32,
+  pub pipeline_switches: u32,
+  pub buffer_switches: u32,
+  pub material_switches: u32,
+  pub triangles: u64,
+  // bytes written through the queue into buffers + textures
+  pub upload_bytes: u64,
+  // textures alive at present, including msaa/depth attachments but not the surface
+  pub textures: u32,
+  pub texture_memory: u64,
+  // time since the previous present
+  pub frame_time_ms: f32,
+  // sum of gpu_pass_timings, only with gpu profiling enabled and lagging a few frames behind
+  pub gpu_time_ms: f32,
+}
+impl RenderStats {
+  // note: adds up the per pass counters, frame wide values are kept
+  pub fn merge(&mut self, other: &RenderStats) {
+    self.objects_drawn += other.objects_drawn;
+    self.objects_culled += other.objects_culled;
+    self.draw_calls += other.draw_calls;
+    self.pipeline_switches += other.pipeline_switches;
+    self.buffer_switches += other.buffer_switches;
+    self.material_switches += other.material_switches;
+    self.triangles += other.triangles;
+  }
+}
+
+// helper for configuring renderer on creation
+// note: sample count of 1 disables msaa, unsupported counts fall back to the next lower one.
+// reversed_z swaps Depth24Plus formats for their float versions, see Renderer::set_reversed_z
+#[derive(Debug, Clone, Copy)]
+pub struct RendererConfig {
+  pub sample_count: u32,
+  pub depth_format: wgpu::TextureFormat,
+  pub reversed_z: bool,
+}
+impl Default for RendererConfig {
+  fn default() -> Self {
+    RendererConfig {
+      sample_count: 4,
+      depth_format: wgpu::TextureFormat::Depth24Plus,
+      reversed_z: false,
     }
   }
 }
@@ -108,34 +254,128 @@ impl RUniformSetup {
   pub const VISIBILITY_FRAGMENT: u8 = 2;
   pub const VISIBILITY_BOTH: u8 = 0;
 }
+// resources shared by all objects of a pipeline, bound at any group from 1 to 3
+// note: group 1 is taken by custom uniforms, group 2 by materials and group 3 by pbr shading.
+// read-write storage buffers are only visible to the fragment stage
+#[derive(Debug, Clone, Copy)]
+pub struct RBindingSetup {
+  pub bind_group: u32,
+  pub bind_slot: u32,
+  pub visibility: u8,
+  pub binding_type: u8,
+  pub texture_id: Option<RTextureId>,
+  pub texture_dimension: u8,
+  pub sampler: RSamplerSetup,
+  pub size_in_bytes: u64,
+}
+impl Default for RBindingSetup {
+  fn default() -> Self {
+    RBindingSetup {
+      bind_group: 1,
+      bind_slot: 0,
+      visibility: RBindingSetup::VISIBILITY_BOTH,
+      binding_type: RBindingSetup::BINDING_TYPE_TEXTURE,
+      texture_id: None,
+      texture_dimension: RPipelineSetup::TEXTURE_DIM_2D,
+      sampler: RSamplerSetup::default(),
+      size_in_bytes: 0,
+    }
+  }
+}
+impl RBindingSetup {
+  pub const VISIBILITY_VERTEX: u8 = 1;
+  pub const VISIBILITY_FRAGMENT: u8 = 2;
+  pub const VISIBILITY_BOTH: u8 = 0;
+  // binding type constants
+  pub const BINDING_TYPE_TEXTURE: u8 = 0;
+  pub const BINDING_TYPE_SAMPLER: u8 = 1;
+  pub const BINDING_TYPE_UNIFORM: u8 = 2;
+  pub const BINDING_TYPE_STORAGE: u8 = 3;
+  pub const BINDING_TYPE_STORAGE_RW: u8 = 4;
+  pub const BINDING_TYPE_STORAGE_TEXTURE: u8 = 5;
+}
+// note: objects of transparent pipelines are drawn after opaque ones, sorted back to front.
+// with order independent transparency enabled, transparent pipelines providing an
+// oit_fragment_fn are accumulated instead (see fragmentOit in embed_assets/base.wgsl)
+// both textures share the sampler at @binding(1), unless texture2_sampler adds one at @binding(5)
+// instance_attributes are read per instance from the object instance_buffer, at the shader
+// locations following the vertex attributes (3 for static, 5 for animated vertices)
+// shaders are preprocessed with defines ("NAME" or "NAME=VALUE") + includes from include_dir or
+// the built-in snippet library, HAS_SKINNING, MAX_JOINTS and HAS_TEXTURE2 are defined automatically
+// frustum_cull skips objects outside the camera view, disable for shaders not placing vertices
+// through the mvp matrices (objects with instances or shared vertex buffers are never culled)
 #[derive(Debug)]
 pub struct RPipelineSetup<'a> {
   pub shader: &'a str,
+  pub defines: Vec<&'a str>,
+  pub include_dir: Option<&'a std::path::Path>,
   pub max_obj_count: usize,
   pub texture1_id: Option<RTextureId>,
   pub texture2_id: Option<RTextureId>,
+  pub texture1_dimension: u8,
+  pub texture2_dimension: u8,
+  pub sampler: RSamplerSetup,
+  pub texture2_sampler: Option<RSamplerSetup>,
   pub cull_mode: u8,
   pub poly_mode: u8,
   pub vertex_fn: &'a str,
   pub fragment_fn: &'a str,
   pub uniforms: Vec<RUniformSetup>,
+  pub bindings: Vec<RBindingSetup>,
   pub vertex_type: u8,
   pub max_joints_count: u32,
+  pub instance_attributes: Vec<wgpu::VertexFormat>,
+  pub alpha_to_coverage: bool,
+  pub blend_mode: u8,
+  pub custom_blend: wgpu::BlendState,
+  pub depth_test: bool,
+  pub depth_write: bool,
+  pub depth_compare: wgpu::CompareFunction,
+  pub depth_bias: wgpu::DepthBiasState,
+  pub stencil: wgpu::StencilState,
+  pub stencil_reference: u32,
+  pub transparent: bool,
+  pub oit_fragment_fn: Option<&'a str>,
+  pub use_materials: bool,
+  pub shading: u8,
+  pub frustum_cull: bool,
 }
 impl Default for RPipelineSetup<'_> {
   fn default() -> Self {
       RPipelineSetup {
         shader: include_str!("../embed_assets/base.wgsl"),
+        defines: Vec::new(),
+        include_dir: None,
         max_obj_count: 10,
         texture1_id: None,
         texture2_id: None,
+        texture1_dimension: RPipelineSetup::TEXTURE_DIM_2D,
+        texture2_dimension: RPipelineSetup::TEXTURE_DIM_2D,
+        sampler: RSamplerSetup::default(),
+        texture2_sampler: None,
         cull_mode: RPipelineSetup::CULL_MODE_NONE,
         poly_mode: RPipelineSetup::POLY_MODE_TRI,
         vertex_fn: "vertexMain",
         fragment_fn: "fragmentMain",
         uniforms: Vec::new(),
+        bindings: Vec::new(),
         vertex_type: RPipelineSetup::VERTEX_TYPE_STATIC,
         max_joints_count: 0,
+        instance_attributes: Vec::new(),
+        alpha_to_coverage: true,
+        blend_mode: RPipelineSetup::BLEND_MODE_ALPHA,
+        custom_blend: wgpu::BlendState::ALPHA_BLENDING,
+        depth_test: true,
+        depth_write: true,
+        depth_compare: wgpu::CompareFunction::LessEqual,
+        depth_bias: wgpu::DepthBiasState::default(),
+        stencil: wgpu::StencilState::default(),
+        stencil_reference: 0,
+        transparent: false,
+        oit_fragment_fn: None,
+        use_materials: false,
+        shading: RPipelineSetup::SHADING_CUSTOM,
+        frustum_cull: true,
       }
   }
 }
@@ -144,6 +384,11 @@ impl RPipelineSetup<'_> {
   pub const CULL_MODE_NONE: u8 = 0;
   pub const CULL_MODE_BACK: u8 = 1;
   pub const CULL_MODE_FRONT: u8 = 2;
+  // texture dimension constants
+  pub const TEXTURE_DIM_2D: u8 = 0;
+  pub const TEXTURE_DIM_CUBE: u8 = 1;
+  pub const TEXTURE_DIM_2D_ARRAY: u8 = 2;
+  pub const TEXTURE_DIM_3D: u8 = 3;
   // vertex type constants
   pub const VERTEX_TYPE_STATIC: u8 = 0;
   pub const VERTEX_TYPE_ANIM: u8 = 1;
@@ -151,9 +396,49 @@ impl RPipelineSetup<'_> {
   pub const POLY_MODE_TRI: u8 = 0;
   pub const POLY_MODE_LINE: u8 = 1;
   pub const POLY_MODE_POINT: u8 = 2;
+  // blend mode constants
+  // note: custom mode uses the components from custom_blend
+  pub const BLEND_MODE_OPAQUE: u8 = 0;
+  pub const BLEND_MODE_ALPHA: u8 = 1;
+  pub const BLEND_MODE_PREMULTIPLIED: u8 = 2;
+  pub const BLEND_MODE_ADDITIVE: u8 = 3;
+  pub const BLEND_MODE_MULTIPLY: u8 = 4;
+  pub const BLEND_MODE_CUSTOM: u8 = 5;
+  // shading constants
+  // note: pbr shading replaces shader and entry points with embed_assets/pbr.wgsl,
+  // using materials with RPbrParams and texture slots base color, metallic-roughness,
+  // normal, occlusion and emissive
+  pub const SHADING_CUSTOM: u8 = 0;
+  pub const SHADING_PBR: u8 = 1;
+}
+
+// helper for building compute pipeline
+// note: bindings may use any bind group from 0 to 3, storage buffers are readable as
+// vertex or instance data of render objects (see Renderer::compute_buffer)
+#[derive(Debug)]
+pub struct RComputeSetup<'a> {
+  pub shader: &'a str,
+  pub entry_fn: &'a str,
+  pub bindings: Vec<RBindingSetup>,
+  pub defines: Vec<&'a str>,
+  pub include_dir: Option<&'a std::path::Path>,
+}
+impl Default for RComputeSetup<'_> {
+  fn default() -> Self {
+    RComputeSetup {
+      shader: "",
+      entry_fn: "computeMain",
+      bindings: Vec::new(),
+      defines: Vec::new(),
+      include_dir: None,
+    }
+  }
 }
 
 // helper for building new render object
+// note: vertex_buffer replaces the vertex data with a compute storage buffer, packed as
+// RVertex (or RVertexAnim) values, ie array<f32> with 8 floats per vertex in wgsl.
+// mesh reuses geometry added with Renderer::add_mesh instead of uploading vertex_data + indices
 #[derive(Debug)]
 pub struct RObjectSetup {
   pub pipeline_id: RPipelineId,
@@ -162,6 +447,13 @@ pub struct RObjectSetup {
   pub indices: Vec<u32>,
   pub vertex_type: u8,
   pub anim_vertex_data: Vec<RVertexAnim>,
+  pub material: Option<RMaterialId>,
+  pub vertex_buffer: Option<RComputeBuffer>,
+  pub instance_buffer: Option<RComputeBuffer>,
+  pub mesh: Option<RMeshId>,
+  pub lods: Vec<RLodSetup>,
+  pub lod_mode: u8,
+  pub lod_hysteresis: f32,
 }
 impl Default for RObjectSetup {
   fn default() -> Self {
@@ -172,12 +464,168 @@ impl Default for RObjectSetup {
       instances: 1,
       anim_vertex_data: Vec::new(),
       vertex_type: RObjectSetup::VERTEX_TYPE_STATIC,
+      material: None,
+      vertex_buffer: None,
+      instance_buffer: None,
+      mesh: None,
+      lods: Vec::new(),
+      lod_mode: RObjectSetup::LOD_MODE_SCREEN_SIZE,
+      lod_hysteresis: 0.1,
     }
   }
 }
 impl RObjectSetup {
   pub const VERTEX_TYPE_STATIC: u8 = 0;
   pub const VERTEX_TYPE_ANIM: u8 = 1;
+  // lod mode constants
+  pub const LOD_MODE_SCREEN_SIZE: u8 = 0;
+  pub const LOD_MODE_DISTANCE: u8 = 1;
+}
+
+// lower detail mesh of an object, ordered from most to least detailed after the object mesh
+// - screen size mode: used once the bounding sphere covers less than threshold of the target height
+// - distance mode: used from threshold camera distance
+// note: lods can be generated with simplify_mesh, only static vertices are supported.
+// mesh reuses existing geometry, like RObjectSetup
+#[derive(Debug, Clone)]
+pub struct RLodSetup {
+  pub vertex_data: Vec<RVertex>,
+  pub indices: Vec<u32>,
+  pub mesh: Option<RMeshId>,
+  pub threshold: f32,
+}
+impl Default for RLodSetup {
+  fn default() -> Self {
+    RLodSetup {
+      vertex_data: Vec::new(),
+      indices: Vec::new(),
+      mesh: None,
+      threshold: 0.0,
+    }
+  }
+}
+
+// helper for building material
+// note: materials are bound at group 2 of pipelines built with use_materials, with params
+// at binding 0, sampler at binding 1 and textures from binding 2 (see embed_assets/material.wgsl).
+// unused texture slots are filled with a white texture
+#[derive(Debug)]
+pub struct RMaterialSetup<'a> {
+  pub textures: Vec<RTextureId>,
+  pub params: &'a [u8],
+  pub sampler: RSamplerSetup,
+}
+impl Default for RMaterialSetup<'_> {
+  fn default() -> Self {
+    RMaterialSetup {
+      textures: Vec::new(),
+      params: bytemuck::cast_slice(&[1.0f32, 1.0, 1.0, 1.0]),
+      sampler: RSamplerSetup {
+        address_mode_u: RSamplerSetup::ADDRESS_MODE_REPEAT,
+        address_mode_v: RSamplerSetup::ADDRESS_MODE_REPEAT,
+        address_mode_w: RSamplerSetup::ADDRESS_MODE_REPEAT,
+        ..Default::default()
+      },
+    }
+  }
+}
+
+// note: anisotropy only applies when all filters are linear,
+// comparison samplers pair with depth textures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RSamplerSetup {
+  pub address_mode_u: u8,
+  pub address_mode_v: u8,
+  pub address_mode_w: u8,
+  pub mag_filter: u8,
+  pub min_filter: u8,
+  pub mipmap_filter: u8,
+  pub anisotropy: u16,
+  pub border_color: u8,
+  pub compare: Option<wgpu::CompareFunction>,
+}
+impl Default for RSamplerSetup {
+  fn default() -> Self {
+    RSamplerSetup {
+      address_mode_u: RSamplerSetup::ADDRESS_MODE_CLAMP,
+      address_mode_v: RSamplerSetup::ADDRESS_MODE_CLAMP,
+      address_mode_w: RSamplerSetup::ADDRESS_MODE_CLAMP,
+      mag_filter: RSamplerSetup::FILTER_LINEAR,
+      min_filter: RSamplerSetup::FILTER_LINEAR,
+      mipmap_filter: RSamplerSetup::FILTER_LINEAR,
+      anisotropy: 1,
+      border_color: RSamplerSetup::BORDER_COLOR_TRANSPARENT,
+      compare: None,
+    }
+  }
+}
+impl RSamplerSetup {
+  // address mode constants
+  // note: border mode requires device support, falls back to clamp
+  pub const ADDRESS_MODE_CLAMP: u8 = 0;
+  pub const ADDRESS_MODE_REPEAT: u8 = 1;
+  pub const ADDRESS_MODE_MIRROR: u8 = 2;
+  pub const ADDRESS_MODE_BORDER: u8 = 3;
+  // filter constants
+  pub const FILTER_NEAREST: u8 = 0;
+  pub const FILTER_LINEAR: u8 = 1;
+  // border color constants
+  pub const BORDER_COLOR_TRANSPARENT: u8 = 0;
+  pub const BORDER_COLOR_BLACK: u8 = 1;
+  pub const BORDER_COLOR_WHITE: u8 = 2;
+}
+impl RMaterialSetup<'_> {
+  pub const TEXTURE_SLOTS: usize = 5;
+}
+
+// material params for pbr shading
+// note: metallic-roughness maps follow gltf, with roughness in g and metallic in b.
+// normal_scale of 0 disables normal mapping
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct RPbrParams {
+  pub base_color: [f32; 4],
+  pub emissive: [f32; 4],
+  pub metallic: f32,
+  pub roughness: f32,
+  pub normal_scale: f32,
+  pub occlusion_strength: f32,
+}
+impl Default for RPbrParams {
+  fn default() -> Self {
+    RPbrParams {
+      base_color: [1.0, 1.0, 1.0, 1.0],
+      emissive: [0.0, 0.0, 0.0, 0.0],
+      metallic: 0.0,
+      roughness: 0.5,
+      normal_scale: 0.0,
+      occlusion_strength: 1.0,
+    }
+  }
+}
+impl RPbrParams {
+  pub fn as_bytes(&self) -> &[u8] {
+    bytemuck::bytes_of(self)
+  }
+}
+
+// helper for pbr scene lighting
+#[derive(Debug, Clone, Copy)]
+pub struct RPbrLighting {
+  pub light_dir: [f32; 3],
+  pub light_color: [f32; 3],
+  pub light_intensity: f32,
+  pub ibl_intensity: f32,
+}
+impl Default for RPbrLighting {
+  fn default() -> Self {
+    RPbrLighting {
+      light_dir: [-0.5, -1.0, -0.3],
+      light_color: [1.0, 1.0, 1.0],
+      light_intensity: 3.0,
+      ibl_intensity: 1.0,
+    }
+  }
 }
 
 // helper for updating render object
@@ -235,3 +683,51 @@ impl<'a> RObjectUpdate<'a> {
     self
   }
 }
+
+// helper for building post processing effect
+// note: params are passed to the effect shader as a vec4f uniform at group 1 binding 0,
+// custom shaders read the previous output from texture1 (see embed_assets/post.wgsl)
+#[derive(Debug)]
+pub struct RPostEffectSetup<'a> {
+  pub effect: u8,
+  pub params: [f32; 4],
+  pub lut_texture_id: Option<RTextureId>,
+  pub shader: Option<&'a str>,
+  pub fragment_fn: &'a str,
+}
+impl Default for RPostEffectSetup<'_> {
+  fn default() -> Self {
+    RPostEffectSetup::new(RPostEffectSetup::EFFECT_TONEMAP_ACES)
+  }
+}
+impl RPostEffectSetup<'_> {
+  // effect constants
+  pub const EFFECT_BLOOM: u8 = 0;
+  pub const EFFECT_TONEMAP_ACES: u8 = 1;
+  pub const EFFECT_TONEMAP_REINHARD: u8 = 2;
+  pub const EFFECT_FXAA: u8 = 3;
+  pub const EFFECT_COLOR_GRADE: u8 = 4;
+  pub const EFFECT_VIGNETTE: u8 = 5;
+  pub const EFFECT_GAMMA: u8 = 6;
+  pub const EFFECT_CUSTOM: u8 = 7;
+  // setup with default params for effect
+  pub fn new(effect: u8) -> Self {
+    let params = match effect {
+      RPostEffectSetup::EFFECT_BLOOM => [1.0, 0.6, 1.5, 0.0], // threshold, intensity, spread
+      RPostEffectSetup::EFFECT_TONEMAP_ACES => [1.0, 0.0, 0.0, 0.0], // exposure
+      RPostEffectSetup::EFFECT_TONEMAP_REINHARD => [1.0, 0.0, 0.0, 0.0], // exposure
+      RPostEffectSetup::EFFECT_FXAA => [8.0, 0.125, 0.0078125, 0.0], // span max, reduce mul, reduce min
+      RPostEffectSetup::EFFECT_COLOR_GRADE => [16.0, 1.0, 0.0, 0.0], // lut size, strength
+      RPostEffectSetup::EFFECT_VIGNETTE => [0.5, 0.75, 0.4, 0.0], // intensity, radius, smoothness
+      RPostEffectSetup::EFFECT_GAMMA => [2.2, 0.0, 0.0, 0.0], // gamma
+      _ => [0.0, 0.0, 0.0, 0.0],
+    };
+    RPostEffectSetup {
+      effect,
+      params,
+      lut_texture_id: None,
+      shader: None,
+      fragment_fn: "fragmentMain",
+    }
+  }
+}